@@ -0,0 +1,312 @@
+// 缓存准入策略
+// 实时计算出的结果是否写入缓存由准入策略决定（预计算和后台任务的结果总是写入）。
+// - always：全部写入
+// - min_cost：计算耗时不少于 min_ms 才写入（原先固定为 100 ms）
+// - frequency：TinyLFU 式的频率过滤。第一次出现的数只记入门卫（布隆过滤器），再次出现时计入
+//   count-min 草图；估计的请求次数达到 min_requests 才写入。每记录 sample_size 次访问，
+//   草图计数减半、门卫清空，使频率随时间衰减。设置了 min_cost_ms 时，计算耗时不少于它的
+//   结果第一次出现就写入，昂贵的结果不必再算一遍
+// - number_size：数的位数不少于 min_bits 才写入
+
+use serde::{Deserialize, Serialize};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// 草图的行数
+const SKETCH_DEPTH: usize = 4;
+/// 草图计数的上限（4 位计数器）
+const MAX_COUNT: u8 = 15;
+
+/// 一次准入判断的候选结果
+#[derive(Debug, Clone, Copy)]
+pub struct AdmissionCandidate {
+    /// 数的哈希（与访问记录中的键一致）
+    pub key: u64,
+    /// 数的位数
+    pub bits: u64,
+    pub computation_time_ms: u64,
+}
+
+pub trait CacheAdmissionPolicy: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// 是否将候选结果写入缓存
+    fn admit(&self, candidate: &AdmissionCandidate) -> bool;
+
+    /// 记录一次查询（无论是否命中），用于基于频率的策略
+    fn record_access(&self, _key: u64) {}
+}
+
+/// 准入策略配置（`type` 字段选择策略）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AdmissionConfig {
+    Always,
+    MinCost { min_ms: u64 },
+    Frequency {
+        min_requests: u8,
+        sample_size: u64,
+        /// 计算耗时不少于此值（毫秒）的结果不看频率直接写入
+        #[serde(default)]
+        min_cost_ms: Option<u64>,
+    },
+    NumberSize { min_bits: u64 },
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        AdmissionConfig::MinCost { min_ms: 100 }
+    }
+}
+
+impl AdmissionConfig {
+    pub fn build(&self) -> Box<dyn CacheAdmissionPolicy> {
+        match *self {
+            AdmissionConfig::Always => Box::new(AlwaysAdmit),
+            AdmissionConfig::MinCost { min_ms } => Box::new(MinCost { min_ms }),
+            AdmissionConfig::Frequency { min_requests, sample_size, min_cost_ms } => {
+                Box::new(FrequencyAdmission::new(min_requests, sample_size, min_cost_ms))
+            }
+            AdmissionConfig::NumberSize { min_bits } => Box::new(NumberSize { min_bits }),
+        }
+    }
+}
+
+/// 计算准入判断和访问记录使用的键
+pub fn admission_key(number: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    number.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub struct AlwaysAdmit;
+
+impl CacheAdmissionPolicy for AlwaysAdmit {
+    fn name(&self) -> &'static str {
+        "always"
+    }
+
+    fn admit(&self, _candidate: &AdmissionCandidate) -> bool {
+        true
+    }
+}
+
+pub struct MinCost {
+    min_ms: u64,
+}
+
+impl CacheAdmissionPolicy for MinCost {
+    fn name(&self) -> &'static str {
+        "min_cost"
+    }
+
+    fn admit(&self, candidate: &AdmissionCandidate) -> bool {
+        candidate.computation_time_ms >= self.min_ms
+    }
+}
+
+pub struct NumberSize {
+    min_bits: u64,
+}
+
+impl CacheAdmissionPolicy for NumberSize {
+    fn name(&self) -> &'static str {
+        "number_size"
+    }
+
+    fn admit(&self, candidate: &AdmissionCandidate) -> bool {
+        candidate.bits >= self.min_bits
+    }
+}
+
+/// 门卫 + count-min 草图
+struct Sketch {
+    doorkeeper: Vec<u64>,
+    counters: Vec<[u8; SKETCH_DEPTH]>,
+    /// 上次重置后记录的访问次数
+    samples: u64,
+}
+
+impl Sketch {
+    fn new(sample_size: u64) -> Self {
+        // 宽度取采样数的 4 倍，重置前门卫的误判率较低
+        let width = (sample_size as usize).saturating_mul(4).next_power_of_two().max(64);
+        Self {
+            doorkeeper: vec![0; width / 64],
+            counters: vec![[0; SKETCH_DEPTH]; width],
+            samples: 0,
+        }
+    }
+
+    /// 第 row 行中 key 对应的位置（双重哈希）
+    fn index(&self, key: u64, row: usize) -> usize {
+        let step = (key >> 32) | 1;
+        (key.wrapping_add(step.wrapping_mul(row as u64)) as usize) & (self.counters.len() - 1)
+    }
+
+    fn in_doorkeeper(&self, key: u64) -> bool {
+        (0..2).all(|row| {
+            let index = self.index(key, row);
+            self.doorkeeper[index / 64] & (1 << (index % 64)) != 0
+        })
+    }
+
+    fn increment(&mut self, key: u64) {
+        if !self.in_doorkeeper(key) {
+            for row in 0..2 {
+                let index = self.index(key, row);
+                self.doorkeeper[index / 64] |= 1 << (index % 64);
+            }
+            return;
+        }
+        for row in 0..SKETCH_DEPTH {
+            let index = self.index(key, row);
+            let counter = &mut self.counters[index][row];
+            *counter = (*counter + 1).min(MAX_COUNT);
+        }
+    }
+
+    fn estimate(&self, key: u64) -> u8 {
+        let count = (0..SKETCH_DEPTH).map(|row| self.counters[self.index(key, row)][row]).min().unwrap_or(0);
+        count + self.in_doorkeeper(key) as u8
+    }
+
+    fn reset(&mut self) {
+        self.doorkeeper.iter_mut().for_each(|bits| *bits = 0);
+        for counters in &mut self.counters {
+            counters.iter_mut().for_each(|count| *count /= 2);
+        }
+        self.samples = 0;
+    }
+}
+
+pub struct FrequencyAdmission {
+    min_requests: u8,
+    sample_size: u64,
+    min_cost_ms: Option<u64>,
+    sketch: Mutex<Sketch>,
+}
+
+impl FrequencyAdmission {
+    pub fn new(min_requests: u8, sample_size: u64, min_cost_ms: Option<u64>) -> Self {
+        Self {
+            min_requests,
+            sample_size: sample_size.max(1),
+            min_cost_ms,
+            sketch: Mutex::new(Sketch::new(sample_size)),
+        }
+    }
+}
+
+impl CacheAdmissionPolicy for FrequencyAdmission {
+    fn name(&self) -> &'static str {
+        "frequency"
+    }
+
+    fn admit(&self, candidate: &AdmissionCandidate) -> bool {
+        if self.min_cost_ms.is_some_and(|min_ms| candidate.computation_time_ms >= min_ms) {
+            return true;
+        }
+        self.sketch.lock().ok().is_some_and(|sketch| sketch.estimate(candidate.key) >= self.min_requests)
+    }
+
+    fn record_access(&self, key: u64) {
+        let Ok(mut sketch) = self.sketch.lock() else {
+            return;
+        };
+        sketch.increment(key);
+        sketch.samples += 1;
+        if sketch.samples >= self.sample_size {
+            sketch.reset();
+        }
+    }
+}
+
+/// 准入判断的统计
+#[derive(Debug, Clone, Serialize)]
+pub struct AdmissionStats {
+    pub policy: &'static str,
+    pub admitted: u64,
+    pub rejected: u64,
+}
+
+/// 准入策略及其判断次数
+pub struct Admission {
+    policy: Box<dyn CacheAdmissionPolicy>,
+    admitted: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl Admission {
+    pub fn new(policy: Box<dyn CacheAdmissionPolicy>) -> Self {
+        Self {
+            policy,
+            admitted: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    pub fn record_access(&self, key: u64) {
+        self.policy.record_access(key);
+    }
+
+    pub fn admit(&self, candidate: &AdmissionCandidate) -> bool {
+        let admitted = self.policy.admit(candidate);
+        let counter = if admitted { &self.admitted } else { &self.rejected };
+        counter.fetch_add(1, Ordering::SeqCst);
+        admitted
+    }
+
+    pub fn stats(&self) -> AdmissionStats {
+        AdmissionStats {
+            policy: self.policy.name(),
+            admitted: self.admitted.load(Ordering::SeqCst),
+            rejected: self.rejected.load(Ordering::SeqCst),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(key: u64, bits: u64, computation_time_ms: u64) -> AdmissionCandidate {
+        AdmissionCandidate { key, bits, computation_time_ms }
+    }
+
+    #[test]
+    fn test_simple_policies() {
+        assert!(AdmissionConfig::Always.build().admit(&candidate(1, 2, 0)));
+        let min_cost = AdmissionConfig::default().build();
+        assert!(!min_cost.admit(&candidate(1, 64, 99)) && min_cost.admit(&candidate(1, 64, 100)));
+        let size = AdmissionConfig::NumberSize { min_bits: 64 }.build();
+        assert!(!size.admit(&candidate(1, 63, 1000)) && size.admit(&candidate(1, 64, 0)));
+    }
+
+    #[test]
+    fn test_frequency() {
+        let admission = Admission::new(AdmissionConfig::Frequency { min_requests: 3, sample_size: 1000, min_cost_ms: Some(100) }.build());
+        let key = admission_key(12345u128);
+        for expected in [false, false, true] {
+            admission.record_access(key);
+            assert_eq!(admission.admit(&candidate(key, 14, 0)), expected);
+        }
+        // 其他只出现一次的数不受影响
+        assert!(!admission.admit(&candidate(admission_key(54321u128), 16, 0)));
+        let stats = admission.stats();
+        assert_eq!((stats.policy, stats.admitted, stats.rejected), ("frequency", 1, 3));
+        // 计算耗时长的结果第一次出现就写入
+        assert!(admission.admit(&candidate(admission_key(54321u128), 16, 100)));
+
+        // 达到采样数后计数衰减
+        for n in 0..1000u128 {
+            admission.record_access(admission_key(n + 1_000_000));
+        }
+        assert!(!admission.admit(&candidate(key, 14, 0)));
+
+        // JSON 中可以省略 min_cost_ms
+        let config: AdmissionConfig = serde_json::from_str(r#"{"type":"frequency","min_requests":2,"sample_size":10}"#).unwrap();
+        assert_eq!(config, AdmissionConfig::Frequency { min_requests: 2, sample_size: 10, min_cost_ms: None });
+    }
+}
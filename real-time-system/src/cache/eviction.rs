@@ -0,0 +1,156 @@
+// 缓存容量与淘汰策略
+// 缓存可以限制条目数和估算的内存占用。超出任一上限时按策略给所有条目打分，
+// 从得分最低的开始淘汰，直到降到上限的 90% 以下（避免每次插入都触发淘汰）。
+// - LRU：最久未访问的先淘汰
+// - LFU：访问次数最少的先淘汰（次数相同时最久未访问的先淘汰）。访问次数按半衰期指数衰减，
+//   过去很热门但已不再访问的条目不会一直占着缓存
+// - 按代价（GreedyDual）：优先级 = 基准值 + 计算耗时，访问时按当前基准值刷新；
+//   每淘汰一个条目，基准值升到它的优先级。计算耗时长的条目优先级高，保留更久，
+//   但长期不被访问的条目最终也会被新刷新的条目超过而淘汰。
+// 打分和排序在后台线程中进行，插入的线程只检查是否超过上限。
+
+use serde::{Deserialize, Serialize};
+use super::admission::AdmissionConfig;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 淘汰后降到上限的比例
+pub const LOW_WATERMARK: f64 = 0.9;
+
+/// 默认的 LFU 访问次数半衰期（逻辑时钟的滴答数）
+pub const DEFAULT_LFU_HALF_LIFE: u64 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictionPolicy {
+    Lru,
+    Lfu,
+    CostAware,
+}
+
+/// 缓存容量与准入配置（JSON 中缺少的字段使用默认值，上限为 None 表示不限制）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub max_entries: Option<usize>,
+    /// 估算的内存占用上限（字节）
+    pub max_bytes: Option<usize>,
+    pub policy: EvictionPolicy,
+    /// LFU 访问次数的半衰期：每次访问或插入为一个滴答，经过这么多滴答后访问次数减半
+    pub lfu_half_life: u64,
+    /// 实时计算结果的准入策略
+    pub admission: AdmissionConfig,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: None,
+            max_bytes: None,
+            policy: EvictionPolicy::Lru,
+            lfu_half_life: DEFAULT_LFU_HALF_LIFE,
+            admission: AdmissionConfig::default(),
+        }
+    }
+}
+
+/// 淘汰原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionReason {
+    /// 超过条目数上限
+    EntryLimit,
+    /// 超过内存上限
+    ByteLimit,
+}
+
+/// 各原因的淘汰次数
+#[derive(Debug, Default)]
+pub struct EvictionCounters {
+    entry_limit: AtomicU64,
+    byte_limit: AtomicU64,
+}
+
+impl EvictionCounters {
+    pub fn record(&self, reason: EvictionReason) {
+        match reason {
+            EvictionReason::EntryLimit => self.entry_limit.fetch_add(1, Ordering::SeqCst),
+            EvictionReason::ByteLimit => self.byte_limit.fetch_add(1, Ordering::SeqCst),
+        };
+    }
+
+    pub fn snapshot(&self) -> EvictionCounts {
+        EvictionCounts {
+            entry_limit: self.entry_limit.load(Ordering::SeqCst),
+            byte_limit: self.byte_limit.load(Ordering::SeqCst),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct EvictionCounts {
+    pub entry_limit: u64,
+    pub byte_limit: u64,
+}
+
+/// 容量与淘汰统计（`/api/stats`）
+#[derive(Debug, Clone, Serialize)]
+pub struct EvictionStats {
+    pub policy: EvictionPolicy,
+    pub max_entries: Option<usize>,
+    pub max_bytes: Option<usize>,
+    /// 当前估算的内存占用
+    pub bytes: usize,
+    pub evictions: EvictionCounts,
+}
+
+/// 条目的访问记录（读锁下也可更新）
+#[derive(Debug)]
+pub struct Usage {
+    /// 最近一次访问的逻辑时间
+    last_access: AtomicU64,
+    /// 截至最近一次访问、衰减后的访问次数（f64 的位）
+    hits: AtomicU64,
+    /// 按代价淘汰时的优先级（f64 的位）
+    priority: AtomicU64,
+    /// 计算耗时（毫秒），即 GreedyDual 中的代价
+    cost: f64,
+}
+
+impl Usage {
+    pub fn new(tick: u64, computation_time_ms: u64, baseline: f64) -> Self {
+        let cost = computation_time_ms as f64 + 1.0;
+        Self {
+            last_access: AtomicU64::new(tick),
+            hits: AtomicU64::new(0f64.to_bits()),
+            priority: AtomicU64::new((baseline + cost).to_bits()),
+            cost,
+        }
+    }
+
+    /// 记录一次命中：之前的访问次数按距上次访问的时间衰减后加一
+    pub fn touch(&self, tick: u64, baseline: f64, half_life: u64) {
+        let previous = self.last_access.swap(tick, Ordering::Relaxed);
+        let decay = decay(tick.saturating_sub(previous), half_life);
+        let _ = self.hits.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |hits| {
+            Some((f64::from_bits(hits) * decay + 1.0).to_bits())
+        });
+        self.priority.store((baseline + self.cost).to_bits(), Ordering::Relaxed);
+    }
+
+    /// 时刻 now 的淘汰得分，低的先淘汰
+    pub fn score(&self, policy: EvictionPolicy, now: u64, half_life: u64) -> (f64, u64) {
+        let last_access = self.last_access.load(Ordering::Relaxed);
+        match policy {
+            EvictionPolicy::Lru => (last_access as f64, 0),
+            EvictionPolicy::Lfu => {
+                let hits = f64::from_bits(self.hits.load(Ordering::Relaxed));
+                (hits * decay(now.saturating_sub(last_access), half_life), last_access)
+            }
+            EvictionPolicy::CostAware => (f64::from_bits(self.priority.load(Ordering::Relaxed)), last_access),
+        }
+    }
+}
+
+/// 经过 elapsed 个滴答后访问次数保留的比例
+fn decay(elapsed: u64, half_life: u64) -> f64 {
+    0.5f64.powf(elapsed as f64 / half_life.max(1) as f64)
+}
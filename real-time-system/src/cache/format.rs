@@ -0,0 +1,319 @@
+// 缓存文件格式
+// 除 JSON 数组外支持紧凑的二进制格式，加载时按文件头自动识别，写入时按扩展名选择（`.bin` 为二进制）。
+// 二进制格式：
+//   头部 20 字节：魔数 `FCACHE`、版本 u16、正文的 CRC32 u32、正文长度 u64（均为小端）；
+//   正文之后不能有多余的字节
+//   正文：bincode（变长整数编码）序列化的 `Body`。算法名称集中保存一次，条目中只存下标；
+//   因子升序排列后保存差值，小因子和相近的因子只占几个字节
+// 转换：`real-time-system convert-cache <输入> <输出>`
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+use std::path::Path;
+
+use super::snapshot::{write_atomically, StoredEntry};
+use crate::models::{BigCacheEntry, CacheEntry};
+
+const MAGIC: &[u8; 6] = b"FCACHE";
+const VERSION: u16 = 1;
+const HEADER_LEN: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheFormat {
+    Json,
+    Binary,
+}
+
+impl CacheFormat {
+    /// 按扩展名选择写入格式
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("bin") => CacheFormat::Binary,
+            _ => CacheFormat::Json,
+        }
+    }
+
+    /// 按文件头识别格式
+    pub fn detect(data: &[u8]) -> Self {
+        if data.starts_with(MAGIC) {
+            CacheFormat::Binary
+        } else {
+            CacheFormat::Json
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FormatError {
+    #[error("not a binary cache file")]
+    BadMagic,
+
+    #[error("unsupported cache file version {0}")]
+    UnsupportedVersion(u16),
+
+    #[error("cache file is truncated")]
+    Truncated,
+
+    #[error("{0} unexpected bytes after the cache file body")]
+    TrailingBytes(u64),
+
+    #[error("cache file checksum mismatch")]
+    ChecksumMismatch,
+
+    #[error("corrupted cache file: {0}")]
+    Corrupted(String),
+}
+
+impl From<FormatError> for io::Error {
+    fn from(e: FormatError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SmallRecord {
+    number: u128,
+    /// 升序因子的差值
+    factor_deltas: Vec<u128>,
+    computation_time_ms: u64,
+    algorithm: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BigRecord {
+    number: String,
+    factors: Vec<String>,
+    computation_time_ms: u64,
+    algorithm: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Body {
+    algorithms: Vec<String>,
+    small: Vec<SmallRecord>,
+    big: Vec<BigRecord>,
+}
+
+fn options() -> impl Options {
+    bincode::DefaultOptions::new()
+}
+
+/// 算法名称的下标（首次出现时加入表中）
+fn intern(algorithms: &mut Vec<String>, name: &str) -> u32 {
+    match algorithms.iter().position(|algorithm| algorithm == name) {
+        Some(index) => index as u32,
+        None => {
+            algorithms.push(name.to_string());
+            (algorithms.len() - 1) as u32
+        }
+    }
+}
+
+fn algorithm(algorithms: &[String], index: u32) -> Result<String, FormatError> {
+    algorithms
+        .get(index as usize)
+        .cloned()
+        .ok_or_else(|| FormatError::Corrupted(format!("unknown algorithm index {}", index)))
+}
+
+pub fn encode_binary(entries: &[StoredEntry]) -> Result<Vec<u8>, FormatError> {
+    let mut body = Body { algorithms: Vec::new(), small: Vec::new(), big: Vec::new() };
+    for entry in entries {
+        match entry {
+            StoredEntry::Small(entry) => {
+                let mut factors = entry.factors.clone();
+                factors.sort_unstable();
+                let mut previous = 0;
+                let factor_deltas = factors
+                    .iter()
+                    .map(|&factor| {
+                        let delta = factor - previous;
+                        previous = factor;
+                        delta
+                    })
+                    .collect();
+                body.small.push(SmallRecord {
+                    number: entry.number,
+                    factor_deltas,
+                    computation_time_ms: entry.computation_time_ms,
+                    algorithm: intern(&mut body.algorithms, &entry.algorithm),
+                });
+            }
+            StoredEntry::Big(entry) => body.big.push(BigRecord {
+                number: entry.number.clone(),
+                factors: entry.factors.clone(),
+                computation_time_ms: entry.computation_time_ms,
+                algorithm: intern(&mut body.algorithms, &entry.algorithm),
+            }),
+        }
+    }
+
+    let payload = options().serialize(&body).map_err(|e| FormatError::Corrupted(e.to_string()))?;
+    let mut data = Vec::with_capacity(HEADER_LEN + payload.len());
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&VERSION.to_le_bytes());
+    data.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    data.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    data.extend_from_slice(&payload);
+    Ok(data)
+}
+
+pub fn decode_binary(data: &[u8]) -> Result<Vec<StoredEntry>, FormatError> {
+    if !data.starts_with(MAGIC) {
+        return Err(FormatError::BadMagic);
+    }
+    if data.len() < HEADER_LEN {
+        return Err(FormatError::Truncated);
+    }
+    let version = u16::from_le_bytes(data[6..8].try_into().unwrap());
+    if version != VERSION {
+        return Err(FormatError::UnsupportedVersion(version));
+    }
+    let checksum = u32::from_le_bytes(data[8..12].try_into().unwrap());
+    let length = u64::from_le_bytes(data[12..20].try_into().unwrap());
+    let payload = &data[HEADER_LEN..];
+    match (payload.len() as u64).cmp(&length) {
+        std::cmp::Ordering::Less => return Err(FormatError::Truncated),
+        std::cmp::Ordering::Greater => return Err(FormatError::TrailingBytes(payload.len() as u64 - length)),
+        std::cmp::Ordering::Equal => {}
+    }
+    if crc32fast::hash(payload) != checksum {
+        return Err(FormatError::ChecksumMismatch);
+    }
+    // 长度限制：损坏的长度前缀不会导致按声明的长度分配内存
+    let body: Body = options().with_limit(length).deserialize(payload).map_err(|e| FormatError::Corrupted(e.to_string()))?;
+
+    let mut entries = Vec::with_capacity(body.small.len() + body.big.len());
+    for record in body.small {
+        let mut factor = 0u128;
+        let mut factors = Vec::with_capacity(record.factor_deltas.len());
+        for delta in record.factor_deltas {
+            factor = factor
+                .checked_add(delta)
+                .ok_or_else(|| FormatError::Corrupted(format!("factor of {} overflows", record.number)))?;
+            factors.push(factor);
+        }
+        entries.push(StoredEntry::Small(CacheEntry {
+            number: record.number,
+            factors,
+            computation_time_ms: record.computation_time_ms,
+            algorithm: algorithm(&body.algorithms, record.algorithm)?,
+        }));
+    }
+    for record in body.big {
+        entries.push(StoredEntry::Big(BigCacheEntry {
+            algorithm: algorithm(&body.algorithms, record.algorithm)?,
+            number: record.number,
+            factors: record.factors,
+            computation_time_ms: record.computation_time_ms,
+        }));
+    }
+    Ok(entries)
+}
+
+/// 按 format 写出条目
+pub fn write_entries(writer: &mut impl Write, format: CacheFormat, entries: &[StoredEntry]) -> io::Result<()> {
+    match format {
+        CacheFormat::Json => serde_json::to_writer(writer, entries).map_err(io::Error::from),
+        CacheFormat::Binary => writer.write_all(&encode_binary(entries)?),
+    }
+}
+
+/// 读取缓存文件，按文件头识别格式
+pub fn read_entries(path: &Path) -> io::Result<Vec<StoredEntry>> {
+    let data = std::fs::read(path)?;
+    match CacheFormat::detect(&data) {
+        CacheFormat::Json => Ok(serde_json::from_slice(&data)?),
+        CacheFormat::Binary => Ok(decode_binary(&data)?),
+    }
+}
+
+/// 格式转换：real-time-system convert-cache <input> <output>
+pub fn run_cli(args: &[String]) -> io::Result<()> {
+    let [input, output] = args else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: real-time-system convert-cache <input> <output(.bin for binary)>",
+        ));
+    };
+    let entries = read_entries(Path::new(input))?;
+    let bytes = write_atomically(Path::new(output), &entries)?;
+    println!(
+        "Converted {} entries: {} ({} bytes) -> {} ({:?}, {} bytes)",
+        entries.len(),
+        input,
+        std::fs::metadata(input)?.len(),
+        output,
+        CacheFormat::from_path(Path::new(output)),
+        bytes
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<StoredEntry> {
+        vec![
+            StoredEntry::Small(CacheEntry {
+                number: 1_000_000_016_000_000_063,
+                factors: vec![1_000_000_009, 1_000_000_007],
+                computation_time_ms: 12,
+                algorithm: "pollard_rho_brent".to_string(),
+            }),
+            StoredEntry::Small(CacheEntry {
+                number: 12,
+                factors: vec![2, 2, 3],
+                computation_time_ms: 0,
+                algorithm: "simple_trial".to_string(),
+            }),
+            StoredEntry::Big(BigCacheEntry {
+                number: "1020847100762815390390123822295304634371".to_string(),
+                factors: vec!["3".to_string(), "340282366920938463463374607431768211457".to_string()],
+                computation_time_ms: 900,
+                algorithm: "pollard_rho_brent".to_string(),
+            }),
+        ]
+    }
+
+    fn summary(entries: &[StoredEntry]) -> Vec<String> {
+        entries
+            .iter()
+            .map(|entry| match entry {
+                StoredEntry::Small(entry) => format!("{}={:?}/{}/{}", entry.number, entry.factors, entry.computation_time_ms, entry.algorithm),
+                StoredEntry::Big(entry) => format!("{}={:?}/{}/{}", entry.number, entry.factors, entry.computation_time_ms, entry.algorithm),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let entries = sample();
+        let data = encode_binary(&entries).unwrap();
+        assert_eq!(CacheFormat::detect(&data), CacheFormat::Binary);
+        assert!(data.len() < serde_json::to_vec(&entries).unwrap().len());
+
+        // 因子按升序恢复
+        let decoded = decode_binary(&data).unwrap();
+        assert_eq!(summary(&decoded)[0], "1000000016000000063=[1000000007, 1000000009]/12/pollard_rho_brent");
+        assert_eq!(summary(&decoded)[1..], summary(&entries)[1..]);
+    }
+
+    #[test]
+    fn test_rejects_damaged_files() {
+        let mut data = encode_binary(&sample()).unwrap();
+        assert!(matches!(decode_binary(&data[..data.len() - 1]), Err(FormatError::Truncated)));
+        assert!(matches!(decode_binary(&data[..10]), Err(FormatError::Truncated)));
+        assert!(matches!(decode_binary(b"[]"), Err(FormatError::BadMagic)));
+        let mut padded = data.clone();
+        padded.extend_from_slice(&[0, 0]);
+        assert!(matches!(decode_binary(&padded), Err(FormatError::TrailingBytes(2))));
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert!(matches!(decode_binary(&data), Err(FormatError::ChecksumMismatch)));
+        data[6] = 9;
+        assert!(matches!(decode_binary(&data), Err(FormatError::UnsupportedVersion(9))));
+    }
+}
@@ -2,6 +2,9 @@ use super::memory::FactorizationCache;
 use std::sync::Arc;
 use tokio::time::{sleep, Duration};
 
+/// 默认的缓存文件（与预处理系统共用，启动时加载）
+pub const CACHE_FILE: &str = "data/cache.json";
+
 pub async fn start_cache_loader(cache: Arc<FactorizationCache>) {
     log::info!("Cache loader started");
 
@@ -1,74 +1,480 @@
 use dashmap::DashMap;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicU64, Ordering};
-use crate::models::CacheEntry;
+use std::mem::size_of;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Instant;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use num_bigint::BigUint;
+use crate::factorization::Factorization;
+use crate::models::{BigCacheEntry, CacheEntry};
+use super::admission::{admission_key, Admission, AdmissionCandidate, AdmissionStats};
+use super::format::read_entries;
+use super::eviction::{CacheConfig, EvictionCounters, EvictionPolicy, EvictionReason, EvictionStats, Usage, LOW_WATERMARK};
+use super::query_log::QueryLog;
+use super::snapshot::{write_atomically, SnapshotInfo, SnapshotStats, StoredEntry};
+use super::wal::{Wal, WalStats};
+
+/// 缓存条目及其访问记录、估算的内存占用
+struct Slot<E> {
+    entry: E,
+    usage: Usage,
+    bytes: usize,
+}
+
+/// 淘汰时用于定位条目的键
+enum Key {
+    Small(u128),
+    Big(String),
+}
+
+/// 条目与容量记账，由缓存和后台淘汰线程共享
+struct Store {
+    inner: DashMap<u128, Slot<CacheEntry>>,
+    // 超过 128 位的数，以十进制字符串为键
+    big_inner: DashMap<String, Slot<BigCacheEntry>>,
+    // 容量上限与淘汰
+    config: CacheConfig,
+    bytes: AtomicUsize,
+    /// 逻辑时钟，每次访问或插入加一
+    clock: AtomicU64,
+    /// 按代价淘汰的基准值（f64 的位）
+    baseline: AtomicU64,
+    evictions: EvictionCounters,
+}
 
 pub struct FactorizationCache {
-    inner: Arc<DashMap<u64, CacheEntry>>,
+    store: Arc<Store>,
     // 添加统计字段
     total_requests: AtomicU64,
     cache_hits: AtomicU64,
+    // 查询记录（用于空闲时预计算）
+    queries: QueryLog,
+    // 实时计算结果的准入
+    admission: Admission,
+    /// 后台正在淘汰；同一时间只进行一轮淘汰
+    evicting: Arc<AtomicBool>,
+    // 快照：插入次数与上次快照时的插入次数，用于跳过没有变化的快照
+    changes: AtomicU64,
+    saved_changes: AtomicU64,
+    snapshots: Mutex<SnapshotStats>,
+    // 写前日志（启动恢复后挂上）
+    wal: OnceLock<Wal>,
+}
+
+/// 估算条目占用的内存（哈希表中的键值 + 堆上的数据）
+fn small_entry_bytes(entry: &CacheEntry) -> usize {
+    size_of::<(u128, Slot<CacheEntry>)>() + entry.factors.len() * size_of::<u128>() + entry.algorithm.len()
+}
+
+fn big_entry_bytes(entry: &BigCacheEntry) -> usize {
+    let factors: usize = entry.factors.iter().map(|factor| size_of::<String>() + factor.len()).sum();
+    // 键与条目中各保存一份十进制字符串
+    size_of::<(String, Slot<BigCacheEntry>)>() + 2 * entry.number.len() + factors + entry.algorithm.len()
+}
+
+impl Store {
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn baseline(&self) -> f64 {
+        f64::from_bits(self.baseline.load(Ordering::Relaxed))
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len() + self.big_inner.len()
+    }
+
+    /// 超过上限的原因；low_watermark 为 true 时与淘汰的目标值（上限的 90%）比较
+    fn over_limit(&self, low_watermark: bool) -> Option<EvictionReason> {
+        let limit = |max: usize| if low_watermark { (max as f64 * LOW_WATERMARK) as usize } else { max };
+        if self.config.max_entries.is_some_and(|max| self.len() > limit(max)) {
+            return Some(EvictionReason::EntryLimit);
+        }
+        if self.config.max_bytes.is_some_and(|max| self.bytes.load(Ordering::SeqCst) > limit(max)) {
+            return Some(EvictionReason::ByteLimit);
+        }
+        None
+    }
+
+    /// 按策略淘汰得分最低的条目，直到降到目标值以下，返回淘汰的条目数
+    fn evict(&self) -> usize {
+        let policy = self.config.policy;
+        let now = self.clock.load(Ordering::Relaxed);
+        let half_life = self.config.lfu_half_life;
+        let mut candidates: Vec<((f64, u64), Key)> = self
+            .inner
+            .iter()
+            .map(|slot| (slot.usage.score(policy, now, half_life), Key::Small(*slot.key())))
+            .chain(self.big_inner.iter().map(|slot| (slot.usage.score(policy, now, half_life), Key::Big(slot.key().clone()))))
+            .collect();
+        candidates.sort_by(|(a, _), (b, _)| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+        let mut evicted = 0;
+        for ((score, _), key) in candidates {
+            let Some(reason) = self.over_limit(true) else {
+                break;
+            };
+            let removed = match key {
+                Key::Small(n) => self.inner.remove(&n).map(|(_, slot)| slot.bytes),
+                Key::Big(n) => self.big_inner.remove(&n).map(|(_, slot)| slot.bytes),
+            };
+            if let Some(bytes) = removed {
+                self.bytes.fetch_sub(bytes, Ordering::SeqCst);
+                self.evictions.record(reason);
+                evicted += 1;
+                if policy == EvictionPolicy::CostAware {
+                    // 基准值升到被淘汰条目的优先级，之后访问的条目优先级随之提高
+                    self.baseline.fetch_max(score.max(0.0).to_bits(), Ordering::Relaxed);
+                }
+            }
+        }
+        log::debug!("Evicted {} cache entries ({:?})", evicted, policy);
+        evicted
+    }
+}
+
+/// 不限容量的缓存
+impl Default for FactorizationCache {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl FactorizationCache {
+    /// 创建不限容量的缓存
     pub fn new() -> Self {
+        Self::with_config(CacheConfig::default())
+    }
+
+    /// 创建按 config 限制容量的缓存
+    pub fn with_config(config: CacheConfig) -> Self {
         Self {
-            inner: Arc::new(DashMap::new()),
             total_requests: AtomicU64::new(0),
             cache_hits: AtomicU64::new(0),
+            queries: QueryLog::default(),
+            admission: Admission::new(config.admission.build()),
+            store: Arc::new(Store {
+                inner: DashMap::new(),
+                big_inner: DashMap::new(),
+                config,
+                bytes: AtomicUsize::new(0),
+                clock: AtomicU64::new(0),
+                baseline: AtomicU64::new(0f64.to_bits()),
+                evictions: EvictionCounters::default(),
+            }),
+            evicting: Arc::new(AtomicBool::new(false)),
+            changes: AtomicU64::new(0),
+            saved_changes: AtomicU64::new(0),
+            snapshots: Mutex::new(SnapshotStats::default()),
+            wal: OnceLock::new(),
         }
     }
 
-    pub fn get(&self, n: u64) -> Option<CacheEntry> {
+    /// 记录一次命中
+    fn touch(&self, usage: &Usage) {
+        usage.touch(self.store.tick(), self.store.baseline(), self.store.config.lfu_half_life);
+    }
+
+    pub fn get(&self, n: u128) -> Option<CacheEntry> {
         // 增加总请求数
         self.total_requests.fetch_add(1, Ordering::SeqCst);
 
-        if let Some(entry) = self.inner.get(&n) {
+        let entry = self.store.inner.get(&n).map(|slot| {
+            self.touch(&slot.usage);
+            slot.entry.clone()
+        });
+        if entry.is_some() {
             // 缓存命中，增加命中数
             self.cache_hits.fetch_add(1, Ordering::SeqCst);
-            Some(entry.clone())
+        }
+        self.queries.record(n, entry.is_some());
+        self.admission.record_access(admission_key(n));
+        entry
+    }
+
+    pub fn get_big(&self, n: &BigUint) -> Option<BigCacheEntry> {
+        self.total_requests.fetch_add(1, Ordering::SeqCst);
+
+        let key = n.to_string();
+        self.admission.record_access(admission_key(&key));
+        if let Some(slot) = self.store.big_inner.get(&key) {
+            self.cache_hits.fetch_add(1, Ordering::SeqCst);
+            self.touch(&slot.usage);
+            Some(slot.entry.clone())
         } else {
             None
         }
     }
 
-    pub fn insert_with_factors(&self, n: u64, factors: Vec<u64>, computation_time_ms: u64, algorithm: String) {
+    /// 是否已缓存 n（不计入命中率统计）
+    pub fn contains(&self, n: u128) -> bool {
+        self.store.inner.contains_key(&n)
+    }
+
+    /// 是否已缓存超过 128 位的 n（不计入命中率统计）
+    pub fn contains_big(&self, n: &BigUint) -> bool {
+        self.store.big_inner.contains_key(&n.to_string())
+    }
+
+    /// 查询记录（最近未命中的数、热门的数）
+    pub fn queries(&self) -> &QueryLog {
+        &self.queries
+    }
+
+    pub fn insert_with_factors(&self, n: u128, factors: Vec<u128>, computation_time_ms: u64, algorithm: String) {
         let entry = CacheEntry {
             number: n,
             factors,
             computation_time_ms,
             algorithm,
         };
-        self.inner.insert(n, entry);
+        let stored = self.wal.get().map(|_| StoredEntry::Small(entry.clone()));
+        self.insert_entry(entry);
+        self.append_to_wal(stored);
+    }
+
+    /// 将插入追加到日志。先写入内存再追加：压缩时旧日志中的记录都已在内存中，一定包含在新快照里
+    fn append_to_wal(&self, entry: Option<StoredEntry>) {
+        if let (Some(wal), Some(entry)) = (self.wal.get(), entry) {
+            if let Err(e) = wal.append(&entry) {
+                log::warn!("Failed to append to cache WAL: {}", e);
+            }
+        }
+    }
+
+    fn insert_entry(&self, entry: CacheEntry) {
+        let bytes = small_entry_bytes(&entry);
+        let slot = Slot {
+            usage: Usage::new(self.store.tick(), entry.computation_time_ms, self.store.baseline()),
+            entry,
+            bytes,
+        };
+        self.store.bytes.fetch_add(bytes, Ordering::SeqCst);
+        if let Some(replaced) = self.store.inner.insert(slot.entry.number, slot) {
+            self.store.bytes.fetch_sub(replaced.bytes, Ordering::SeqCst);
+        }
+        self.changes.fetch_add(1, Ordering::SeqCst);
+        self.enforce_limits();
+    }
+
+    fn insert_big_entry(&self, entry: BigCacheEntry) {
+        let bytes = big_entry_bytes(&entry);
+        let slot = Slot {
+            usage: Usage::new(self.store.tick(), entry.computation_time_ms, self.store.baseline()),
+            entry,
+            bytes,
+        };
+        self.store.bytes.fetch_add(bytes, Ordering::SeqCst);
+        if let Some(replaced) = self.store.big_inner.insert(slot.entry.number.clone(), slot) {
+            self.store.bytes.fetch_sub(replaced.bytes, Ordering::SeqCst);
+        }
+        self.changes.fetch_add(1, Ordering::SeqCst);
+        self.enforce_limits();
+    }
+
+    /// 缓存分解结果；不完整的结果不会被缓存，返回是否插入
+    pub fn insert_factorization(&self, n: u128, factorization: &Factorization, computation_time_ms: u64, algorithm: String) -> bool {
+        if !factorization.complete {
+            return false;
+        }
+        self.insert_with_factors(n, factorization.proven_primes.clone(), computation_time_ms, algorithm);
+        true
+    }
+
+    /// 缓存超过 128 位的分解结果；不完整的结果不会被缓存，返回是否插入
+    pub fn insert_big_factorization(&self, n: &BigUint, factorization: &Factorization<BigUint>, computation_time_ms: u64, algorithm: String) -> bool {
+        if !factorization.complete {
+            return false;
+        }
+        let entry = BigCacheEntry {
+            number: n.to_string(),
+            factors: factorization.proven_primes.iter().map(|f| f.to_string()).collect(),
+            computation_time_ms,
+            algorithm,
+        };
+        let stored = self.wal.get().map(|_| StoredEntry::Big(entry.clone()));
+        self.insert_big_entry(entry);
+        self.append_to_wal(stored);
+        true
     }
 
-    // 注意：这里只有 insert_with_factors，没有单独的 insert 方法
-    // 如果你有 insert 方法，可以保留或删除
+    /// 按准入策略缓存实时计算的结果，返回是否插入
+    pub fn offer_factorization(&self, n: u128, factorization: &Factorization, computation_time_ms: u64, algorithm: String) -> bool {
+        let candidate = AdmissionCandidate {
+            key: admission_key(n),
+            bits: (u128::BITS - n.leading_zeros()) as u64,
+            computation_time_ms,
+        };
+        factorization.complete && self.admission.admit(&candidate) && self.insert_factorization(n, factorization, computation_time_ms, algorithm)
+    }
+
+    /// 按准入策略缓存超过 128 位的实时计算结果，返回是否插入
+    pub fn offer_big_factorization(&self, n: &BigUint, factorization: &Factorization<BigUint>, computation_time_ms: u64, algorithm: String) -> bool {
+        let candidate = AdmissionCandidate {
+            key: admission_key(n.to_string()),
+            bits: n.bits(),
+            computation_time_ms,
+        };
+        factorization.complete && self.admission.admit(&candidate) && self.insert_big_factorization(n, factorization, computation_time_ms, algorithm)
+    }
+
+    /// 准入判断统计
+    pub fn admission_stats(&self) -> AdmissionStats {
+        self.admission.stats()
+    }
 
     pub fn len(&self) -> usize {
-        self.inner.len()
+        self.store.len()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.inner.is_empty()
+        self.store.inner.is_empty() && self.store.big_inner.is_empty()
     }
 
     pub fn load_from_file(&self, path: &str) -> Result<usize, std::io::Error> {
-        use std::fs::File;
-        use std::io::BufReader;
-
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let entries: Vec<CacheEntry> = serde_json::from_reader(reader)?;
+        // JSON 或二进制格式，按文件头识别
+        let entries = read_entries(Path::new(path))?;
 
+        // 只加入缓存中没有的条目：定期重新加载时不会覆盖已有条目的访问记录
         let count = entries.len();
         for entry in entries {
-            self.inner.insert(entry.number, entry);
+            match entry {
+                StoredEntry::Small(entry) if !self.store.inner.contains_key(&entry.number) => self.insert_entry(entry),
+                StoredEntry::Big(entry) if !self.store.big_inner.contains_key(&entry.number) => self.insert_big_entry(entry),
+                _ => {}
+            }
         }
 
         Ok(count)
     }
 
+    /// 将所有条目原子地写入 path（先写临时文件再重命名）
+    pub fn save_to_file(&self, path: &str) -> Result<SnapshotInfo, std::io::Error> {
+        // 定期快照与关闭时的快照可能同时发生，同一时间只写一次
+        // 统计只是计数，之前的快照中途 panic 也可以继续使用
+        let mut stats = self.snapshots.lock().unwrap_or_else(|e| e.into_inner());
+        let start = Instant::now();
+        let changes = self.changes.load(Ordering::SeqCst);
+        let entries: Vec<StoredEntry> = self
+            .store
+            .inner
+            .iter()
+            .map(|slot| StoredEntry::Small(slot.entry.clone()))
+            .chain(self.store.big_inner.iter().map(|slot| StoredEntry::Big(slot.entry.clone())))
+            .collect();
+
+        match write_atomically(Path::new(path), &entries) {
+            Ok(bytes) => {
+                let info = SnapshotInfo {
+                    entries: entries.len(),
+                    bytes,
+                    duration_ms: start.elapsed().as_millis() as u64,
+                    saved_at: chrono::Utc::now().to_rfc3339(),
+                };
+                self.saved_changes.store(changes, Ordering::SeqCst);
+                stats.snapshots += 1;
+                stats.last = Some(info.clone());
+                stats.last_error = None;
+                Ok(info)
+            }
+            Err(e) => {
+                stats.failures += 1;
+                stats.last_error = Some(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    /// 重放日志中的条目（日志比快照新，覆盖已有条目）
+    pub fn replay(&self, entries: Vec<StoredEntry>) {
+        for entry in entries {
+            match entry {
+                StoredEntry::Small(entry) => self.insert_entry(entry),
+                StoredEntry::Big(entry) => self.insert_big_entry(entry),
+            }
+        }
+    }
+
+    /// 挂上日志，之后的插入都会追加到日志
+    pub fn attach_wal(&self, wal: Wal) {
+        if self.wal.set(wal).is_err() {
+            log::warn!("Cache WAL is already attached");
+        }
+    }
+
+    /// 将日志折叠进新快照：开始新日志，写出快照后删除旧日志。没有日志时只写快照
+    pub fn compact(&self, path: &str) -> Result<SnapshotInfo, std::io::Error> {
+        let Some(wal) = self.wal.get() else {
+            return self.save_to_file(path);
+        };
+        wal.rotate()?;
+        let info = self.save_to_file(path)?;
+        wal.finish_compaction()?;
+        Ok(info)
+    }
+
+    /// 当前日志的大小（没有日志时为 None）
+    pub fn wal_bytes(&self) -> Option<u64> {
+        self.wal.get().map(Wal::bytes)
+    }
+
+    pub fn wal_stats(&self) -> Option<WalStats> {
+        self.wal.get().map(Wal::stats)
+    }
+
+    /// 上次快照后是否插入过条目
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.changes.load(Ordering::SeqCst) != self.saved_changes.load(Ordering::SeqCst)
+    }
+
+    /// 快照统计
+    pub fn snapshot_stats(&self) -> SnapshotStats {
+        self.snapshots.lock().map(|stats| stats.clone()).unwrap_or_else(|e| e.into_inner().clone())
+    }
+
+    /// 超过上限时在后台线程中淘汰，插入的线程不做打分和排序
+    fn enforce_limits(&self) {
+        if self.store.over_limit(false).is_none() || self.evicting.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let store = Arc::clone(&self.store);
+        let evicting = Arc::clone(&self.evicting);
+        let spawned = thread::Builder::new().name("cache-eviction".to_string()).spawn(move || loop {
+            // 淘汰期间插入的条目不在本轮的候选中，仍超过上限时再淘汰一轮
+            while store.over_limit(false).is_some() && store.evict() > 0 {}
+            evicting.store(false, Ordering::SeqCst);
+            // 重置标志之前插入的条目没有触发淘汰，这里补上
+            if store.over_limit(false).is_none() || evicting.swap(true, Ordering::SeqCst) {
+                break;
+            }
+        });
+        if let Err(e) = spawned {
+            log::warn!("Failed to spawn cache eviction thread: {}", e);
+            self.evicting.store(false, Ordering::SeqCst);
+        }
+    }
+
+    /// 等待后台淘汰结束
+    #[cfg(test)]
+    fn wait_for_eviction(&self) {
+        while self.evicting.load(Ordering::SeqCst) {
+            thread::yield_now();
+        }
+    }
+
+    /// 容量与淘汰统计
+    pub fn eviction_stats(&self) -> EvictionStats {
+        EvictionStats {
+            policy: self.store.config.policy,
+            max_entries: self.store.config.max_entries,
+            max_bytes: self.store.config.max_bytes,
+            bytes: self.store.bytes.load(Ordering::SeqCst),
+            evictions: self.store.evictions.snapshot(),
+        }
+    }
+
     // 只保留一个 get_hit_rate 函数定义
     pub fn get_hit_rate(&self) -> f64 {
         let total = self.total_requests.load(Ordering::SeqCst);
@@ -82,6 +488,7 @@ impl FactorizationCache {
     }
 
     // 添加获取原始统计数据的方法
+    #[allow(dead_code)]
     pub fn get_cache_stats(&self) -> (u64, u64, f64) {
         let total = self.total_requests.load(Ordering::SeqCst);
         let hits = self.cache_hits.load(Ordering::SeqCst);
@@ -89,4 +496,136 @@ impl FactorizationCache {
 
         (total, hits, rate)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::admission::AdmissionConfig;
+
+    fn bounded(max_entries: usize, policy: EvictionPolicy) -> FactorizationCache {
+        FactorizationCache::with_config(CacheConfig { max_entries: Some(max_entries), max_bytes: None, policy, ..CacheConfig::default() })
+    }
+
+    fn insert(cache: &FactorizationCache, n: u128, computation_time_ms: u64) {
+        cache.insert_with_factors(n, vec![n], computation_time_ms, "test".to_string());
+    }
+
+    #[test]
+    fn test_lru_and_lfu() {
+        let lru = bounded(10, EvictionPolicy::Lru);
+        for n in 0..10 {
+            insert(&lru, n, 0);
+        }
+        lru.get(0);
+        // 超过上限后降到 9 条：最久未访问的 1、2 被淘汰
+        insert(&lru, 10, 0);
+        lru.wait_for_eviction();
+        assert_eq!(lru.len(), 9);
+        assert!(lru.contains(0) && !lru.contains(1) && !lru.contains(2) && lru.contains(3));
+        assert_eq!(lru.eviction_stats().evictions.entry_limit, 2);
+
+        let lfu = bounded(10, EvictionPolicy::Lfu);
+        for n in 0..10 {
+            insert(&lfu, n, 0);
+            lfu.get(n);
+        }
+        lfu.get(9);
+        insert(&lfu, 10, 0);
+        lfu.wait_for_eviction();
+        // 只被访问一次的条目中最久未访问的先淘汰；新条目访问次数为 0
+        assert!(!lfu.contains(10) && !lfu.contains(0) && lfu.contains(1) && lfu.contains(9));
+    }
+
+    #[test]
+    fn test_lfu_decay() {
+        let cache = FactorizationCache::with_config(CacheConfig {
+            max_entries: Some(4),
+            policy: EvictionPolicy::Lfu,
+            lfu_half_life: 10,
+            ..CacheConfig::default()
+        });
+        // 0 曾经很热门，之后只访问 1、2、3
+        insert(&cache, 0, 0);
+        for _ in 0..50 {
+            cache.get(0);
+        }
+        for n in 1..4 {
+            insert(&cache, n, 0);
+        }
+        for _ in 0..40 {
+            for n in 1..4 {
+                cache.get(n);
+            }
+        }
+        insert(&cache, 4, 0);
+        cache.wait_for_eviction();
+        // 访问次数衰减后 0 的得分低于近期访问的条目，先被淘汰
+        assert!(!cache.contains(0) && !cache.contains(4));
+        assert!((1..4).all(|n| cache.contains(n)));
+    }
+
+    #[test]
+    fn test_cost_aware() {
+        let cache = bounded(10, EvictionPolicy::CostAware);
+        insert(&cache, 1, 1000);
+        for n in 2..20 {
+            insert(&cache, n, 1);
+        }
+        cache.wait_for_eviction();
+        // 计算耗时长的条目保留
+        assert!(cache.contains(1));
+        assert!(cache.len() <= 10);
+
+        // 基准值随淘汰升高，不再访问的昂贵条目最终也会被淘汰
+        // （每次插入后等淘汰结束：突发插入时一轮淘汰的条目多，基准值升得慢）
+        for n in 20..20_000 {
+            insert(&cache, n, 1);
+            cache.wait_for_eviction();
+        }
+        assert!(!cache.contains(1));
+    }
+
+    #[test]
+    fn test_byte_limit() {
+        let max_bytes = 10 * small_entry_bytes(&CacheEntry {
+            number: 0,
+            factors: vec![0],
+            computation_time_ms: 0,
+            algorithm: "test".to_string(),
+        });
+        let cache = FactorizationCache::with_config(CacheConfig {
+            max_bytes: Some(max_bytes),
+            ..CacheConfig::default()
+        });
+        for n in 0..100 {
+            insert(&cache, n, 0);
+        }
+        cache.wait_for_eviction();
+        let stats = cache.eviction_stats();
+        assert!(stats.bytes <= max_bytes && cache.len() <= 10);
+        assert_eq!(stats.evictions.byte_limit as usize, 100 - cache.len());
+
+        // 替换已有条目不会重复计算占用
+        insert(&cache, 99, 0);
+        assert_eq!(cache.eviction_stats().bytes, stats.bytes);
+    }
+
+    #[test]
+    fn test_offer_by_frequency() {
+        let cache = FactorizationCache::with_config(CacheConfig {
+            admission: AdmissionConfig::Frequency { min_requests: 2, sample_size: 1000, min_cost_ms: None },
+            ..CacheConfig::default()
+        });
+        let factorization = Factorization::complete(vec![3, 5]);
+        // 第一次请求未命中，结果不写入；第二次请求后写入
+        assert!(cache.get(15).is_none());
+        assert!(!cache.offer_factorization(15, &factorization, 0, "test".to_string()));
+        assert!(cache.get(15).is_none());
+        assert!(cache.offer_factorization(15, &factorization, 0, "test".to_string()));
+        assert!(cache.get(15).is_some());
+
+        let stats = cache.admission_stats();
+        assert_eq!((stats.policy, stats.admitted, stats.rejected), ("frequency", 1, 1));
+    }
+}
@@ -1,6 +1,20 @@
 pub mod memory;
+pub mod eviction;
+pub mod admission;
+// 定期加载任务保留为 API，服务启动时只加载一次缓存文件
+#[allow(dead_code)]
 pub mod loader;
+pub mod query_log;
+pub mod snapshot;
+pub mod wal;
+pub mod format;
 
 // 重新导出
 pub use memory::FactorizationCache;
-pub use loader::start_cache_loader;
\ No newline at end of file
+pub use eviction::{CacheConfig, EvictionPolicy};
+pub use admission::AdmissionConfig;
+#[allow(unused_imports)]
+pub use loader::start_cache_loader;
+pub use loader::CACHE_FILE;
+pub use snapshot::start_cache_snapshots;
+pub use wal::Wal;
\ No newline at end of file
@@ -0,0 +1,77 @@
+// 查询记录
+// 记录最近未命中缓存的数和每个数被查询的次数，空闲时的预计算据此选择要填充的数（见 `precompute`）。
+// 只记录 u128 范围内的数：更大的数分解代价太高，不适合预计算。
+
+use dashmap::DashMap;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// 保留的最近未命中数
+const MISS_CAPACITY: usize = 1000;
+
+/// 记录查询次数的数的上限，超出后所有计数减半并删除归零的数
+const COUNT_CAPACITY: usize = 10_000;
+
+#[derive(Debug, Default)]
+pub struct QueryLog {
+    /// 最近未命中的数（最新的在后，不重复）
+    misses: Mutex<VecDeque<u128>>,
+    counts: DashMap<u128, u64>,
+}
+
+impl QueryLog {
+    /// 记录一次查询
+    pub fn record(&self, n: u128, hit: bool) {
+        *self.counts.entry(n).or_insert(0) += 1;
+        if self.counts.len() > COUNT_CAPACITY {
+            // 衰减：只被查询过一次的数被删除，热门的数保留
+            self.counts.retain(|_, count| {
+                *count /= 2;
+                *count > 0
+            });
+        }
+
+        if !hit {
+            if let Ok(mut misses) = self.misses.lock() {
+                if !misses.contains(&n) {
+                    if misses.len() >= MISS_CAPACITY {
+                        misses.pop_front();
+                    }
+                    misses.push_back(n);
+                }
+            }
+        }
+    }
+
+    /// 取出最近一个未命中的数
+    pub fn pop_recent_miss(&self) -> Option<u128> {
+        self.misses.lock().ok()?.pop_back()
+    }
+
+    /// 查询次数最多的 limit 个数（次数从多到少，次数相同时数小的在前）
+    pub fn popular(&self, limit: usize) -> Vec<(u128, u64)> {
+        let mut counts: Vec<(u128, u64)> = self.counts.iter().map(|entry| (*entry.key(), *entry.value())).collect();
+        counts.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        counts.truncate(limit);
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_misses_and_popularity() {
+        let log = QueryLog::default();
+        for n in [10, 20, 10, 30, 10, 20] {
+            log.record(n, n == 30);
+        }
+
+        assert_eq!(log.popular(2), vec![(10, 3), (20, 2)]);
+        // 重复未命中的数只记录一次，命中的数不记录
+        assert_eq!(log.pop_recent_miss(), Some(20));
+        assert_eq!(log.pop_recent_miss(), Some(10));
+        assert_eq!(log.pop_recent_miss(), None);
+    }
+}
@@ -0,0 +1,143 @@
+// 缓存快照
+// 缓存定期写回缓存文件（默认为与预处理系统共用的 JSON 数组格式，见 `format`），服务关闭时再写一次，重启后不丢失运行时计算的结果。
+// 超过 128 位的条目以字符串保存，加载时按能否解析为 u128 区分。
+// 写入先落到同目录下的临时文件并 fsync，再重命名覆盖原文件，中途崩溃不会留下写了一半的缓存文件。
+// 临时文件名带进程号和序号，多个进程或同时进行的写入（如格式转换）不会互相覆盖临时文件。
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::time::{interval, Duration, MissedTickBehavior};
+
+use super::format::{write_entries, CacheFormat};
+use super::memory::FactorizationCache;
+use crate::models::{BigCacheEntry, CacheEntry};
+
+/// 定期快照的间隔
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(60);
+/// 挂上写前日志后，日志超过此大小才压缩（插入已记录在日志中，不必每次都写完整快照）
+const COMPACT_WAL_BYTES: u64 = 8 * 1024 * 1024;
+/// 本进程内临时文件的序号
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// 缓存文件中的一个条目
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StoredEntry {
+    Small(CacheEntry),
+    Big(BigCacheEntry),
+}
+
+/// 一次快照的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotInfo {
+    pub entries: usize,
+    pub bytes: u64,
+    pub duration_ms: u64,
+    pub saved_at: String,
+}
+
+/// 快照统计（`/api/stats`）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SnapshotStats {
+    pub snapshots: u64,
+    pub failures: u64,
+    pub last: Option<SnapshotInfo>,
+    pub last_error: Option<String>,
+}
+
+/// 原子地将条目写入 path（格式按扩展名选择），返回文件大小
+pub fn write_atomically(path: &Path, entries: &[StoredEntry]) -> Result<u64, std::io::Error> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(".{}.{}.tmp", std::process::id(), TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)));
+    let temp = path.with_file_name(temp_name);
+
+    let write = || -> Result<u64, std::io::Error> {
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        write_entries(&mut writer, CacheFormat::from_path(path), entries)?;
+        writer.flush()?;
+        let file = writer.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        Ok(file.metadata()?.len())
+    };
+    let bytes = match write() {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            return Err(e);
+        }
+    };
+    fs::rename(&temp, path)?;
+    Ok(bytes)
+}
+
+/// 定期将有变化的缓存写回 path；有写前日志时改为在日志变大后压缩
+pub async fn start_cache_snapshots(cache: Arc<FactorizationCache>, path: String) {
+    log::info!("Cache snapshots started, writing to {} every {:?}", path, SNAPSHOT_INTERVAL);
+
+    let mut ticker = interval(SNAPSHOT_INTERVAL);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // 第一次 tick 立即完成，跳过
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        let due = match cache.wal_bytes() {
+            Some(bytes) => bytes >= COMPACT_WAL_BYTES,
+            None => cache.has_unsaved_changes(),
+        };
+        if !due {
+            continue;
+        }
+        let (cache, target) = (Arc::clone(&cache), path.clone());
+        // 序列化和写文件在阻塞线程中进行
+        match tokio::task::spawn_blocking(move || cache.compact(&target)).await {
+            Ok(Ok(info)) => log::info!(
+                "Saved {} cache entries ({} bytes) to {} in {} ms",
+                info.entries, info.bytes, path, info.duration_ms
+            ),
+            Ok(Err(e)) => log::warn!("Failed to save cache snapshot: {}", e),
+            Err(e) => log::error!("Cache snapshot task panicked: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::FactorizationCache;
+    use crate::factorization::Factorization;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("cache-snapshot-{}", std::process::id()));
+        let path = dir.join("cache.json");
+        let path = path.to_str().unwrap();
+
+        let cache = FactorizationCache::new();
+        cache.insert_with_factors(15, vec![3, 5], 120, "simple_trial".to_string());
+        let big: BigUint = BigUint::from(u128::MAX) * 3u32;
+        let factors = vec![BigUint::from(3u32), BigUint::from(u128::MAX)];
+        cache.insert_big_factorization(&big, &Factorization::complete(factors), 900, "ecm".to_string());
+        assert!(cache.has_unsaved_changes());
+
+        let info = cache.save_to_file(path).unwrap();
+        assert_eq!(info.entries, 2);
+        assert!(!cache.has_unsaved_changes());
+        // 临时文件已重命名
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        let restored = FactorizationCache::new();
+        assert_eq!(restored.load_from_file(path).unwrap(), 2);
+        assert_eq!(restored.get(15).unwrap().factors, vec![3, 5]);
+        assert_eq!(restored.get_big(&big).unwrap().algorithm, "ecm");
+        assert_eq!(cache.snapshot_stats().snapshots, 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,282 @@
+// 缓存写前日志（WAL）
+// 每次插入追加一条记录：[长度 u32 LE][CRC32 u32 LE][条目的 JSON]，写入操作系统后即返回（不逐条 fsync，
+// 进程崩溃不丢记录，断电可能丢失最后几条）。
+// 启动时先加载快照，再按顺序重放 `<日志>.old` 和日志；末尾不完整或校验失败的记录被截断。
+// 压缩：将日志重命名为 `<日志>.old` 并开始新日志，写出包含所有条目的新快照后删除 `.old`。
+// 压缩中途崩溃时 `.old` 仍在，重启后照常重放，不会丢失记录。
+// 日志路径为 `<缓存文件>.wal`，每个实例随各自的缓存文件使用自己的日志；打开的日志文件加排他锁，
+// 两个进程误用同一个日志时后打开的一方直接报错，不会交错写入。
+
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use super::snapshot::StoredEntry;
+
+/// 记录头：长度 + 校验和
+const HEADER_LEN: usize = 8;
+/// 单条记录的长度上限，超过时视为损坏
+const MAX_RECORD_LEN: usize = 16 * 1024 * 1024;
+
+/// 日志统计（`/api/stats`）
+#[derive(Debug, Clone, Serialize)]
+pub struct WalStats {
+    /// 当前日志中的记录数与大小
+    pub records: u64,
+    pub bytes: u64,
+    pub compactions: u64,
+    /// 启动时重放的记录数与截断的字节数
+    pub recovered: u64,
+    pub truncated_bytes: u64,
+}
+
+pub struct Wal {
+    path: PathBuf,
+    file: Mutex<File>,
+    records: AtomicU64,
+    bytes: AtomicU64,
+    compactions: AtomicU64,
+    recovered: u64,
+    truncated_bytes: u64,
+}
+
+/// 压缩期间保存旧日志的路径
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".old");
+    path.with_file_name(name)
+}
+
+fn encode(entry: &StoredEntry) -> Result<Vec<u8>, std::io::Error> {
+    let payload = serde_json::to_vec(entry)?;
+    let mut record = Vec::with_capacity(HEADER_LEN + payload.len());
+    record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    record.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    record.extend_from_slice(&payload);
+    Ok(record)
+}
+
+/// 解析日志内容，返回有效的条目和有效部分的长度（之后的内容损坏或不完整）
+fn decode(data: &[u8]) -> (Vec<StoredEntry>, usize) {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while data.len() - offset >= HEADER_LEN {
+        let len = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
+        let checksum = u32::from_le_bytes(data[offset + 4..offset + 8].try_into().unwrap());
+        let start = offset + HEADER_LEN;
+        if len > MAX_RECORD_LEN || data.len() - start < len {
+            break;
+        }
+        let payload = &data[start..start + len];
+        if crc32fast::hash(payload) != checksum {
+            break;
+        }
+        match serde_json::from_slice(payload) {
+            Ok(entry) => entries.push(entry),
+            Err(_) => break,
+        }
+        offset = start + len;
+    }
+    (entries, offset)
+}
+
+fn read_all(path: &Path) -> Result<Option<Vec<u8>>, std::io::Error> {
+    let mut data = Vec::new();
+    match File::open(path) {
+        Ok(mut file) => file.read_to_end(&mut data).map(|_| Some(data)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// 以追加方式打开日志并加排他锁
+fn open_locked(path: &Path) -> Result<File, std::io::Error> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    match file.try_lock() {
+        Ok(()) => Ok(file),
+        Err(TryLockError::WouldBlock) => Err(std::io::Error::new(
+            ErrorKind::WouldBlock,
+            format!("{} is in use by another process", path.display()),
+        )),
+        Err(TryLockError::Error(e)) => Err(e),
+    }
+}
+
+impl Wal {
+    /// 缓存文件对应的日志路径（`<缓存文件>.wal`）
+    pub fn path_for(cache_file: impl AsRef<Path>) -> PathBuf {
+        let mut name = cache_file.as_ref().as_os_str().to_os_string();
+        name.push(".wal");
+        PathBuf::from(name)
+    }
+
+    /// 打开日志并返回需要重放的条目（先 `.old` 后当前日志），损坏的末尾被截断
+    pub fn open(path: impl Into<PathBuf>) -> Result<(Self, Vec<StoredEntry>), std::io::Error> {
+        let path = path.into();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        // 先加锁再读取和截断，另一个进程正在写的日志不会被改动
+        let file = open_locked(&path)?;
+
+        let mut entries = Vec::new();
+        let mut truncated_bytes = 0;
+        // 旧日志在下次压缩成功后删除，这里只读取有效部分
+        if let Some(data) = read_all(&rotated_path(&path))? {
+            let (old, valid) = decode(&data);
+            truncated_bytes += (data.len() - valid) as u64;
+            entries.extend(old);
+        }
+        let (mut current, mut valid) = (Vec::new(), 0);
+        if let Some(data) = read_all(&path)? {
+            (current, valid) = decode(&data);
+            if valid < data.len() {
+                log::warn!(
+                    "Truncating {} corrupted bytes at offset {} of {}",
+                    data.len() - valid,
+                    valid,
+                    path.display()
+                );
+                OpenOptions::new().write(true).open(&path)?.set_len(valid as u64)?;
+                truncated_bytes += (data.len() - valid) as u64;
+            }
+        }
+        let records = current.len() as u64;
+        entries.extend(current);
+
+        let wal = Self {
+            file: Mutex::new(file),
+            path,
+            records: AtomicU64::new(records),
+            bytes: AtomicU64::new(valid as u64),
+            compactions: AtomicU64::new(0),
+            recovered: entries.len() as u64,
+            truncated_bytes,
+        };
+        Ok((wal, entries))
+    }
+
+    /// 追加一条记录
+    pub fn append(&self, entry: &StoredEntry) -> Result<(), std::io::Error> {
+        let record = encode(entry)?;
+        let mut file = self.file.lock().map_err(|_| std::io::Error::other("cache WAL lock poisoned"))?;
+        file.write_all(&record)?;
+        self.records.fetch_add(1, Ordering::SeqCst);
+        self.bytes.fetch_add(record.len() as u64, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// 压缩开始：日志重命名为 `.old` 并开始新日志。
+    /// 上次压缩未完成（`.old` 仍在）时继续追加到当前日志，由新快照一并覆盖
+    pub fn rotate(&self) -> Result<(), std::io::Error> {
+        let mut file = self.file.lock().map_err(|_| std::io::Error::other("cache WAL lock poisoned"))?;
+        let rotated = rotated_path(&self.path);
+        if rotated.exists() {
+            return Ok(());
+        }
+        fs::rename(&self.path, &rotated)?;
+        *file = open_locked(&self.path)?;
+        self.records.store(0, Ordering::SeqCst);
+        self.bytes.store(0, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// 压缩完成：新快照已写出，删除旧日志
+    pub fn finish_compaction(&self) -> Result<(), std::io::Error> {
+        match fs::remove_file(rotated_path(&self.path)) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        self.compactions.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::SeqCst)
+    }
+
+    pub fn stats(&self) -> WalStats {
+        WalStats {
+            records: self.records.load(Ordering::SeqCst),
+            bytes: self.bytes(),
+            compactions: self.compactions.load(Ordering::SeqCst),
+            recovered: self.recovered,
+            truncated_bytes: self.truncated_bytes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CacheEntry;
+
+    fn entry(n: u128) -> StoredEntry {
+        StoredEntry::Small(CacheEntry {
+            number: n,
+            factors: vec![n],
+            computation_time_ms: 1,
+            algorithm: "test".to_string(),
+        })
+    }
+
+    fn numbers(entries: &[StoredEntry]) -> Vec<u128> {
+        entries
+            .iter()
+            .map(|entry| match entry {
+                StoredEntry::Small(entry) => entry.number,
+                StoredEntry::Big(_) => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_recovery_truncates_corrupted_tail() {
+        let dir = std::env::temp_dir().join(format!("cache-wal-{}", std::process::id()));
+        let path = Wal::path_for(dir.join("cache.json"));
+        assert_eq!(path, dir.join("cache.json.wal"));
+
+        let (wal, replayed) = Wal::open(&path).unwrap();
+        assert!(replayed.is_empty());
+        // 日志已被占用时不能再次打开
+        assert_eq!(Wal::open(&path).err().map(|e| e.kind()), Some(ErrorKind::WouldBlock));
+        for n in [2, 3, 5] {
+            wal.append(&entry(n)).unwrap();
+        }
+        drop(wal);
+
+        // 模拟写到一半崩溃：最后一条记录不完整
+        let length = fs::metadata(&path).unwrap().len();
+        OpenOptions::new().write(true).open(&path).unwrap().set_len(length - 3).unwrap();
+        let (wal, replayed) = Wal::open(&path).unwrap();
+        assert_eq!(numbers(&replayed), vec![2, 3]);
+        assert!(wal.stats().truncated_bytes > 0);
+
+        // 截断后继续追加的记录可以正常读出；压缩开始后的记录写入新日志
+        wal.append(&entry(7)).unwrap();
+        wal.rotate().unwrap();
+        wal.append(&entry(11)).unwrap();
+        drop(wal);
+        let (wal, replayed) = Wal::open(&path).unwrap();
+        assert_eq!(numbers(&replayed), vec![2, 3, 7, 11]);
+        wal.finish_compaction().unwrap();
+        drop(wal);
+        let (_, replayed) = Wal::open(&path).unwrap();
+        assert_eq!(numbers(&replayed), vec![11]);
+
+        // 校验和不匹配的记录同样被截断
+        let mut data = fs::read(&path).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+        fs::write(&path, &data).unwrap();
+        let (wal, replayed) = Wal::open(&path).unwrap();
+        assert!(replayed.is_empty());
+        assert_eq!(wal.bytes(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,4 @@
+pub mod pool;
+
+// 重新导出
+pub use pool::{ComputeError, ComputePool};
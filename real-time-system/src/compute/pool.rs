@@ -0,0 +1,268 @@
+// 专用计算线程池
+// 分解是 CPU 密集型任务，直接在 async 处理函数中执行会阻塞整个 actix worker，
+// 使排在后面的缓存查询也被拖慢。计算任务统一提交到计算线程，处理函数只等待结果。
+// 线程数可在运行时调整：扩容时立即创建新线程，缩容时多余的线程在空闲后自行退出。
+// 等待执行的任务数有上限，队列已满时立即返回错误。
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// 空闲线程检查是否需要退出的间隔
+const RETIRE_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 默认的等待队列长度上限（计算请求和任务在进入线程池前已各自限流，正常情况下远达不到）
+const DEFAULT_MAX_QUEUED: usize = 256;
+
+type Task = Box<dyn FnOnce() + Send + 'static>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ComputeError {
+    #[error("compute pool has shut down")]
+    ShutDown,
+
+    #[error("compute queue is full ({0} tasks waiting)")]
+    QueueFull(usize),
+
+    #[error("compute task panicked")]
+    Panicked,
+}
+
+/// 计算线程共享的状态
+#[derive(Debug)]
+struct Shared {
+    receiver: Mutex<Receiver<Task>>,
+    /// 目标线程数
+    target: AtomicUsize,
+    /// 实际存活的线程数
+    live: AtomicUsize,
+    /// 已提交但尚未开始执行的任务数
+    queued: AtomicUsize,
+    /// 等待执行的任务数上限
+    max_queued: usize,
+    /// 正在执行的任务数
+    busy: AtomicUsize,
+}
+
+impl Shared {
+    /// 存活线程多于目标时，让调用线程退出（返回 true）
+    fn try_retire(&self) -> bool {
+        let mut live = self.live.load(Ordering::SeqCst);
+        while live > self.target.load(Ordering::SeqCst) {
+            match self.live.compare_exchange(live, live - 1, Ordering::SeqCst, Ordering::SeqCst) {
+                Ok(_) => return true,
+                Err(actual) => live = actual,
+            }
+        }
+        false
+    }
+}
+
+#[derive(Debug)]
+pub struct ComputePool {
+    sender: Sender<Task>,
+    shared: Arc<Shared>,
+    /// 串行化扩容操作，并为新线程编号
+    next_thread_id: Mutex<usize>,
+}
+
+impl ComputePool {
+    /// 创建包含 threads 个计算线程的线程池（至少 1 个）
+    pub fn new(threads: usize) -> Self {
+        Self::with_queue_limit(threads, DEFAULT_MAX_QUEUED)
+    }
+
+    /// 创建线程池，最多 max_queued 个任务等待执行
+    pub fn with_queue_limit(threads: usize, max_queued: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Task>();
+        let pool = Self {
+            sender,
+            shared: Arc::new(Shared {
+                receiver: Mutex::new(receiver),
+                target: AtomicUsize::new(0),
+                live: AtomicUsize::new(0),
+                queued: AtomicUsize::new(0),
+                max_queued,
+                busy: AtomicUsize::new(0),
+            }),
+            next_thread_id: Mutex::new(0),
+        };
+        pool.resize(threads);
+        log::info!("Compute pool started with {} threads", pool.target_threads());
+        pool
+    }
+
+    /// 调整线程数（至少 1 个）；缩容时正在执行的任务不受影响，多余线程空闲后退出
+    pub fn resize(&self, threads: usize) {
+        let threads = threads.max(1);
+        let Ok(mut next_id) = self.next_thread_id.lock() else {
+            return;
+        };
+        let previous = self.shared.target.swap(threads, Ordering::SeqCst);
+        if previous != threads && previous != 0 {
+            log::info!("Resizing compute pool: {} -> {} threads", previous, threads);
+        }
+
+        while self.shared.live.load(Ordering::SeqCst) < threads {
+            let shared = Arc::clone(&self.shared);
+            let spawned = thread::Builder::new()
+                .name(format!("compute-{}", *next_id))
+                .spawn(move || worker_loop(shared));
+            match spawned {
+                Ok(_) => {
+                    self.shared.live.fetch_add(1, Ordering::SeqCst);
+                    *next_id += 1;
+                }
+                Err(e) => {
+                    log::error!("Failed to spawn compute thread: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+
+    /// 在计算线程中执行 f 并等待结果（等待期间不占用调用方线程）；等待队列已满时返回 `QueueFull`
+    pub async fn run<T, F>(&self, f: F) -> Result<T, ComputeError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let task: Task = Box::new(move || {
+            // 接收方已放弃等待时直接丢弃结果
+            let _ = tx.send(f());
+        });
+
+        let max_queued = self.shared.max_queued;
+        self.shared
+            .queued
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |queued| (queued < max_queued).then_some(queued + 1))
+            .map_err(|_| ComputeError::QueueFull(max_queued))?;
+        if self.sender.send(task).is_err() {
+            self.shared.queued.fetch_sub(1, Ordering::SeqCst);
+            return Err(ComputeError::ShutDown);
+        }
+        // 任务 panic 时 tx 被丢弃
+        rx.await.map_err(|_| ComputeError::Panicked)
+    }
+
+    /// 目标线程数
+    pub fn target_threads(&self) -> usize {
+        self.shared.target.load(Ordering::SeqCst)
+    }
+
+    /// 实际存活的线程数（缩容后会短暂高于目标）
+    pub fn live_threads(&self) -> usize {
+        self.shared.live.load(Ordering::SeqCst)
+    }
+
+    /// 排队等待的任务数
+    pub fn queued(&self) -> usize {
+        self.shared.queued.load(Ordering::SeqCst)
+    }
+
+    /// 正在执行的任务数
+    pub fn busy(&self) -> usize {
+        self.shared.busy.load(Ordering::SeqCst)
+    }
+}
+
+fn worker_loop(shared: Arc<Shared>) {
+    loop {
+        if shared.try_retire() {
+            log::debug!("Compute thread {} retired", thread::current().name().unwrap_or("?"));
+            return;
+        }
+
+        // 只在取任务时持有锁；超时后重新检查是否需要退出
+        let task = match shared.receiver.lock() {
+            Ok(receiver) => receiver.recv_timeout(RETIRE_CHECK_INTERVAL),
+            Err(_) => Err(RecvTimeoutError::Disconnected),
+        };
+        let task = match task {
+            Ok(task) => task,
+            Err(RecvTimeoutError::Timeout) => continue,
+            // 发送端已关闭，线程池被丢弃
+            Err(RecvTimeoutError::Disconnected) => {
+                shared.live.fetch_sub(1, Ordering::SeqCst);
+                return;
+            }
+        };
+
+        shared.queued.fetch_sub(1, Ordering::SeqCst);
+        shared.busy.fetch_add(1, Ordering::SeqCst);
+        // 单个任务 panic 不应使计算线程退出
+        if panic::catch_unwind(AssertUnwindSafe(task)).is_err() {
+            log::error!("Compute task panicked");
+        }
+        shared.busy.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_compute_pool_runs_tasks() {
+        let pool = ComputePool::new(2);
+        assert_eq!(pool.target_threads(), 2);
+        assert_eq!(pool.run(|| 6 * 7).await.unwrap(), 42);
+
+        // panic 的任务返回错误，线程池仍可继续使用
+        let result = pool.run(|| -> u32 { panic!("boom") }).await;
+        assert!(matches!(result, Err(ComputeError::Panicked)));
+        let on_compute_thread = pool.run(|| thread::current().name().unwrap_or("").starts_with("compute-")).await;
+        assert!(on_compute_thread.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_compute_pool_resize() {
+        let pool = ComputePool::new(1);
+        pool.resize(4);
+        assert_eq!(pool.target_threads(), 4);
+        assert_eq!(pool.live_threads(), 4);
+
+        // 缩容后空闲线程在下一次检查时退出
+        pool.resize(2);
+        assert_eq!(pool.target_threads(), 2);
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while pool.live_threads() > 2 && std::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(pool.live_threads(), 2);
+        assert_eq!(pool.run(|| 1 + 1).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_compute_pool_queue_limit() {
+        let pool = Arc::new(ComputePool::with_queue_limit(1, 1));
+        let (release, blocked) = std::sync::mpsc::channel::<()>();
+        let running = {
+            let pool = Arc::clone(&pool);
+            tokio::spawn(async move { pool.run(move || blocked.recv().ok()).await })
+        };
+        while pool.busy() == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        // 线程被占用，第一个任务排队，第二个任务因队列已满被拒绝
+        let queued = {
+            let pool = Arc::clone(&pool);
+            tokio::spawn(async move { pool.run(|| 1).await })
+        };
+        while pool.queued() == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert!(matches!(pool.run(|| 2).await, Err(ComputeError::QueueFull(1))));
+
+        release.send(()).unwrap();
+        assert!(running.await.unwrap().is_ok());
+        assert_eq!(queued.await.unwrap().unwrap(), 1);
+        assert_eq!(pool.queued(), 0);
+    }
+}
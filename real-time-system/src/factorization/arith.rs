@@ -0,0 +1,167 @@
+// u128 模运算工具：256 位乘积与 Montgomery 乘法
+// 128 位数的乘积会溢出 u128，因此使用 Montgomery 形式（R = 2^128）避免大数除法
+
+const LOW_MASK: u128 = u64::MAX as u128;
+
+/// 128 × 128 → 256 位乘法，返回 (高 128 位, 低 128 位)
+#[inline]
+pub fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    let (a1, a0) = (a >> 64, a & LOW_MASK);
+    let (b1, b0) = (b >> 64, b & LOW_MASK);
+
+    let p00 = a0 * b0;
+    let p01 = a0 * b1;
+    let p10 = a1 * b0;
+    let p11 = a1 * b1;
+
+    // 中间项最多为 3 * (2^64 - 1)，不会溢出
+    let mid = (p00 >> 64) + (p01 & LOW_MASK) + (p10 & LOW_MASK);
+    let lo = (p00 & LOW_MASK) | (mid << 64);
+    let hi = p11 + (p01 >> 64) + (p10 >> 64) + (mid >> 64);
+    (hi, lo)
+}
+
+/// (a + b) mod m，要求 a, b < m
+#[inline]
+pub fn add_mod(a: u128, b: u128, m: u128) -> u128 {
+    let (sum, overflow) = a.overflowing_add(b);
+    if overflow || sum >= m {
+        sum.wrapping_sub(m)
+    } else {
+        sum
+    }
+}
+
+pub fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    a
+}
+
+/// 奇数模 n 下的 Montgomery 运算（R = 2^128）
+#[derive(Debug, Clone, Copy)]
+pub struct Montgomery {
+    n: u128,
+    /// -n^{-1} mod 2^128
+    n_neg_inv: u128,
+    /// R^2 mod n
+    r2: u128,
+    /// R mod n，即 Montgomery 形式的 1
+    one: u128,
+}
+
+impl Montgomery {
+    /// 创建模 n 的 Montgomery 上下文，n 必须是大于 1 的奇数
+    pub fn new(n: u128) -> Self {
+        debug_assert!(n > 1 && n % 2 == 1, "Montgomery modulus must be odd and > 1");
+
+        // 牛顿迭代求 n^{-1} mod 2^128：每次迭代有效位数翻倍（3 → 6 → ... → 192）
+        let mut inv = n;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u128.wrapping_sub(n.wrapping_mul(inv)));
+        }
+
+        let one = (u128::MAX % n + 1) % n;
+        let mut r2 = one;
+        for _ in 0..128 {
+            r2 = add_mod(r2, r2, n);
+        }
+
+        Self {
+            n,
+            n_neg_inv: inv.wrapping_neg(),
+            r2,
+            one,
+        }
+    }
+
+    /// Montgomery 形式的 1
+    pub fn one(&self) -> u128 {
+        self.one
+    }
+
+    /// Montgomery 约简：返回 T * R^{-1} mod n
+    #[inline]
+    fn reduce(&self, hi: u128, lo: u128) -> u128 {
+        let m = lo.wrapping_mul(self.n_neg_inv);
+        let (mh, _) = mul_wide(m, self.n);
+        // lo + ml ≡ 0 (mod 2^128)，只有 lo 非零时才会产生进位
+        let carry = (lo != 0) as u128;
+        let (t, overflow1) = hi.overflowing_add(mh);
+        let (t, overflow2) = t.overflowing_add(carry);
+        if overflow1 || overflow2 || t >= self.n {
+            t.wrapping_sub(self.n)
+        } else {
+            t
+        }
+    }
+
+    /// 普通形式转为 Montgomery 形式
+    #[inline]
+    pub fn encode(&self, a: u128) -> u128 {
+        self.mul(a % self.n, self.r2)
+    }
+
+    /// Montgomery 形式下的乘法
+    #[inline]
+    pub fn mul(&self, a: u128, b: u128) -> u128 {
+        let (hi, lo) = mul_wide(a, b);
+        self.reduce(hi, lo)
+    }
+
+    #[inline]
+    pub fn add(&self, a: u128, b: u128) -> u128 {
+        add_mod(a, b, self.n)
+    }
+
+    /// Montgomery 形式下的快速幂（base 为 Montgomery 形式）
+    pub fn pow(&self, mut base: u128, mut exp: u128) -> u128 {
+        let mut result = self.one;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = self.mul(result, base);
+            }
+            base = self.mul(base, base);
+            exp >>= 1;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul_wide() {
+        assert_eq!(mul_wide(u128::MAX, u128::MAX), (u128::MAX - 1, 1));
+        assert_eq!(mul_wide(1 << 64, 1 << 64), (1, 0));
+        assert_eq!(mul_wide(12345, 67890), (0, 12345 * 67890));
+    }
+
+    #[test]
+    fn test_montgomery_mul() {
+        for &n in &[97u128, (1u128 << 64) + 13, u128::MAX, (1u128 << 127) + 1] {
+            let mont = Montgomery::new(n);
+            let a = n / 3 + 7;
+            let b = n / 5 + 11;
+            let expected = {
+                // 用加倍法计算 a * b mod n 作为参照
+                let (mut acc, mut x, mut y) = (0u128, a % n, b);
+                while y > 0 {
+                    if y & 1 == 1 {
+                        acc = add_mod(acc, x, n);
+                    }
+                    x = add_mod(x, x, n);
+                    y >>= 1;
+                }
+                acc
+            };
+            let product = mont.mul(mont.encode(a), mont.encode(b));
+            assert_eq!(product, mont.encode(expected), "modulus {}", n);
+        }
+    }
+}
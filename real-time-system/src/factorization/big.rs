@@ -0,0 +1,194 @@
+// 任意精度整数分解：试除 → Pollard rho → ECM
+// 用于超过 128 位的数（约 60 位十进制以内的研究用途）
+
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, ToPrimitive, Zero};
+
+use super::budget::Budget;
+use super::ecm::{self, EcmParams};
+use super::optimized::factorize_optimized;
+use super::primality::is_prime_big;
+use super::result::Factorization;
+
+/// 试除法的上界
+const TRIAL_DIVISION_BOUND: u64 = 10_000;
+
+/// rho 的最大迭代次数，约能找到 9 位以内的因子，超过后交给 ECM
+const RHO_MAX_ITERATIONS: u64 = 1 << 16;
+
+/// Brent 算法中每批累乘的步数
+const BATCH_SIZE: u64 = 128;
+
+/// 有上限的 Brent–Pollard rho，返回 n 的一个非平凡因子
+fn rho_big(n: &BigUint, c: u32, max_iterations: u64, budget: &Budget) -> Option<BigUint> {
+    let one = BigUint::one();
+    let c = BigUint::from(c);
+    let f = |x: &BigUint| (x * x + &c) % n;
+
+    let mut y = BigUint::from(2u32);
+    let mut x = y.clone();
+    let mut ys = y.clone();
+    let mut g = one.clone();
+    let mut q = one.clone();
+    let mut r = 1u64;
+    let mut iterations = 0u64;
+
+    while g == one {
+        x = y.clone();
+        for _ in 0..r {
+            y = f(&y);
+        }
+
+        let mut k = 0;
+        while k < r && g == one {
+            if budget.is_exhausted() {
+                return None;
+            }
+            ys = y.clone();
+            let steps = BATCH_SIZE.min(r - k);
+            for _ in 0..steps {
+                y = f(&y);
+                let diff = if x > y { &x - &y } else { &y - &x };
+                q = q * diff % n;
+            }
+            g = q.gcd(n);
+            k += steps;
+        }
+
+        iterations += 2 * r;
+        if g == one && iterations > max_iterations {
+            return None;
+        }
+        r *= 2;
+    }
+
+    // 批量累乘导致 gcd 变为 n 时，逐步回溯
+    if &g == n {
+        loop {
+            ys = f(&ys);
+            let diff = if x > ys { &x - &ys } else { &ys - &x };
+            g = diff.gcd(n);
+            if g > one {
+                break;
+            }
+        }
+    }
+
+    if &g == n {
+        None
+    } else {
+        Some(g)
+    }
+}
+
+/// 寻找合数 n 的一个非平凡因子：先用有限次数的 rho，再用 ECM
+fn find_factor(n: &BigUint, schedule: &[EcmParams], budget: &Budget) -> Option<BigUint> {
+    rho_big(n, 1, RHO_MAX_ITERATIONS, budget).or_else(|| ecm::ecm_find_factor(n, schedule, budget))
+}
+
+/// 使用默认 ECM 参数表分解任意精度整数
+pub fn factorize_big(n: &BigUint, budget: &Budget) -> Factorization<BigUint> {
+    factorize_big_with(n, &ecm::DEFAULT_SCHEDULE, budget)
+}
+
+/// 分解任意精度整数；ECM 参数表用尽或预算耗尽时仍未分解的合数放入 `unfactored_cofactors`
+pub fn factorize_big_with(n: &BigUint, schedule: &[EcmParams], budget: &Budget) -> Factorization<BigUint> {
+    let mut primes = Vec::new();
+    let mut cofactors = Vec::new();
+    let mut n = n.clone();
+
+    if n.is_zero() {
+        return Factorization::partial(primes, vec![n]);
+    }
+
+    // 1. 试除剥离小素因子
+    for p in ecm::primes_up_to(TRIAL_DIVISION_BOUND) {
+        while (&n % p).is_zero() {
+            primes.push(BigUint::from(p));
+            n /= p;
+        }
+    }
+
+    // 2. 逐个拆分剩余的合数
+    let mut pending = vec![n];
+    while let Some(m) = pending.pop() {
+        if m.is_one() {
+            continue;
+        }
+        if is_prime_big(&m) {
+            primes.push(m);
+        } else if let Some(small) = m.to_u64() {
+            // u64 范围内的 rho 总是很快
+            let result = factorize_optimized(small as u128, budget);
+            primes.extend(result.proven_primes.into_iter().map(BigUint::from));
+            cofactors.extend(result.unfactored_cofactors.into_iter().map(BigUint::from));
+        } else {
+            match find_factor(&m, schedule, budget) {
+                Some(d) => {
+                    let rest = &m / &d;
+                    pending.push(d);
+                    pending.push(rest);
+                }
+                None => cofactors.push(m),
+            }
+        }
+    }
+
+    Factorization::partial(primes, cofactors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigUint {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_factorize_big_small_factors() {
+        // 2^130 - 2 = 2 · 3 · 11 · 131 · 2731 · 409891 · 7623851 · 145295143558111
+        let n = (BigUint::one() << 130u32) - 2u32;
+        let result = factorize_big(&n, &Budget::unlimited());
+        assert!(result.complete);
+        assert_eq!(result.proven_primes.iter().product::<BigUint>(), n);
+        assert!(result.proven_primes.iter().all(is_prime_big));
+    }
+
+    #[test]
+    fn test_factorize_big_needs_ecm() {
+        // 两个 12 位素数与一个 20 位素数的乘积（约 44 位十进制）
+        let p = big("100000000003");
+        let q = big("999999999989");
+        let r = big("18446744073709551557");
+        let n = &p * &q * &r;
+        let result = factorize_big(&n, &Budget::unlimited());
+        assert!(result.complete);
+        assert_eq!(result.proven_primes, vec![p, q, r]);
+    }
+
+    #[test]
+    fn test_factorize_big_gives_up() {
+        // 空参数表时 ECM 不运行，两个 20 位素数的乘积无法分解
+        let r = big("18446744073709551557");
+        let n = &r * &r;
+        let result = factorize_big_with(&n, &[], &Budget::unlimited());
+        assert!(!result.complete);
+        assert_eq!(result.unfactored_cofactors, vec![n]);
+    }
+
+    #[test]
+    fn test_factorize_big_cancelled() {
+        use super::super::budget::CancellationToken;
+
+        // 取消后只完成试除，其余部分作为余因子返回
+        let token = CancellationToken::new();
+        token.cancel();
+        let r = big("18446744073709551557");
+        let result = factorize_big(&(&r * &r * 6u32), &Budget::new(token, None));
+        assert!(!result.complete);
+        assert_eq!(result.proven_primes, vec![big("2"), big("3")]);
+        assert_eq!(result.unfactored_cofactors, vec![&r * &r]);
+    }
+}
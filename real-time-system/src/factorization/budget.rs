@@ -0,0 +1,120 @@
+// 分解的时间预算、取消与进度
+// 耗时的分解例程在检查点调用 `Budget::is_exhausted`，超时或被取消时尽快返回
+// 已找到的素因子和尚未分解的余因子（见 `Factorization::partial`）
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// 可在线程间共享的取消标记
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 返回一个在被丢弃时取消本标记的守卫（例如请求的 future 因客户端断开而被丢弃）
+    pub fn drop_guard(&self) -> DropGuard {
+        DropGuard { token: self.clone() }
+    }
+}
+
+/// 丢弃时触发取消
+#[derive(Debug)]
+pub struct DropGuard {
+    token: CancellationToken,
+}
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+/// 当前阶段及完成度（total 为 0 表示总量未知）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Progress {
+    pub stage: &'static str,
+    pub done: u64,
+    pub total: u64,
+}
+
+/// 一次分解的预算：取消标记 + 可选的截止时间，并记录最近一次报告的进度
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    token: CancellationToken,
+    deadline: Option<Instant>,
+    progress: Arc<Mutex<Option<Progress>>>,
+}
+
+impl Budget {
+    /// 不限时的预算（仍可通过 `cancel` 取消）
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn new(token: CancellationToken, deadline: Option<Instant>) -> Self {
+        Self {
+            token,
+            deadline,
+            progress: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// 取消本预算（与持有同一取消标记的预算共享）
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// 已被取消或超过截止时间
+    pub fn is_exhausted(&self) -> bool {
+        self.token.is_cancelled() || self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// 报告当前进度
+    pub fn report(&self, stage: &'static str, done: u64, total: u64) {
+        if let Ok(mut progress) = self.progress.lock() {
+            *progress = Some(Progress { stage, done, total });
+        }
+    }
+
+    /// 最近一次报告的进度
+    pub fn progress(&self) -> Option<Progress> {
+        self.progress.lock().ok().and_then(|progress| progress.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_budget_exhaustion() {
+        assert!(!Budget::unlimited().is_exhausted());
+
+        let token = CancellationToken::new();
+        let budget = Budget::new(token.clone(), None);
+        {
+            let _guard = token.drop_guard();
+            assert!(!budget.is_exhausted());
+        }
+        assert!(budget.is_exhausted());
+
+        let expired = Budget::new(CancellationToken::new(), Some(Instant::now() - Duration::from_millis(1)));
+        assert!(expired.is_exhausted());
+    }
+}
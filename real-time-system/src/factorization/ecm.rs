@@ -0,0 +1,318 @@
+// Lenstra 椭圆曲线分解（ECM）
+// 使用 Montgomery 曲线 By² = x³ + Ax² + x 与 Suyama 参数化，只计算 X/Z 射影坐标，
+// stage 1 依次乘以不超过 B1 的素数幂，stage 2 使用步长 2D 的标准续接覆盖 (B1, B2] 中的素数
+
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::One;
+
+use super::budget::Budget;
+
+/// 一组 ECM 参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EcmParams {
+    /// stage 1 界
+    pub b1: u64,
+    /// stage 2 界
+    pub b2: u64,
+    /// 曲线数量
+    pub curves: usize,
+}
+
+/// 按目标因子位数递增的默认参数表（B1 参考 GMP-ECM 推荐值，B2 取 50·B1 以控制筛法开销）
+pub const DEFAULT_SCHEDULE: [EcmParams; 4] = [
+    EcmParams { b1: 2_000, b2: 100_000, curves: 25 },       // 约 15 位因子
+    EcmParams { b1: 11_000, b2: 550_000, curves: 90 },      // 约 20 位因子
+    EcmParams { b1: 50_000, b2: 2_500_000, curves: 300 },   // 约 25 位因子
+    EcmParams { b1: 250_000, b2: 12_500_000, curves: 700 }, // 约 30 位因子
+];
+
+/// stage 2 预计算的倍点数量，每轮覆盖 2·D 的区间
+const STAGE2_D: u64 = 100;
+
+/// Suyama 参数化的起始 σ（σ ≥ 6）
+const FIRST_SIGMA: u64 = 6;
+
+/// stage 1 每处理多少个素数、stage 2 每前进多少步检查一次预算
+const CHECK_INTERVAL: usize = 256;
+
+/// X/Z 射影坐标下的点
+#[derive(Debug, Clone)]
+struct Point {
+    x: BigUint,
+    z: BigUint,
+}
+
+/// 模 n 的 Montgomery 曲线，a24 = (A + 2) / 4
+struct Curve<'a> {
+    n: &'a BigUint,
+    a24: BigUint,
+}
+
+impl Curve<'_> {
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        (a * b) % self.n
+    }
+
+    fn add(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        let sum = a + b;
+        if &sum >= self.n {
+            sum - self.n
+        } else {
+            sum
+        }
+    }
+
+    /// (a - b) mod n，要求 a, b < n
+    fn sub(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        if a >= b {
+            a - b
+        } else {
+            a + self.n - b
+        }
+    }
+
+    /// 倍点 [2]P
+    fn double(&self, p: &Point) -> Point {
+        let sum = self.add(&p.x, &p.z);
+        let diff = self.sub(&p.x, &p.z);
+        let t1 = self.mul(&sum, &sum);
+        let t2 = self.mul(&diff, &diff);
+        let t = self.sub(&t1, &t2);
+        Point {
+            x: self.mul(&t1, &t2),
+            z: self.mul(&t, &self.add(&t2, &self.mul(&self.a24, &t))),
+        }
+    }
+
+    /// 差分加法 P + Q，需要已知 P - Q
+    fn add_points(&self, p: &Point, q: &Point, diff: &Point) -> Point {
+        let u = self.mul(&self.sub(&p.x, &p.z), &self.add(&q.x, &q.z));
+        let v = self.mul(&self.add(&p.x, &p.z), &self.sub(&q.x, &q.z));
+        let s = self.add(&u, &v);
+        let d = self.sub(&u, &v);
+        Point {
+            x: self.mul(&diff.z, &self.mul(&s, &s)),
+            z: self.mul(&diff.x, &self.mul(&d, &d)),
+        }
+    }
+
+    /// Montgomery 阶梯计算 [k]P（k ≥ 1）
+    fn multiply(&self, p: &Point, k: u64) -> Point {
+        if k == 1 {
+            return p.clone();
+        }
+        let mut r0 = p.clone();
+        let mut r1 = self.double(p);
+        for bit in (0..63 - k.leading_zeros()).rev() {
+            if (k >> bit) & 1 == 1 {
+                r0 = self.add_points(&r1, &r0, p);
+                r1 = self.double(&r1);
+            } else {
+                r1 = self.add_points(&r0, &r1, p);
+                r0 = self.double(&r0);
+            }
+        }
+        r0
+    }
+}
+
+/// 单条曲线的结果
+enum CurveOutcome {
+    /// 找到非平凡因子
+    Factor(BigUint),
+    /// 本曲线失败，换下一条
+    Failed,
+    /// 预算耗尽，中途放弃
+    Interrupted,
+}
+
+/// 若 g 是 n 的非平凡因子则返回它
+fn nontrivial(g: BigUint, n: &BigUint) -> Option<BigUint> {
+    if g > BigUint::one() && &g < n {
+        Some(g)
+    } else {
+        None
+    }
+}
+
+/// 埃氏筛，返回不超过 limit 的所有素数
+pub fn primes_up_to(limit: u64) -> Vec<u64> {
+    if limit < 2 {
+        return Vec::new();
+    }
+    let limit = limit as usize;
+    let mut is_composite = vec![false; limit + 1];
+    let mut primes = Vec::new();
+    for i in 2..=limit {
+        if !is_composite[i] {
+            primes.push(i as u64);
+            let mut j = i * i;
+            while j <= limit {
+                is_composite[j] = true;
+                j += i;
+            }
+        }
+    }
+    primes
+}
+
+/// 在由 σ 确定的曲线上运行 stage 1 和 stage 2
+fn run_curve(n: &BigUint, sigma: u64, params: &EcmParams, primes: &[u64], budget: &Budget) -> CurveOutcome {
+    // Suyama 参数化：u = σ² - 5, v = 4σ
+    let sigma = BigUint::from(sigma) % n;
+    let five = BigUint::from(5u32) % n;
+    let helper = Curve { n, a24: BigUint::one() };
+    let u = helper.sub(&helper.mul(&sigma, &sigma), &five);
+    let v = helper.mul(&BigUint::from(4u32), &sigma);
+
+    let u3 = helper.mul(&helper.mul(&u, &u), &u);
+    let v_minus_u = helper.sub(&v, &u);
+    let three_u_plus_v = helper.add(&helper.mul(&BigUint::from(3u32), &u), &v);
+
+    // a24 = (v - u)³ (3u + v) / (16 u³ v)
+    let numerator = helper.mul(
+        &helper.mul(&helper.mul(&v_minus_u, &v_minus_u), &v_minus_u),
+        &three_u_plus_v,
+    );
+    let denominator = helper.mul(&helper.mul(&BigUint::from(16u32), &u3), &v);
+    let inverse = match denominator.modinv(n) {
+        Some(inverse) => inverse,
+        // 分母不可逆时，gcd 可能直接给出因子
+        None => {
+            return match nontrivial(denominator.gcd(n), n) {
+                Some(g) => CurveOutcome::Factor(g),
+                None => CurveOutcome::Failed,
+            };
+        }
+    };
+
+    let curve = Curve { n, a24: helper.mul(&numerator, &inverse) };
+    let mut q = Point {
+        x: u3,
+        z: helper.mul(&helper.mul(&v, &v), &v),
+    };
+
+    // stage 1：乘以所有不超过 B1 的素数幂
+    for (i, &p) in primes.iter().take_while(|&&p| p <= params.b1).enumerate() {
+        if i.is_multiple_of(CHECK_INTERVAL) && budget.is_exhausted() {
+            return CurveOutcome::Interrupted;
+        }
+        let mut power = p;
+        while power * p <= params.b1 {
+            power *= p;
+        }
+        q = curve.multiply(&q, power);
+    }
+
+    let g = q.z.gcd(n);
+    if let Some(g) = nontrivial(g.clone(), n) {
+        return CurveOutcome::Factor(g);
+    }
+    if &g == n {
+        return CurveOutcome::Failed;
+    }
+
+    stage2(&curve, &q, params, primes, budget)
+}
+
+/// stage 2：对 (B1, B2] 中的每个素数 q，累乘 [r]Q 与 [q - r]Q 的交叉积
+fn stage2(curve: &Curve<'_>, q: &Point, params: &EcmParams, primes: &[u64], budget: &Budget) -> CurveOutcome {
+    let n = curve.n;
+    let d = STAGE2_D as usize;
+
+    // 预计算 S[δ] = [2δ]Q 及 β[δ] = X·Z，δ = 1..=D
+    let mut s = Vec::with_capacity(d + 1);
+    s.push(q.clone()); // 占位，索引从 1 开始
+    s.push(curve.double(q));
+    s.push(curve.double(&s[1]));
+    for i in 3..=d {
+        let next = curve.add_points(&s[i - 1], &s[1], &s[i - 2]);
+        s.push(next);
+    }
+    let beta: Vec<BigUint> = s.iter().map(|p| curve.mul(&p.x, &p.z)).collect();
+
+    // r 为奇数，使 q - r 为偶数
+    let step = 2 * STAGE2_D;
+    let mut r = (params.b1 | 1).max(step + 1);
+    let mut big_r = curve.multiply(q, r);
+    let mut big_t = curve.multiply(q, r - step);
+
+    let mut g = BigUint::one();
+    let mut idx = primes.partition_point(|&p| p <= r);
+    let mut steps = 0usize;
+
+    while r < params.b2 {
+        steps += 1;
+        if steps.is_multiple_of(CHECK_INTERVAL) && budget.is_exhausted() {
+            return CurveOutcome::Interrupted;
+        }
+        let alpha = curve.mul(&big_r.x, &big_r.z);
+        while idx < primes.len() && primes[idx] <= r + step {
+            let delta = ((primes[idx] - r) / 2) as usize;
+            let sd = &s[delta];
+            // (X_R - X_S)(Z_R + Z_S) - X_R·Z_R + X_S·Z_S = X_R·Z_S - X_S·Z_R
+            let cross = curve.mul(
+                &curve.sub(&big_r.x, &sd.x),
+                &curve.add(&big_r.z, &sd.z),
+            );
+            let term = curve.add(&curve.sub(&cross, &alpha), &beta[delta]);
+            g = curve.mul(&g, &term);
+            idx += 1;
+        }
+        let next = curve.add_points(&big_r, &s[d], &big_t);
+        big_t = std::mem::replace(&mut big_r, next);
+        r += step;
+    }
+
+    match nontrivial(g.gcd(n), n) {
+        Some(g) => CurveOutcome::Factor(g),
+        None => CurveOutcome::Failed,
+    }
+}
+
+/// 使用 ECM 寻找 n 的一个非平凡因子，按参数表依次增大 B1；全部失败或预算耗尽时返回 None
+///
+/// n 应为已剥离小因子的奇合数，曲线由 σ = 6, 7, ... 确定，结果可复现
+pub fn ecm_find_factor(n: &BigUint, schedule: &[EcmParams], budget: &Budget) -> Option<BigUint> {
+    let total_curves: usize = schedule.iter().map(|params| params.curves).sum();
+    let mut sigma = FIRST_SIGMA;
+    for params in schedule {
+        let primes = primes_up_to(params.b2);
+        for _ in 0..params.curves {
+            budget.report("ecm", sigma - FIRST_SIGMA, total_curves as u64);
+            match run_curve(n, sigma, params, &primes, budget) {
+                CurveOutcome::Factor(g) => {
+                    log::debug!("ECM found factor {} (B1 = {}, sigma = {})", g, params.b1, sigma);
+                    return Some(g);
+                }
+                CurveOutcome::Interrupted => return None,
+                CurveOutcome::Failed => {}
+            }
+            sigma += 1;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primes_up_to() {
+        assert_eq!(primes_up_to(30), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert_eq!(primes_up_to(100_000).len(), 9592);
+    }
+
+    #[test]
+    fn test_ecm_finds_factor() {
+        // 两个 12 位素数的乘积
+        let p = BigUint::from(100_000_000_003u64);
+        let q = BigUint::from(999_999_999_989u64);
+        let n = &p * &q;
+        let params = [EcmParams { b1: 2_000, b2: 100_000, curves: 60 }];
+        let factor = ecm_find_factor(&n, &params, &Budget::unlimited()).expect("ECM should find a 12-digit factor");
+        assert!(factor == p || factor == q);
+    }
+}
@@ -0,0 +1,159 @@
+// GF(2) 上的结构化高斯消元，用于二次筛的线性代数步骤
+// 先反复删除只出现一次的列（及其所在行），压缩矩阵后再做稠密消元
+
+/// 稠密位向量
+#[derive(Debug, Clone)]
+struct BitRow {
+    words: Vec<u64>,
+}
+
+impl BitRow {
+    fn new(bits: usize) -> Self {
+        Self { words: vec![0; bits.div_ceil(64)] }
+    }
+
+    fn set(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    fn flip(&mut self, i: usize) {
+        self.words[i / 64] ^= 1 << (i % 64);
+    }
+
+    fn get(&self, i: usize) -> bool {
+        self.words[i / 64] >> (i % 64) & 1 == 1
+    }
+
+    fn xor_with(&mut self, other: &BitRow) {
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a ^= b;
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, &w)| {
+            (0..64).filter(move |b| w >> b & 1 == 1).map(move |b| i * 64 + b)
+        })
+    }
+}
+
+/// 结构化预处理：反复删除权重为 1 的列所在的行（这些行不可能出现在任何相关组合中）
+/// 返回保留的行下标
+fn remove_singletons(rows: &[Vec<usize>], ncols: usize) -> Vec<usize> {
+    let mut alive = vec![true; rows.len()];
+    let mut weight = vec![0usize; ncols];
+    for row in rows {
+        for &c in row {
+            weight[c] += 1;
+        }
+    }
+
+    loop {
+        let mut removed = false;
+        for (i, row) in rows.iter().enumerate() {
+            if alive[i] && row.iter().any(|&c| weight[c] == 1) {
+                alive[i] = false;
+                removed = true;
+                for &c in row {
+                    weight[c] -= 1;
+                }
+            }
+        }
+        if !removed {
+            break;
+        }
+    }
+
+    (0..rows.len()).filter(|&i| alive[i]).collect()
+}
+
+/// 在 GF(2) 上寻找行的线性相关组合
+///
+/// `rows[i]` 为第 i 行中系数为 1 的列下标（每列最多出现一次），返回若干行下标集合，
+/// 每个集合中所有行的和为零向量。
+pub fn find_dependencies(rows: &[Vec<usize>], ncols: usize) -> Vec<Vec<usize>> {
+    let kept = remove_singletons(rows, ncols);
+
+    // 压缩列：只保留仍被使用的列
+    let mut col_map = vec![usize::MAX; ncols];
+    let mut dense_cols = 0;
+    for &i in &kept {
+        for &c in &rows[i] {
+            if col_map[c] == usize::MAX {
+                col_map[c] = dense_cols;
+                dense_cols += 1;
+            }
+        }
+    }
+
+    let n = kept.len();
+    let mut matrix: Vec<BitRow> = Vec::with_capacity(n);
+    let mut history: Vec<BitRow> = Vec::with_capacity(n);
+    for (k, &i) in kept.iter().enumerate() {
+        let mut row = BitRow::new(dense_cols);
+        for &c in &rows[i] {
+            row.flip(col_map[c]);
+        }
+        matrix.push(row);
+        let mut h = BitRow::new(n);
+        h.set(k);
+        history.push(h);
+    }
+
+    // 稠密高斯消元：rank 之后的行全部化为零，对应的历史即为相关组合
+    let mut rank = 0;
+    for col in 0..dense_cols {
+        let Some(pivot) = (rank..n).find(|&r| matrix[r].get(col)) else {
+            continue;
+        };
+        matrix.swap(rank, pivot);
+        history.swap(rank, pivot);
+        for r in rank + 1..n {
+            if matrix[r].get(col) {
+                let (top, bottom) = matrix.split_at_mut(r);
+                bottom[0].xor_with(&top[rank]);
+                let (top, bottom) = history.split_at_mut(r);
+                bottom[0].xor_with(&top[rank]);
+            }
+        }
+        rank += 1;
+    }
+
+    (rank..n)
+        .filter(|&r| matrix[r].is_zero())
+        .map(|r| history[r].ones().map(|k| kept[k]).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_dependencies() {
+        let rows = vec![
+            vec![0, 1],    // r0
+            vec![1, 2],    // r1
+            vec![0, 2],    // r2 = r0 + r1
+            vec![3],       // 单独出现的列，会被预处理删除
+            vec![0, 1],    // r4 = r0
+        ];
+        let deps = find_dependencies(&rows, 4);
+        assert!(!deps.is_empty());
+        for dep in &deps {
+            assert!(!dep.contains(&3));
+            // 每列出现次数必须为偶数
+            let mut parity = [0u32; 4];
+            for &r in dep {
+                for &c in &rows[r] {
+                    parity[c] ^= 1;
+                }
+            }
+            assert_eq!(parity, [0; 4]);
+        }
+    }
+}
@@ -1,4 +1,21 @@
+pub mod arith;
+pub mod budget;
 pub mod simple;
+pub mod optimized;
+pub mod primality;
+pub mod result;
+pub mod registry;
+pub mod ecm;
+pub mod big;
+pub mod linalg;
+pub mod siqs;
 
 // 重新导出
-pub use simple::factorize;
\ No newline at end of file
+pub use simple::factorize;
+pub use optimized::factorize_optimized;
+pub use primality::{is_prime, is_prime_big};
+pub use budget::{Budget, CancellationToken, Progress};
+pub use result::Factorization;
+pub use registry::{estimate_big_cost_by, factorize_big_by, FactorizerRegistry, BIG_ALGORITHMS, INTERACTIVE_BIG_ALGORITHMS};
+pub use big::factorize_big;
+pub use siqs::factorize_siqs;
@@ -1,5 +1,160 @@
-// 添加更高效的分解算法
-pub fn factorize_optimized(n: u64) -> Vec<u64> {
-    // 实现更高效的试除法
-    // 比如只检查到 sqrt(n)，跳过偶数等
-}
\ No newline at end of file
+// 基于 Pollard rho（Brent 变体）的高效分解算法
+// 对于两个约 32 位因子组成的 64 位半素数，试除法需要数秒，而 rho 只需毫秒级
+
+use super::arith::{gcd, Montgomery};
+use super::budget::Budget;
+use super::primality::is_prime;
+use super::result::Factorization;
+
+/// 先用试除法剥离的小素数
+const SMALL_PRIMES: [u128; 15] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47];
+
+/// Brent 算法中每批累乘的步数（减少 gcd 调用次数）
+const BATCH_SIZE: u128 = 128;
+
+/// Brent 变体的 Pollard rho，返回 n 的一个非平凡因子（预算耗尽时返回 None）
+/// n 必须是大于 3 的奇合数；迭代在 Montgomery 形式下进行，结果不受影响
+fn brent_rho(n: u128, budget: &Budget) -> Option<u128> {
+    let mont = Montgomery::new(n);
+
+    // 依次尝试不同的多项式常数 c，直到找到非平凡因子
+    for c in 1..n {
+        let c = mont.encode(c);
+        let f = |x: u128| mont.add(mont.mul(x, x), c);
+
+        let mut y = mont.encode(2);
+        let mut x = y;
+        let mut ys = y;
+        let mut g = 1u128;
+        let mut q = mont.one();
+        let mut r = 1u128;
+
+        while g == 1 {
+            x = y;
+            for _ in 0..r {
+                y = f(y);
+            }
+
+            let mut k = 0;
+            while k < r && g == 1 {
+                if budget.is_exhausted() {
+                    return None;
+                }
+                ys = y;
+                let steps = BATCH_SIZE.min(r - k);
+                for _ in 0..steps {
+                    y = f(y);
+                    q = mont.mul(q, x.abs_diff(y));
+                }
+                g = gcd(q, n);
+                k += steps;
+            }
+            r *= 2;
+        }
+
+        // 批量累乘导致 gcd 变为 n 时，逐步回溯
+        if g == n {
+            loop {
+                ys = f(ys);
+                g = gcd(x.abs_diff(ys), n);
+                if g > 1 {
+                    break;
+                }
+            }
+        }
+
+        if g != n {
+            return Some(g);
+        }
+    }
+    None
+}
+
+/// 递归分解：素数直接加入结果，合数用 rho 拆分，预算耗尽时合数放入 cofactors
+fn factor_recursive(n: u128, factors: &mut Vec<u128>, cofactors: &mut Vec<u128>, budget: &Budget) {
+    if n == 1 {
+        return;
+    }
+    if is_prime(n) {
+        factors.push(n);
+        return;
+    }
+    match brent_rho(n, budget) {
+        Some(d) => {
+            factor_recursive(d, factors, cofactors, budget);
+            factor_recursive(n / d, factors, cofactors, budget);
+        }
+        None => cofactors.push(n),
+    }
+}
+
+/// 使用 Pollard rho（Brent 变体）分解 n；预算耗尽时未拆开的合数放入 `unfactored_cofactors`
+pub fn factorize_optimized(mut n: u128, budget: &Budget) -> Factorization {
+    let mut factors = Vec::new();
+    let mut cofactors = Vec::new();
+
+    // 先剥离小素因子
+    for &p in &SMALL_PRIMES {
+        while n.is_multiple_of(p) {
+            factors.push(p);
+            n /= p;
+        }
+    }
+
+    factor_recursive(n, &mut factors, &mut cofactors, budget);
+    Factorization::partial(factors, cofactors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_factorize_optimized_small() {
+        assert_eq!(factorize_optimized(1, &Budget::unlimited()), Factorization::complete(vec![]));
+        assert_eq!(factorize_optimized(2, &Budget::unlimited()), Factorization::complete(vec![2]));
+        assert_eq!(factorize_optimized(84, &Budget::unlimited()), Factorization::complete(vec![2, 2, 3, 7]));
+        assert_eq!(factorize_optimized(997, &Budget::unlimited()), Factorization::complete(vec![997]));
+        assert_eq!(factorize_optimized(53 * 53 * 59, &Budget::unlimited()), Factorization::complete(vec![53, 53, 59]));
+    }
+
+    #[test]
+    fn test_factorize_optimized_semiprime() {
+        // 两个约 32 位素数的乘积
+        assert_eq!(
+            factorize_optimized(4_294_967_291 * 4_294_967_279, &Budget::unlimited()),
+            Factorization::complete(vec![4_294_967_279, 4_294_967_291])
+        );
+        assert_eq!(factorize_optimized(u64::MAX as u128, &Budget::unlimited()), Factorization::complete(vec![3, 5, 17, 257, 641, 65537, 6700417]));
+    }
+
+    #[test]
+    fn test_factorize_optimized_u128() {
+        // 超出 u64 的数：大素数乘以中等大小的因子
+        assert_eq!(
+            factorize_optimized(18_446_744_073_709_551_557 * 1_000_003, &Budget::unlimited()),
+            Factorization::complete(vec![1_000_003, 18_446_744_073_709_551_557])
+        );
+        // 2^128 - 1 = 3 · 5 · 17 · 257 · 641 · 65537 · 274177 · 6700417 · 67280421310721
+        assert_eq!(
+            factorize_optimized(u128::MAX, &Budget::unlimited()),
+            Factorization::complete(vec![3, 5, 17, 257, 641, 65537, 274177, 6700417, 67280421310721])
+        );
+    }
+
+    #[test]
+    fn test_factorize_optimized_within_timeout() {
+        use super::super::budget::CancellationToken;
+        use std::time::Instant;
+
+        // 截止时间已过：小因子照常剥离，合数余因子原样返回
+        let budget = Budget::new(CancellationToken::new(), Some(Instant::now()));
+        let result = factorize_optimized(6 * 4_294_967_291 * 4_294_967_279, &budget);
+        assert_eq!(result, Factorization::partial(vec![2, 3], vec![4_294_967_291 * 4_294_967_279]));
+    }
+
+    #[test]
+    fn test_factorize_optimized_large_prime() {
+        assert_eq!(factorize_optimized(18_446_744_073_709_551_557, &Budget::unlimited()), Factorization::complete(vec![18_446_744_073_709_551_557]));
+    }
+}
@@ -0,0 +1,349 @@
+// 素性测试
+// 使用前 12 个素数作为 Miller–Rabin 见证数，对所有 u64 给出确定性结果；
+// 前 13 个素数对 n < 3.3 × 10^24 是确定性的，更大的数使用 Baillie–PSW（以 2 为底的强素性测试 +
+// 强 Lucas 测试，目前无已知反例）
+
+use super::arith::Montgomery;
+use num_bigint::BigUint;
+use num_traits::{One, ToPrimitive};
+
+/// 试除用的小素数，同时也是 Miller–Rabin 的见证数集合
+const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// u128 使用的见证数集合
+const WITNESSES_U128: [u128; 20] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71];
+
+/// 以前 13 个素数为底的 Miller–Rabin 在此值以下是确定性的
+const DETERMINISTIC_LIMIT_U128: u128 = 3_317_044_064_679_887_385_961_981;
+
+/// 使用 128 位中间结果计算 (a * b) mod m，避免溢出
+#[inline]
+pub fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    ((a as u128 * b as u128) % m as u128) as u64
+}
+
+/// 快速幂取模
+pub fn pow_mod(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut result = 1 % m;
+    base %= m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+/// 判断 n 是否为素数
+pub fn is_prime(n: u128) -> bool {
+    match u64::try_from(n) {
+        Ok(small) => is_prime_u64(small),
+        Err(_) => is_prime_u128(n),
+    }
+}
+
+/// u64 范围内的素性测试（确定性正确）
+pub fn is_prime_u64(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    for &p in &WITNESSES {
+        if n == p {
+            return true;
+        }
+        if n.is_multiple_of(p) {
+            return false;
+        }
+    }
+    // 小于 41² 且没有小因子的一定是素数
+    if n < 41 * 41 {
+        return true;
+    }
+
+    // n - 1 = d * 2^s
+    let mut d = n - 1;
+    let mut s = 0;
+    while d.is_multiple_of(2) {
+        d /= 2;
+        s += 1;
+    }
+
+    'witness: for &a in &WITNESSES {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// 超出 u64 范围的素性测试，使用 Montgomery 乘法避免溢出
+fn is_prime_u128(n: u128) -> bool {
+    for &p in &WITNESSES_U128 {
+        if n.is_multiple_of(p) {
+            return false;
+        }
+    }
+
+    let mont = Montgomery::new(n);
+    let one = mont.one();
+    let minus_one = n - one;
+
+    let mut d = n - 1;
+    let mut s = 0;
+    while d.is_multiple_of(2) {
+        d /= 2;
+        s += 1;
+    }
+
+    'witness: for &a in &WITNESSES_U128[..13] {
+        let mut x = mont.pow(mont.encode(a), d);
+        if x == one || x == minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = mont.mul(x, x);
+            if x == minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    // 超出确定性范围时再做强 Lucas 测试（与上面以 2 为底的测试合起来即 BPSW）
+    n < DETERMINISTIC_LIMIT_U128 || is_strong_lucas_probable_prime(n)
+}
+
+/// Jacobi 符号 (a/n)，n 为正奇数
+fn jacobi(mut a: u128, mut n: u128) -> i32 {
+    let mut result = 1;
+    a %= n;
+    while a != 0 {
+        while a.is_multiple_of(2) {
+            a /= 2;
+            if n % 8 == 3 || n % 8 == 5 {
+                result = -result;
+            }
+        }
+        std::mem::swap(&mut a, &mut n);
+        if a % 4 == 3 && n % 4 == 3 {
+            result = -result;
+        }
+        a %= n;
+    }
+    if n == 1 { result } else { 0 }
+}
+
+/// Selfridge 方法选取 D（5, -7, 9, -11, ...）时的 Jacobi 符号 (D/n)。
+/// D 为奇数，用二次互反律换成 (n mod |D| / |D|)，只需要 n 模 4 和模 |D| 的余数
+fn selfridge_jacobi(d: i64, n_mod_4: u128, n_mod_d: u128) -> i32 {
+    let abs = d.unsigned_abs() as u128;
+    let mut result = jacobi(n_mod_d, abs);
+    if abs % 4 == 3 && n_mod_4 == 3 {
+        result = -result;
+    }
+    if d < 0 && n_mod_4 == 3 {
+        result = -result;
+    }
+    result
+}
+
+/// Selfridge 参数：第一个使 (D/n) = -1 的 D；遇到 (D/n) = 0 说明 n 是合数，返回 None
+fn selfridge_d(n_mod: impl Fn(u128) -> u128) -> Option<i64> {
+    let mut d: i64 = 5;
+    loop {
+        let abs = d.unsigned_abs() as u128;
+        match selfridge_jacobi(d, n_mod(4), n_mod(abs)) {
+            -1 => return Some(d),
+            // |D| 与 n 有公因子（调用方保证 n 大于 |D|）
+            0 => return None,
+            _ => d = if d > 0 { -(d + 2) } else { -d + 2 },
+        }
+    }
+}
+
+/// 强 Lucas 测试（P = 1，Q = (1 - D) / 4），n 为大于 1 的奇数
+fn is_strong_lucas_probable_prime(n: u128) -> bool {
+    let root = n.isqrt();
+    if root * root == n {
+        return false;
+    }
+    let Some(d) = selfridge_d(|m| n % m) else {
+        return false;
+    };
+
+    let mont = Montgomery::new(n);
+    let encode = |x: i64| {
+        let residue = (x.unsigned_abs() as u128) % n;
+        mont.encode(if x < 0 && residue != 0 { n - residue } else { residue })
+    };
+    let sub = |a: u128, b: u128| if a >= b { a - b } else { n - (b - a) };
+    // x / 2 mod n（Montgomery 形式下同样成立）
+    let half = |x: u128| if x.is_multiple_of(2) { x / 2 } else { x / 2 + n / 2 + 1 };
+    let (d_m, q_m) = (encode(d), encode((1 - d) / 4));
+
+    // n + 1 = k * 2^s（n 不会是 u128::MAX，它能被 3 整除）
+    let s = (n + 1).trailing_zeros();
+    let k = (n + 1) >> s;
+    let (mut u, mut v, mut qk) = (mont.one(), mont.one(), q_m);
+    for bit in (0..127 - k.leading_zeros()).rev() {
+        // U_2k = U_k V_k，V_2k = V_k² - 2Q^k
+        u = mont.mul(u, v);
+        v = sub(mont.mul(v, v), mont.add(qk, qk));
+        qk = mont.mul(qk, qk);
+        if (k >> bit) & 1 == 1 {
+            // U_{k+1} = (U_k + V_k) / 2，V_{k+1} = (D U_k + V_k) / 2
+            (u, v) = (half(mont.add(u, v)), half(mont.add(mont.mul(d_m, u), v)));
+            qk = mont.mul(qk, q_m);
+        }
+    }
+    if u == 0 || v == 0 {
+        return true;
+    }
+    for _ in 1..s {
+        v = sub(mont.mul(v, v), mont.add(qk, qk));
+        qk = mont.mul(qk, qk);
+        if v == 0 {
+            return true;
+        }
+    }
+    false
+}
+
+/// BigUint 的强 Lucas 测试，步骤同 `is_strong_lucas_probable_prime`
+fn is_strong_lucas_probable_prime_big(n: &BigUint) -> bool {
+    let root = n.sqrt();
+    if &root * &root == *n {
+        return false;
+    }
+    let Some(d) = selfridge_d(|m| (n % m).to_u128().unwrap_or_default()) else {
+        return false;
+    };
+
+    let encode = |x: i64| {
+        let residue = BigUint::from(x.unsigned_abs()) % n;
+        if x < 0 && residue != BigUint::ZERO { n - residue } else { residue }
+    };
+    let sub = |a: &BigUint, b: &BigUint| (a + n - b) % n;
+    let half = |x: BigUint| if x.bit(0) { (x + n) >> 1 } else { x >> 1 };
+    let (d_m, q_m) = (encode(d), encode((1 - d) / 4));
+
+    let n_plus_one = n + 1u32;
+    let s = n_plus_one.trailing_zeros().unwrap_or(0);
+    let k = &n_plus_one >> s;
+    let (mut u, mut v, mut qk) = (BigUint::one(), BigUint::one(), q_m.clone());
+    for bit in (0..k.bits() - 1).rev() {
+        u = &u * &v % n;
+        v = sub(&(&v * &v % n), &(&qk * 2u32 % n));
+        qk = &qk * &qk % n;
+        if k.bit(bit) {
+            let next_u = half((&u + &v) % n);
+            v = half((&d_m * &u + &v) % n);
+            u = next_u;
+            qk = &qk * &q_m % n;
+        }
+    }
+    if u == BigUint::ZERO || v == BigUint::ZERO {
+        return true;
+    }
+    for _ in 1..s {
+        v = sub(&(&v * &v % n), &(&qk * 2u32 % n));
+        qk = &qk * &qk % n;
+        if v == BigUint::ZERO {
+            return true;
+        }
+    }
+    false
+}
+
+/// 任意精度整数的素性测试：u128 范围内委托给 `is_prime`，更大的数使用前 20 个素数为底的
+/// Miller–Rabin 加强 Lucas 测试（BPSW）
+pub fn is_prime_big(n: &BigUint) -> bool {
+    if let Some(small) = n.to_u128() {
+        return is_prime(small);
+    }
+    for &p in &WITNESSES_U128 {
+        if (n % p as u64) == BigUint::ZERO {
+            return false;
+        }
+    }
+
+    let one = BigUint::one();
+    let n_minus_one = n - &one;
+    let s = n_minus_one.trailing_zeros().unwrap_or(0);
+    let d = &n_minus_one >> s;
+
+    'witness: for &a in &WITNESSES_U128 {
+        let mut x = BigUint::from(a).modpow(&d, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = &x * &x % n;
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    is_strong_lucas_probable_prime_big(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_prime_small() {
+        let primes: Vec<u128> = (0..100).filter(|&n| is_prime(n)).collect();
+        assert_eq!(
+            primes,
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97]
+        );
+    }
+
+    #[test]
+    fn test_is_prime_large() {
+        assert!(is_prime(4_294_967_291));
+        assert!(is_prime((1 << 127) - 1)); // 梅森素数 M127
+        assert!(is_prime(u128::MAX - 158)); // 最大的 u128 素数
+        assert!(!is_prime(u128::MAX));
+        assert!(!is_prime(18_446_744_073_709_551_557 * 18_446_744_073_709_551_557));
+        // 以前 13 个素数为底的最小强伪素数
+        assert!(!is_prime(3_317_044_064_679_887_385_961_981));
+        assert!(is_prime(18_446_744_073_709_551_557)); // 最大的 u64 素数
+        assert!(!is_prime(u64::MAX as u128));
+        assert!(!is_prime(4_294_967_291 * 4_294_967_279));
+        // 以 2 为底的强伪素数
+        assert!(!is_prime(3_215_031_751));
+        assert!(!is_prime(3_825_123_056_546_413_051));
+    }
+
+    #[test]
+    fn test_bpsw() {
+        // 强 Lucas 伪素数能通过 Lucas 测试，但通不过以 2 为底的强素性测试
+        for n in [5459u128, 5777, 10877, 16109, 18971] {
+            assert!(is_strong_lucas_probable_prime(n), "{}", n);
+            assert!(!is_prime(n), "{}", n);
+        }
+        let primes = [1_000_000_007u128, (1 << 127) - 1, u128::MAX - 158];
+        for n in primes {
+            assert!(is_strong_lucas_probable_prime(n), "{}", n);
+            assert!(is_strong_lucas_probable_prime_big(&BigUint::from(n)), "{}", n);
+        }
+        assert!(!is_strong_lucas_probable_prime(1_000_000_007 * 998_244_353));
+        assert!(!is_strong_lucas_probable_prime_big(&BigUint::from(1_000_000_007u128 * 998_244_353)));
+        // 超过 128 位：梅森素数 M521 与合数 M521 × M127
+        let m521 = (BigUint::one() << 521u32) - 1u32;
+        assert!(is_prime_big(&m521));
+        assert!(!is_prime_big(&(&m521 * BigUint::from((1u128 << 127) - 1))));
+    }
+}
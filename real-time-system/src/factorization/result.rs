@@ -0,0 +1,53 @@
+use serde::Serialize;
+use crate::models::json_number::{self, JsonInteger};
+
+/// 分解结果（可能是部分分解）
+///
+/// `complete` 为 true 时，`proven_primes` 的乘积等于原数；
+/// 否则 `unfactored_cofactors` 中保存尚未分解的合数余因子。
+/// 默认使用 u128，超过 128 位的数使用 `BigUint`。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(bound(serialize = "N: JsonInteger"))]
+pub struct Factorization<N = u128> {
+    /// 已证明为素数的因子（升序）；超过 3.3 × 10^24 的因子由 BPSW 测试判定（无已知反例）
+    #[serde(serialize_with = "json_number::serialize_vec")]
+    pub proven_primes: Vec<N>,
+    /// 尚未完全分解的余因子
+    #[serde(serialize_with = "json_number::serialize_vec")]
+    pub unfactored_cofactors: Vec<N>,
+    /// 是否为完整分解
+    pub complete: bool,
+}
+
+impl<N: Ord + Clone> Factorization<N> {
+    /// 已找到的全部因子（素因子和余因子，升序）
+    pub fn factors(&self) -> Vec<N> {
+        let mut factors: Vec<N> = self.proven_primes.iter().chain(&self.unfactored_cofactors).cloned().collect();
+        factors.sort_unstable();
+        factors
+    }
+}
+
+impl<N: Ord> Factorization<N> {
+    /// 构造完整的分解结果
+    pub fn complete(mut proven_primes: Vec<N>) -> Self {
+        proven_primes.sort_unstable();
+        Self {
+            proven_primes,
+            unfactored_cofactors: Vec::new(),
+            complete: true,
+        }
+    }
+
+    /// 构造部分分解结果；没有余因子时自动视为完整
+    pub fn partial(mut proven_primes: Vec<N>, mut unfactored_cofactors: Vec<N>) -> Self {
+        unfactored_cofactors.sort_unstable();
+        proven_primes.sort_unstable();
+        let complete = unfactored_cofactors.is_empty();
+        Self {
+            proven_primes,
+            unfactored_cofactors,
+            complete,
+        }
+    }
+}
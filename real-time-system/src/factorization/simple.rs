@@ -1,18 +1,37 @@
-pub fn factorize(mut n: u64) -> Vec<u64> {
+use super::budget::Budget;
+use super::primality::is_prime;
+use super::result::Factorization;
+
+/// 试除多少个候选因子检查一次预算
+const CHECK_INTERVAL: u128 = 1 << 16;
+
+/// 试除法分解；预算耗尽时返回已找到的因子，剩余部分作为未分解的余因子
+pub fn factorize(mut n: u128, budget: &Budget) -> Factorization {
     let mut factors = Vec::new();
 
     // 处理因子2
-    while n % 2 == 0 {
+    while n.is_multiple_of(2) {
         factors.push(2);
         n /= 2;
     }
 
     // 处理奇数因子
+    // 剩余部分已是素数时提前结束，避免完整的试除（仅在 n 变化时重新检测）
     let mut i = 3;
-    while i * i <= n {
-        while n % i == 0 {
-            factors.push(i);
-            n /= i;
+    let mut remaining_is_prime = is_prime(n);
+    while !remaining_is_prime && i * i <= n {
+        if (i / 2).is_multiple_of(CHECK_INTERVAL) {
+            if budget.is_exhausted() {
+                return Factorization::partial(factors, vec![n]);
+            }
+            budget.report("trial_division", (i / 2) as u64, (n.isqrt() / 2) as u64);
+        }
+        if n.is_multiple_of(i) {
+            while n.is_multiple_of(i) {
+                factors.push(i);
+                n /= i;
+            }
+            remaining_is_prime = is_prime(n);
         }
         i += 2;
     }
@@ -22,19 +41,19 @@ pub fn factorize(mut n: u64) -> Vec<u64> {
         factors.push(n);
     }
 
-    factors
+    Factorization::complete(factors)
 }
 
 /// 快速分解版本（可能不完整，但速度快）
-/// 用于高负载情况下的快速响应
-pub fn factorize_fast(mut n: u64) -> Vec<u64> {
+/// 用于高负载情况下的快速响应，未能分解的部分放入 `unfactored_cofactors`
+pub fn factorize_fast(mut n: u128) -> Factorization {
     let mut factors = Vec::new();
 
     // 只检查小质数
-    let small_primes = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29];
+    let small_primes: [u128; 10] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29];
 
     for &p in &small_primes {
-        while n % p == 0 {
+        while n.is_multiple_of(p) {
             factors.push(p);
             n /= p;
         }
@@ -42,16 +61,15 @@ pub fn factorize_fast(mut n: u64) -> Vec<u64> {
             break;
         }
     }
-    // 如果还有剩余且不大，直接作为质数
-    if n > 1 && n < 1000 {
+    // 剩余部分是素数时直接加入，否则作为未分解的余因子
+    let mut cofactors = Vec::new();
+    if n > 1 && is_prime(n) {
         factors.push(n);
     } else if n > 1 {
-        // 大数剩余部分，标记为需要进一步分解
-        factors.push(n);
-        factors.push(0); // 0作为标记，表示需要进一步分解
+        cofactors.push(n);
     }
 
-    factors
+    Factorization::partial(factors, cofactors)
 }
 
 #[cfg(test)]
@@ -60,16 +78,41 @@ mod tests {
 
     #[test]
     fn test_factorize() {
-        assert_eq!(factorize(2), vec![2]);
-        assert_eq!(factorize(15), vec![3, 5]);
-        assert_eq!(factorize(84), vec![2, 2, 3, 7]);
-        assert_eq!(factorize(997), vec![997]); // 质数
+        assert_eq!(factorize(2, &Budget::unlimited()), Factorization::complete(vec![2]));
+        assert_eq!(factorize(15, &Budget::unlimited()), Factorization::complete(vec![3, 5]));
+        assert_eq!(factorize(84, &Budget::unlimited()), Factorization::complete(vec![2, 2, 3, 7]));
+        assert_eq!(factorize(997, &Budget::unlimited()), Factorization::complete(vec![997])); // 质数
+        assert_eq!(factorize(6 * 4_294_967_291, &Budget::unlimited()), Factorization::complete(vec![2, 3, 4_294_967_291])); // 大素数余因子
+    }
+
+    #[test]
+    fn test_factorize_within_cancelled() {
+        use super::super::budget::CancellationToken;
+
+        // 两个约 32 位素数的乘积，试除需要约 2^31 次，取消后立即返回
+        let token = CancellationToken::new();
+        token.cancel();
+        let budget = Budget::new(token, None);
+        let n = 12 * 4_294_967_291 * 4_294_967_279;
+        let result = factorize(n, &budget);
+        assert!(!result.complete);
+        assert_eq!(result.proven_primes, vec![2, 2, 3]);
+        assert_eq!(result.unfactored_cofactors, vec![4_294_967_291 * 4_294_967_279]);
     }
 
     #[test]
     fn test_factorize_fast() {
         // 快速分解可能不完整，但应该能处理小因子
-        assert_eq!(factorize_fast(84), vec![2, 2, 3, 7]);
-        assert_eq!(factorize_fast(100), vec![2, 2, 5, 5]);
+        assert_eq!(factorize_fast(84), Factorization::complete(vec![2, 2, 3, 7]));
+        assert_eq!(factorize_fast(100), Factorization::complete(vec![2, 2, 5, 5]));
+        // 剩余部分是大素数时结果完整
+        assert_eq!(factorize_fast(2 * 4_294_967_291), Factorization::complete(vec![2, 4_294_967_291]));
+        // 小合数余因子不能当作素数
+        assert_eq!(factorize_fast(31 * 31), Factorization::partial(vec![], vec![961]));
+        // 大合数余因子不完整
+        let result = factorize_fast(4 * 4_294_967_291 * 65_521);
+        assert!(!result.complete);
+        assert_eq!(result.proven_primes, vec![2, 2]);
+        assert_eq!(result.unfactored_cofactors, vec![4_294_967_291 * 65_521]);
     }
 }
\ No newline at end of file
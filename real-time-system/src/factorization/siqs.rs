@@ -0,0 +1,756 @@
+// 自初始化二次筛（SIQS）
+// 适用于 40–90 位十进制的平衡半素数：Knuth–Schroeppel 乘子、Gray 码切换 B 系数、
+// 单大素数变体，线性代数步骤见 `linalg` 模块
+
+use std::collections::{HashMap, HashSet};
+
+use num_bigint::{BigInt, BigUint, Sign};
+use num_integer::Integer;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+use super::big::{factorize_big, factorize_big_with};
+use super::budget::Budget;
+use super::ecm::primes_up_to;
+use super::linalg;
+use super::primality::{is_prime_big, mul_mod, pow_mod};
+use super::result::Factorization;
+
+/// 不参与筛分的小素数上界（其贡献通过阈值补偿）
+const SMALL_PRIME_SKIP: u64 = 30;
+
+/// 关系数超出因子基大小的余量
+const EXTRA_RELATIONS: usize = 24;
+
+/// 大素数上界 = 因子基最大素数 × 此倍数
+const LARGE_PRIME_MULTIPLIER: u64 = 64;
+
+/// 低于此位数的合数交给 rho/ECM
+const MIN_SIQS_DIGITS: usize = 20;
+
+/// 选择 A 系数时伪随机数生成器的固定种子（保证结果可复现）
+const POLY_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Knuth–Schroeppel 候选乘子（无平方因子的小奇数）
+const MULTIPLIERS: [u64; 20] = [1, 3, 5, 7, 11, 13, 15, 17, 19, 21, 23, 29, 31, 33, 35, 37, 39, 41, 43, 47];
+
+/// 按位数给出的参数：(十进制位数, 因子基大小, 筛区间半宽 M, 阈值系数 T)
+const PARAM_TABLE: [(usize, usize, i64, f64); 12] = [
+    (20, 100, 16_384, 1.8),
+    (25, 150, 16_384, 1.9),
+    (30, 250, 32_768, 2.0),
+    (35, 400, 32_768, 2.0),
+    (40, 600, 32_768, 2.1),
+    (45, 900, 32_768, 2.2),
+    (50, 1_300, 65_536, 2.3),
+    (55, 1_800, 65_536, 2.4),
+    (60, 2_500, 65_536, 2.5),
+    (70, 5_000, 65_536, 2.6),
+    (80, 10_000, 65_536, 2.7),
+    (90, 20_000, 65_536, 2.8),
+];
+
+/// SIQS 参数
+#[derive(Debug, Clone, Copy)]
+struct SiqsParams {
+    fb_size: usize,
+    m: i64,
+    t: f64,
+}
+
+fn params_for(digits: usize) -> SiqsParams {
+    let &(_, fb_size, m, t) = PARAM_TABLE
+        .iter()
+        .find(|&&(d, ..)| digits <= d)
+        .unwrap_or(&PARAM_TABLE[PARAM_TABLE.len() - 1]);
+    SiqsParams { fb_size, m, t }
+}
+
+/// 因子基中的素数
+#[derive(Debug, Clone, Copy)]
+struct FactorBasePrime {
+    p: u64,
+    /// sqrt(kN) mod p
+    sqrt: u64,
+    /// round(log2 p)
+    logp: u8,
+}
+
+/// 一条关系：y² ≡ ∏ p^e · large_prime² (mod N)
+#[derive(Debug, Clone)]
+struct Relation {
+    /// (Ax + B) mod N 的乘积
+    y: BigUint,
+    /// 列下标 → 指数（列 0 为符号 -1，列 j + 1 为因子基第 j 个素数）
+    exponents: HashMap<usize, u32>,
+    /// 合并两个部分关系时出现的大素数（平方因子），开方时需要乘回
+    large_prime: u64,
+}
+
+impl Relation {
+    fn combine(&self, other: &Relation, n: &BigUint, large_prime: u64) -> Relation {
+        let mut exponents = self.exponents.clone();
+        for (&col, &e) in &other.exponents {
+            *exponents.entry(col).or_insert(0) += e;
+        }
+        Relation {
+            y: (&self.y * &other.y) % n,
+            exponents,
+            large_prime: self.large_prime * other.large_prime * large_prime,
+        }
+    }
+
+    /// 指数为奇数的列
+    fn odd_columns(&self) -> Vec<usize> {
+        let mut cols: Vec<usize> = self.exponents.iter().filter(|&(_, &e)| e % 2 == 1).map(|(&c, _)| c).collect();
+        cols.sort_unstable();
+        cols
+    }
+}
+
+/// 固定种子的 xorshift 伪随机数生成器
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// a 模奇素数 p 的逆元（费马小定理）
+fn inv_mod(a: u64, p: u64) -> u64 {
+    pow_mod(a % p, p - 2, p)
+}
+
+/// 勒让德符号是否为 1（或 p | a）
+fn is_residue(a: u64, p: u64) -> bool {
+    a.is_multiple_of(p) || pow_mod(a, (p - 1) / 2, p) == 1
+}
+
+/// Tonelli–Shanks：求 a 模奇素数 p 的平方根（a 必须是二次剩余）
+fn sqrt_mod(a: u64, p: u64) -> u64 {
+    let a = a % p;
+    if a == 0 {
+        return 0;
+    }
+    if p % 4 == 3 {
+        return pow_mod(a, (p + 1) / 4, p);
+    }
+
+    let mut q = p - 1;
+    let mut s = 0;
+    while q.is_multiple_of(2) {
+        q /= 2;
+        s += 1;
+    }
+    let mut z = 2;
+    while is_residue(z, p) {
+        z += 1;
+    }
+
+    let mut m = s;
+    let mut c = pow_mod(z, q, p);
+    let mut t = pow_mod(a, q, p);
+    let mut r = pow_mod(a, q.div_ceil(2), p);
+    while t != 1 {
+        let mut i = 0;
+        let mut t2 = t;
+        while t2 != 1 {
+            t2 = mul_mod(t2, t2, p);
+            i += 1;
+        }
+        let b = pow_mod(c, 1 << (m - i - 1), p);
+        m = i;
+        c = mul_mod(b, b, p);
+        t = mul_mod(t, c, p);
+        r = mul_mod(r, b, p);
+    }
+    r
+}
+
+/// Knuth–Schroeppel 乘子选择：使 kN 的因子基中小素数尽量多
+fn choose_multiplier(n: &BigUint) -> u64 {
+    let small_primes = primes_up_to(1_000);
+    let mut best = (f64::MIN, 1);
+
+    for &k in &MULTIPLIERS {
+        let kn = n * k;
+        let mut score = -0.5 * (k as f64).ln();
+
+        let kn_mod8 = (&kn % 8u32).to_u64().unwrap_or(0);
+        score += match kn_mod8 {
+            1 => 2.0,
+            5 => 1.0,
+            _ => 0.5,
+        } * 2f64.ln();
+
+        for &p in small_primes.iter().skip(1) {
+            let r = (&kn % p).to_u64().unwrap_or(0);
+            if k.is_multiple_of(p) {
+                score += (p as f64).ln() / p as f64;
+            } else if is_residue(r, p) {
+                score += 2.0 * (p as f64).ln() / (p - 1) as f64;
+            }
+        }
+
+        if score > best.0 {
+            best = (score, k);
+        }
+    }
+    best.1
+}
+
+/// 构造因子基；若发现某个素数直接整除 n，则返回该因子
+fn build_factor_base(n: &BigUint, kn: &BigUint, size: usize) -> Result<Vec<FactorBasePrime>, BigUint> {
+    let mut base = vec![FactorBasePrime { p: 2, sqrt: 1, logp: 1 }];
+    let mut limit = 1_000;
+    loop {
+        for p in primes_up_to(limit).into_iter().skip(1) {
+            if base.len() >= size {
+                return Ok(base);
+            }
+            if p <= base.last().map(|f| f.p).unwrap_or(0) {
+                continue;
+            }
+            if (n % p).is_zero() {
+                return Err(BigUint::from(p));
+            }
+            let r = (kn % p).to_u64().unwrap_or(0);
+            if is_residue(r, p) {
+                base.push(FactorBasePrime {
+                    p,
+                    sqrt: sqrt_mod(r, p),
+                    logp: (p as f64).log2().round() as u8,
+                });
+            }
+        }
+        limit *= 2;
+    }
+}
+
+/// 把 BigInt 转为模 n 的非负代表
+fn to_residue(value: &BigInt, n: &BigUint) -> BigUint {
+    let n_int = BigInt::from_biguint(Sign::Plus, n.clone());
+    value.mod_floor(&n_int).to_biguint().unwrap_or_default()
+}
+
+/// SIQS 主体
+struct Sieve<'a> {
+    n: &'a BigUint,
+    kn: BigUint,
+    params: SiqsParams,
+    base: Vec<FactorBasePrime>,
+    threshold: u8,
+    large_prime_bound: u64,
+    full: Vec<Relation>,
+    partials: HashMap<u64, Relation>,
+    rng: XorShift,
+    used_a: HashSet<Vec<usize>>,
+    /// 关系数超出因子基大小的余量（依赖不足时增加）
+    extra_relations: usize,
+}
+
+/// 一个 A 系数下的多项式族
+struct PolyFamily {
+    a: BigUint,
+    /// 组成 A 的素数在因子基中的下标
+    a_indices: Vec<usize>,
+    /// B_l 系数
+    b_terms: Vec<BigUint>,
+    /// bainv2[l][j] = 2 · B_l · A⁻¹ mod p_j
+    bainv2: Vec<Vec<u64>>,
+}
+
+impl<'a> Sieve<'a> {
+    fn new(n: &'a BigUint) -> Result<Self, BigUint> {
+        let digits = n.to_string().len();
+        let params = params_for(digits);
+        let k = choose_multiplier(n);
+        let kn = n * k;
+        let base = build_factor_base(n, &kn, params.fb_size)?;
+
+        let pmax = base.last().map(|f| f.p).unwrap_or(2);
+        // Q(x)/A 在区间端点处约为 M·sqrt(kN/2)
+        let log_q = (params.m as f64).log2() + kn.bits() as f64 / 2.0 - 0.5;
+        let threshold = (log_q - params.t * (pmax as f64).log2()).max(1.0) as u8;
+
+        log::debug!(
+            "SIQS: {} digits, multiplier {}, factor base {} primes (max {}), M = {}, threshold {}",
+            digits, k, base.len(), pmax, params.m, threshold
+        );
+
+        Ok(Self {
+            n,
+            kn,
+            params,
+            base,
+            threshold,
+            large_prime_bound: pmax * LARGE_PRIME_MULTIPLIER,
+            full: Vec::new(),
+            partials: HashMap::new(),
+            rng: XorShift(POLY_SEED),
+            used_a: HashSet::new(),
+            extra_relations: EXTRA_RELATIONS,
+        })
+    }
+
+    fn relations_needed(&self) -> usize {
+        self.base.len() + 1 + self.extra_relations
+    }
+
+    /// 选择 A = ∏ q_l，使其接近 sqrt(2kN)/M
+    fn choose_a(&mut self) -> Option<(BigUint, Vec<usize>)> {
+        let log_target = ((&self.kn * 2u32).sqrt()).bits() as f64 - (self.params.m as f64).log2();
+
+        // 候选素数取因子基中段（跳过小素数，也不取最大的）
+        let lo = self
+            .base
+            .iter()
+            .position(|f| f.p > SMALL_PRIME_SKIP.max(50))
+            .unwrap_or(1)
+            .max(self.base.len() / 4);
+        let hi = (self.base.len() * 3 / 4).max(lo + 2).min(self.base.len());
+        if hi <= lo + 1 {
+            return None;
+        }
+        let mid_log = (self.base[(lo + hi) / 2].p as f64).log2();
+        let s = ((log_target / mid_log).round() as usize).clamp(1, hi - lo);
+
+        for _ in 0..1_000 {
+            let mut indices = Vec::with_capacity(s);
+            let mut log_a = 0.0;
+            while indices.len() + 1 < s {
+                let i = lo + (self.rng.next() as usize) % (hi - lo);
+                if !indices.contains(&i) {
+                    indices.push(i);
+                    log_a += (self.base[i].p as f64).log2();
+                }
+            }
+
+            // 最后一个素数使 log A 最接近目标
+            let remaining = log_target - log_a;
+            let last = (1..self.base.len())
+                .filter(|i| !indices.contains(i) && self.base[*i].sqrt != 0 && self.base[*i].p > 2)
+                .min_by(|&a, &b| {
+                    let da = ((self.base[a].p as f64).log2() - remaining).abs();
+                    let db = ((self.base[b].p as f64).log2() - remaining).abs();
+                    da.total_cmp(&db)
+                })?;
+            indices.push(last);
+            indices.sort_unstable();
+
+            if indices.iter().any(|&i| self.base[i].sqrt == 0) || !self.used_a.insert(indices.clone()) {
+                continue;
+            }
+            let a = indices.iter().fold(BigUint::one(), |acc, &i| acc * self.base[i].p);
+            return Some((a, indices));
+        }
+        None
+    }
+
+    /// 计算 B_l 系数及各素数的 2·B_l·A⁻¹
+    fn init_family(&self, a: BigUint, a_indices: Vec<usize>) -> PolyFamily {
+        let mut b_terms = Vec::with_capacity(a_indices.len());
+        for &l in &a_indices {
+            let q = self.base[l].p;
+            let a_over_q = &a / q;
+            let a_over_q_mod = (&a_over_q % q).to_u64().unwrap_or(0);
+            let mut gamma = mul_mod(self.base[l].sqrt, inv_mod(a_over_q_mod, q), q);
+            if gamma > q / 2 {
+                gamma = q - gamma;
+            }
+            b_terms.push(a_over_q * gamma);
+        }
+
+        let bainv2 = b_terms
+            .iter()
+            .map(|b_l| {
+                self.base
+                    .iter()
+                    .map(|f| {
+                        let a_mod = (&a % f.p).to_u64().unwrap_or(0);
+                        if f.p == 2 || a_mod == 0 {
+                            return 0;
+                        }
+                        let ainv = inv_mod(a_mod, f.p);
+                        let b_mod = (b_l % f.p).to_u64().unwrap_or(0);
+                        mul_mod(mul_mod(2, b_mod, f.p), ainv, f.p)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        PolyFamily { a, a_indices, b_terms, bainv2 }
+    }
+
+    /// 当前 B 下各素数的两个根 A⁻¹(±t - B) mod p（不筛分的素数为 None）
+    fn roots(&self, family: &PolyFamily, b: &BigInt) -> Vec<Option<(u64, u64)>> {
+        self.base
+            .iter()
+            .enumerate()
+            .map(|(j, f)| {
+                if f.p < SMALL_PRIME_SKIP || family.a_indices.contains(&j) {
+                    return None;
+                }
+                let a_mod = (&family.a % f.p).to_u64().unwrap_or(0);
+                let ainv = inv_mod(a_mod, f.p);
+                let p_int = BigInt::from(f.p);
+                let b_mod = b.mod_floor(&p_int).to_u64().unwrap_or(0);
+                let r1 = mul_mod(ainv, (f.sqrt + f.p - b_mod) % f.p, f.p);
+                let r2 = mul_mod(ainv, (2 * f.p - f.sqrt - b_mod) % f.p, f.p);
+                Some((r1, r2))
+            })
+            .collect()
+    }
+
+    /// 对一个多项式筛分并收集关系
+    fn sieve_polynomial(&mut self, family: &PolyFamily, b: &BigInt, roots: &[Option<(u64, u64)>], sieve: &mut [u8]) {
+        let m = self.params.m;
+        sieve.iter_mut().for_each(|v| *v = 0);
+
+        for (f, root) in self.base.iter().zip(roots) {
+            let Some((r1, r2)) = *root else { continue };
+            let p = f.p as usize;
+            let offset = (m as u64 % f.p) as usize;
+            for r in [r1, r2] {
+                let mut idx = (r as usize + offset) % p;
+                while idx < sieve.len() {
+                    sieve[idx] = sieve[idx].wrapping_add(f.logp);
+                    idx += p;
+                }
+                if r1 == r2 {
+                    break;
+                }
+            }
+        }
+
+        let a_int = BigInt::from_biguint(Sign::Plus, family.a.clone());
+        let kn_int = BigInt::from_biguint(Sign::Plus, self.kn.clone());
+        let c = (b * b - &kn_int) / &a_int;
+
+        let threshold = self.threshold;
+        let candidates: Vec<i64> = sieve
+            .iter()
+            .enumerate()
+            .filter(|&(_, &v)| v >= threshold)
+            .map(|(idx, _)| idx as i64 - m)
+            .collect();
+
+        for x in candidates {
+            self.check_candidate(family, &a_int, b, &c, x, roots);
+        }
+    }
+
+    /// 试除候选位置 x 处的 g(x) = Ax² + 2Bx + C
+    fn check_candidate(
+        &mut self,
+        family: &PolyFamily,
+        a: &BigInt,
+        b: &BigInt,
+        c: &BigInt,
+        x: i64,
+        roots: &[Option<(u64, u64)>],
+    ) {
+        let x_big = BigInt::from(x);
+        let g: BigInt = a * &x_big * &x_big + b * &x_big * 2 + c;
+        if g.is_zero() {
+            return;
+        }
+
+        let mut exponents: HashMap<usize, u32> = HashMap::new();
+        if g.is_negative() {
+            exponents.insert(0, 1);
+        }
+        let mut rest = g.abs().to_biguint().unwrap_or_default();
+
+        for (j, f) in self.base.iter().enumerate() {
+            let divides = match roots[j] {
+                Some((r1, r2)) => {
+                    let xm = x.rem_euclid(f.p as i64) as u64;
+                    xm == r1 || xm == r2
+                }
+                None => (&rest % f.p).is_zero(),
+            };
+            if !divides {
+                continue;
+            }
+            while (&rest % f.p).is_zero() {
+                rest /= f.p;
+                *exponents.entry(j + 1).or_insert(0) += 1;
+            }
+        }
+
+        // (Ax + B)² - kN = A · g(x)
+        for &l in &family.a_indices {
+            *exponents.entry(l + 1).or_insert(0) += 1;
+        }
+        let y = to_residue(&(a * &x_big + b), self.n);
+
+        if rest.is_one() {
+            self.full.push(Relation { y, exponents, large_prime: 1 });
+        } else if let Some(large) = rest.to_u64().filter(|&r| r < self.large_prime_bound) {
+            let relation = Relation { y, exponents, large_prime: 1 };
+            match self.partials.remove(&large) {
+                Some(other) => self.full.push(relation.combine(&other, self.n, large)),
+                None => {
+                    self.partials.insert(large, relation);
+                }
+            }
+        }
+    }
+
+    /// 筛分直到收集到足够的关系；无法再选出新的 A 或预算耗尽时返回 false
+    fn collect_relations(&mut self, budget: &Budget) -> bool {
+        let mut sieve = vec![0u8; 2 * self.params.m as usize];
+        let mut families = 0usize;
+
+        while self.full.len() < self.relations_needed() {
+            let Some((a, a_indices)) = self.choose_a() else {
+                return false;
+            };
+            let family = self.init_family(a, a_indices);
+            families += 1;
+
+            let s = family.b_terms.len();
+            let mut b = BigInt::from_biguint(Sign::Plus, family.b_terms.iter().sum());
+            let mut roots = self.roots(&family, &b);
+
+            for i in 1..=(1usize << (s - 1)) {
+                if budget.is_exhausted() {
+                    return false;
+                }
+                self.sieve_polynomial(&family, &b, &roots, &mut sieve);
+                if i == 1 << (s - 1) {
+                    break;
+                }
+
+                // Gray 码切换：B ← B ± 2·B_v，根相应地 ∓ 2·B_v·A⁻¹
+                let v = i.trailing_zeros() as usize;
+                let subtract = i.div_ceil(1 << (v + 1)) % 2 == 1;
+                let delta = BigInt::from_biguint(Sign::Plus, &family.b_terms[v] * 2u32);
+                if subtract {
+                    b -= delta;
+                } else {
+                    b += delta;
+                }
+                for (j, root) in roots.iter_mut().enumerate() {
+                    if let Some((r1, r2)) = root {
+                        let p = self.base[j].p;
+                        let d = family.bainv2[v][j];
+                        if subtract {
+                            *r1 = (*r1 + d) % p;
+                            *r2 = (*r2 + d) % p;
+                        } else {
+                            *r1 = (*r1 + p - d) % p;
+                            *r2 = (*r2 + p - d) % p;
+                        }
+                    }
+                }
+            }
+
+            budget.report("siqs", self.full.len() as u64, self.relations_needed() as u64);
+            if families.is_multiple_of(16) {
+                log::debug!(
+                    "SIQS: {} polynomial families, {} / {} relations ({} partials)",
+                    families,
+                    self.full.len(),
+                    self.relations_needed(),
+                    self.partials.len()
+                );
+            }
+        }
+        true
+    }
+
+    /// 线性代数 + 开平方，得到非平凡因子
+    fn find_factor(&self) -> Option<BigUint> {
+        let rows: Vec<Vec<usize>> = self.full.iter().map(Relation::odd_columns).collect();
+        let dependencies = linalg::find_dependencies(&rows, self.base.len() + 1);
+
+        for dep in dependencies {
+            let mut x = BigUint::one();
+            let mut y = BigUint::one();
+            let mut totals: HashMap<usize, u32> = HashMap::new();
+            for &r in &dep {
+                let relation = &self.full[r];
+                x = x * &relation.y % self.n;
+                y = y * relation.large_prime % self.n;
+                for (&col, &e) in &relation.exponents {
+                    *totals.entry(col).or_insert(0) += e;
+                }
+            }
+            for (&col, &e) in &totals {
+                if col == 0 {
+                    continue;
+                }
+                let p = BigUint::from(self.base[col - 1].p);
+                y = y * p.modpow(&BigUint::from(e / 2), self.n) % self.n;
+            }
+
+            let diff = if x >= y { &x - &y } else { &y - &x };
+            let g = diff.gcd(self.n);
+            if !g.is_one() && &g != self.n {
+                return Some(g);
+            }
+        }
+        None
+    }
+}
+
+/// 使用 SIQS 寻找 n 的一个非平凡因子
+///
+/// n 应为没有小因子、不是完全幂的合数；找不到或预算耗尽时返回 None
+pub fn siqs_find_factor(n: &BigUint, budget: &Budget) -> Option<BigUint> {
+    let mut sieve = match Sieve::new(n) {
+        Ok(sieve) => sieve,
+        Err(factor) => return Some(factor),
+    };
+
+    // 依赖不足时继续补充关系（最多重试几轮）
+    for _ in 0..4 {
+        if !sieve.collect_relations(budget) {
+            return None;
+        }
+        if let Some(factor) = sieve.find_factor() {
+            return Some(factor);
+        }
+        sieve.extra_relations += EXTRA_RELATIONS;
+    }
+    None
+}
+
+/// 若 n = r^k（k ≥ 2），返回 (r, k)
+fn perfect_power(n: &BigUint) -> Option<(BigUint, u32)> {
+    for k in 2..=n.bits() as u32 {
+        let root = n.nth_root(k);
+        if root <= BigUint::one() {
+            break;
+        }
+        if root.pow(k) == *n {
+            return Some((root, k));
+        }
+    }
+    None
+}
+
+/// 使用 SIQS 分解任意精度整数
+///
+/// 小因子先由试除和有限次数的 rho 剥离（不运行 ECM）；剩余的合数余因子用 SIQS 拆分，
+/// 不足 SIQS 适用位数的余因子交给 `factorize_big`；预算耗尽时未拆开的合数放入 `unfactored_cofactors`
+pub fn factorize_siqs(n: &BigUint, budget: &Budget) -> Factorization<BigUint> {
+    let prepared = factorize_big_with(n, &[], budget);
+    let mut primes = prepared.proven_primes;
+    let mut cofactors = Vec::new();
+    let mut pending = prepared.unfactored_cofactors;
+
+    while let Some(m) = pending.pop() {
+        if m.is_one() {
+            continue;
+        }
+        if is_prime_big(&m) {
+            primes.push(m);
+            continue;
+        }
+        if let Some((root, k)) = perfect_power(&m) {
+            pending.extend(std::iter::repeat_n(root, k as usize));
+            continue;
+        }
+        if m.to_string().len() < MIN_SIQS_DIGITS || m.is_even() {
+            let result = factorize_big(&m, budget);
+            primes.extend(result.proven_primes);
+            cofactors.extend(result.unfactored_cofactors);
+            continue;
+        }
+        match siqs_find_factor(&m, budget) {
+            Some(d) => {
+                let rest = &m / &d;
+                pending.push(d);
+                pending.push(rest);
+            }
+            None => cofactors.push(m),
+        }
+    }
+
+    Factorization::partial(primes, cofactors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigUint {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_sqrt_mod() {
+        for &p in &[3u64, 5, 13, 17, 97, 65537, 1_000_000_007] {
+            for a in 1..50u64 {
+                if is_residue(a, p) {
+                    let r = sqrt_mod(a, p);
+                    assert_eq!(mul_mod(r, r, p), a % p, "sqrt({}) mod {}", a, p);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_siqs_balanced_semiprime_30_digits() {
+        // 两个 15 位素数的乘积
+        let p = big("100000000000031");
+        let q = big("999999999999989");
+        let n = &p * &q;
+        let factor = siqs_find_factor(&n, &Budget::unlimited()).expect("SIQS should split a 30-digit semiprime");
+        assert!(factor == p || factor == q);
+    }
+
+    #[test]
+    fn test_factorize_siqs_complete() {
+        // 3 · 两个 13 位素数 · 平方因子
+        let p = big("1000000000039");
+        let q = big("9999999999971");
+        let n = &p * &q * 3u32 * 49u32;
+        let result = factorize_siqs(&n, &Budget::unlimited());
+        assert!(result.complete);
+        assert_eq!(result.proven_primes, vec![big("3"), big("7"), big("7"), p, q]);
+    }
+
+    #[test]
+    fn test_siqs_balanced_semiprime_40_digits() {
+        let p = big("10000000000000000051");
+        let q = big("99999999999999999989");
+        let n = &p * &q;
+        let factor = siqs_find_factor(&n, &Budget::unlimited()).expect("SIQS should split a 40-digit semiprime");
+        assert!(factor == p || factor == q);
+    }
+
+    #[test]
+    fn test_siqs_balanced_semiprime_50_digits() {
+        let p = big("1000000000000000000012369");
+        let q = big("3000000000000000000006793");
+        let n = &p * &q;
+        let factor = siqs_find_factor(&n, &Budget::unlimited()).expect("SIQS should split a 50-digit semiprime");
+        assert!(factor == p || factor == q);
+    }
+
+    #[test]
+    fn test_siqs_balanced_semiprime_60_digits() {
+        let p = big("100000000000000000000000012349");
+        let q = big("300000000000000000000000006857");
+        let n = &p * &q;
+        let factor = siqs_find_factor(&n, &Budget::unlimited()).expect("SIQS should split a 60-digit semiprime");
+        assert!(factor == p || factor == q);
+    }
+
+    #[test]
+    fn test_siqs_balanced_semiprime_70_digits() {
+        // 两个 35 位素数的乘积
+        let p = big("20000000000000000000000000000000203");
+        let q = big("50000000000000000000000000000000107");
+        let n = &p * &q;
+        let factor = siqs_find_factor(&n, &Budget::unlimited()).expect("SIQS should split a 70-digit semiprime");
+        assert!(factor == p || factor == q);
+    }
+}
@@ -0,0 +1,347 @@
+// 长时间运行的分解任务
+// SIQS 等算法在 60 位以上的数上可能需要数秒到数分钟，不适合在请求内同步完成：
+// 提交后立即返回任务编号，计算在计算线程池中进行，客户端轮询任务状态获取结果。
+// 同时运行的任务数有上限，其余任务等待（数量同样有上限），计算线程池始终留有线程处理实时请求；
+// 运行中的任务和实时请求一样占用准入控制的计算名额
+
+use dashmap::DashMap;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+use crate::cache::FactorizationCache;
+use crate::compute::ComputePool;
+use crate::factorization::{self, Budget, Factorization, FactorizerRegistry, Progress};
+use crate::load_balancer::LoadBalancer;
+
+/// 最多保留的任务数，超出后删除最早结束的任务
+const MAX_RETAINED_JOBS: usize = 1000;
+
+/// 同时未结束（等待或运行中）的任务数上限
+const MAX_ACTIVE_JOBS: usize = 16;
+
+/// 同时运行的任务数上限（计算线程池至少有 `max_compute_threads` 个线程，任务最多占用其中两个）
+const MAX_RUNNING_JOBS: usize = 2;
+
+#[derive(Debug, thiserror::Error)]
+pub enum JobError {
+    #[error("algorithm '{algorithm}' is not available for {number}")]
+    UnsupportedAlgorithm { algorithm: String, number: String },
+
+    #[error("too many unfinished jobs (limit {0})")]
+    TooManyJobs(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+impl JobStatus {
+    fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Completed | JobStatus::Cancelled | JobStatus::Failed)
+    }
+}
+
+/// 任务状态快照
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: u64,
+    pub number: String,
+    pub algorithm: String,
+    pub status: JobStatus,
+    pub submitted_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub computation_time_ms: Option<u64>,
+    /// 最近一次报告的进度
+    pub progress: Option<Progress>,
+    pub result: Option<Factorization<BigUint>>,
+    pub error: Option<String>,
+}
+
+pub struct JobManager {
+    jobs: DashMap<u64, Job>,
+    /// 未结束任务的预算（用于取消和读取进度）
+    budgets: DashMap<u64, Budget>,
+    next_id: AtomicU64,
+    /// 未结束的任务数
+    active: AtomicUsize,
+    /// 运行名额（限制同时占用计算线程的任务数）
+    lane: Arc<Semaphore>,
+    cache: Arc<FactorizationCache>,
+    registry: Arc<FactorizerRegistry>,
+    pool: Arc<ComputePool>,
+    /// 任务与实时请求共用计算名额
+    load_balancer: Arc<LoadBalancer>,
+}
+
+impl JobManager {
+    pub fn new(
+        cache: Arc<FactorizationCache>,
+        registry: Arc<FactorizerRegistry>,
+        pool: Arc<ComputePool>,
+        load_balancer: Arc<LoadBalancer>,
+    ) -> Self {
+        Self {
+            jobs: DashMap::new(),
+            budgets: DashMap::new(),
+            next_id: AtomicU64::new(1),
+            active: AtomicUsize::new(0),
+            lane: Arc::new(Semaphore::new(MAX_RUNNING_JOBS)),
+            cache,
+            registry,
+            pool,
+            load_balancer,
+        }
+    }
+
+    /// 算法是否可用于分解 number（u128 范围内可用注册表中的任意算法）
+    fn supports(&self, number: &BigUint, algorithm: &str) -> bool {
+        match number.to_u128() {
+            Some(_) => self.registry.get(algorithm).is_some(),
+            None => factorization::BIG_ALGORITHMS.contains(&algorithm),
+        }
+    }
+
+    /// 提交任务并立即返回其初始状态；算法不可用或未结束的任务过多时返回错误
+    ///
+    /// 必须在 tokio 运行时中调用
+    pub fn submit(self: &Arc<Self>, number: BigUint, algorithm: String) -> Result<Job, JobError> {
+        if !self.supports(&number, &algorithm) {
+            return Err(JobError::UnsupportedAlgorithm { algorithm, number: number.to_string() });
+        }
+        self.active
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |active| (active < MAX_ACTIVE_JOBS).then_some(active + 1))
+            .map_err(|_| JobError::TooManyJobs(MAX_ACTIVE_JOBS))?;
+
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let job = Job {
+            id,
+            number: number.to_string(),
+            algorithm: algorithm.clone(),
+            status: JobStatus::Pending,
+            submitted_at: chrono::Utc::now().to_rfc3339(),
+            started_at: None,
+            finished_at: None,
+            computation_time_ms: None,
+            progress: None,
+            result: None,
+            error: None,
+        };
+        let budget = Budget::unlimited();
+        self.jobs.insert(id, job.clone());
+        self.budgets.insert(id, budget.clone());
+        self.evict_finished();
+
+        let manager = Arc::clone(self);
+        tokio::spawn(async move {
+            let worker = Arc::clone(&manager);
+            // 等待运行名额和计算名额期间任务保持 Pending；信号量不会被关闭
+            let _running = Arc::clone(&manager.lane).acquire_owned().await;
+            let _permit = manager.load_balancer.admit_job().await;
+            let outcome = manager.pool.run(move || worker.run(id, &number, &algorithm, &budget)).await;
+            manager.budgets.remove(&id);
+            manager.active.fetch_sub(1, Ordering::SeqCst);
+            if let Err(e) = outcome {
+                log::error!("Job {} failed: {}", id, e);
+                manager.update(id, |job| {
+                    job.status = JobStatus::Failed;
+                    job.finished_at = Some(chrono::Utc::now().to_rfc3339());
+                    job.error = Some(e.to_string());
+                });
+            }
+        });
+
+        Ok(job)
+    }
+
+    /// 查询任务状态
+    pub fn get(&self, id: u64) -> Option<Job> {
+        let mut job = self.jobs.get(&id).map(|job| job.clone())?;
+        if let Some(budget) = self.budgets.get(&id) {
+            job.progress = budget.progress();
+        }
+        Some(job)
+    }
+
+    /// 取消未结束的任务，返回任务是否存在；已找到的因子仍会作为部分结果保留
+    pub fn cancel(&self, id: u64) -> bool {
+        if let Some(budget) = self.budgets.get(&id) {
+            budget.cancel();
+        }
+        self.jobs.contains_key(&id)
+    }
+
+    /// 在计算线程中执行分解，完整的结果写入缓存
+    fn run(&self, id: u64, number: &BigUint, algorithm: &str, budget: &Budget) {
+        self.update(id, |job| {
+            job.status = JobStatus::Running;
+            job.started_at = Some(chrono::Utc::now().to_rfc3339());
+        });
+
+        let start = std::time::Instant::now();
+        let Some(result) = self.compute(number, algorithm, budget) else {
+            // 提交时已检查过算法，正常情况下不会发生
+            self.update(id, |job| {
+                job.status = JobStatus::Failed;
+                job.finished_at = Some(chrono::Utc::now().to_rfc3339());
+                job.error = Some(format!("algorithm '{}' is not available for {}", algorithm, number));
+            });
+            return;
+        };
+        let elapsed_ms = start.elapsed().as_millis() as u64;
+
+        match number.to_u128() {
+            Some(small) => {
+                let narrowed = Factorization::partial(
+                    result.proven_primes.iter().filter_map(|f| f.to_u128()).collect(),
+                    result.unfactored_cofactors.iter().filter_map(|f| f.to_u128()).collect(),
+                );
+                self.cache.insert_factorization(small, &narrowed, elapsed_ms, algorithm.to_string());
+            }
+            None => {
+                self.cache.insert_big_factorization(number, &result, elapsed_ms, algorithm.to_string());
+            }
+        }
+
+        log::info!("Job {} ({}) finished in {} ms, complete: {}", id, algorithm, elapsed_ms, result.complete);
+        let status = if budget.is_exhausted() && !result.complete {
+            JobStatus::Cancelled
+        } else {
+            JobStatus::Completed
+        };
+        self.update(id, |job| {
+            job.status = status;
+            job.finished_at = Some(chrono::Utc::now().to_rfc3339());
+            job.computation_time_ms = Some(elapsed_ms);
+            job.progress = budget.progress();
+            job.result = Some(result);
+        });
+    }
+
+    /// 按名称分解；算法不可用时返回 None
+    fn compute(&self, number: &BigUint, algorithm: &str, budget: &Budget) -> Option<Factorization<BigUint>> {
+        if let Some(small) = number.to_u128() {
+            let result = self.registry.get(algorithm)?.factorize(small, budget);
+            return Some(Factorization::partial(
+                result.proven_primes.into_iter().map(BigUint::from).collect(),
+                result.unfactored_cofactors.into_iter().map(BigUint::from).collect(),
+            ));
+        }
+        factorization::factorize_big_by(algorithm, number, budget)
+    }
+
+    fn update(&self, id: u64, f: impl FnOnce(&mut Job)) {
+        if let Some(mut job) = self.jobs.get_mut(&id) {
+            f(&mut job);
+        }
+    }
+
+    /// 任务过多时删除编号最小的已结束任务
+    fn evict_finished(&self) {
+        while self.jobs.len() > MAX_RETAINED_JOBS {
+            let oldest = self
+                .jobs
+                .iter()
+                .filter(|job| job.status.is_finished())
+                .map(|job| job.id)
+                .min();
+            match oldest {
+                Some(id) => {
+                    self.jobs.remove(&id);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_balancer::LoadBalancerConfig;
+
+    #[tokio::test]
+    async fn test_job_lifecycle() {
+        let cache = Arc::new(FactorizationCache::new());
+        let manager = Arc::new(JobManager::new(
+            Arc::clone(&cache),
+            Arc::new(FactorizerRegistry::with_defaults()),
+            Arc::new(ComputePool::new(2)),
+            Arc::new(LoadBalancer::new(LoadBalancerConfig::default()).unwrap()),
+        ));
+
+        // 两个 15 位素数的乘积，超出 u64 但在 u128 范围内
+        let number = BigUint::from(100_000_000_000_031u128 * 999_999_999_999_989u128);
+        assert!(manager.supports(&number, "siqs"));
+        assert!(matches!(
+            manager.submit(&number << 128u32, "pollard_rho_brent".to_string()),
+            Err(JobError::UnsupportedAlgorithm { .. })
+        ));
+
+        let job = manager.submit(number, "siqs".to_string()).unwrap();
+        assert_eq!(job.status, JobStatus::Pending);
+
+        let finished = loop {
+            let job = manager.get(job.id).unwrap();
+            if job.status.is_finished() {
+                break job;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        };
+        assert_eq!(finished.status, JobStatus::Completed);
+        let result = finished.result.unwrap();
+        assert!(result.complete);
+        assert_eq!(
+            result.proven_primes,
+            vec![BigUint::from(100_000_000_000_031u64), BigUint::from(999_999_999_999_989u64)]
+        );
+        assert!(cache.get(100_000_000_000_031u128 * 999_999_999_999_989u128).is_some());
+        assert!(manager.get(job.id + 1).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_job_cancel() {
+        let manager = Arc::new(JobManager::new(
+            Arc::new(FactorizationCache::new()),
+            Arc::new(FactorizerRegistry::with_defaults()),
+            Arc::new(ComputePool::new(2)),
+            Arc::new(LoadBalancer::new(LoadBalancerConfig::default()).unwrap()),
+        ));
+
+        // 60 位平衡半素数，SIQS 需要数秒，取消后应很快结束
+        let p: BigUint = "100000000000000000000000012349".parse().unwrap();
+        let q: BigUint = "300000000000000000000000006857".parse().unwrap();
+        let job = manager.submit(&p * &q, "siqs".to_string()).unwrap();
+        assert!(manager.cancel(job.id));
+        assert!(!manager.cancel(job.id + 1));
+
+        // 未结束的任务达到上限后拒绝新任务
+        let queued: Vec<Job> = (1..MAX_ACTIVE_JOBS).map(|_| manager.submit(&p * &q, "siqs".to_string()).unwrap()).collect();
+        assert!(matches!(manager.submit(&p * &q, "siqs".to_string()), Err(JobError::TooManyJobs(MAX_ACTIVE_JOBS))));
+        for job in &queued {
+            manager.cancel(job.id);
+        }
+
+        let finished = loop {
+            let job = manager.get(job.id).unwrap();
+            if job.status.is_finished() {
+                break job;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        };
+        assert_eq!(finished.status, JobStatus::Cancelled);
+        let result = finished.result.unwrap();
+        assert!(!result.complete);
+        assert_eq!(result.unfactored_cofactors, vec![&p * &q]);
+    }
+}
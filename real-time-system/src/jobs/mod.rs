@@ -0,0 +1,4 @@
+pub mod manager;
+
+// 重新导出
+pub use manager::{JobError, JobManager};
@@ -0,0 +1,275 @@
+// 计算请求的准入控制
+// 同时进行的计算数有上限（等于计算线程数，随线程池扩缩容调整），超出的请求在有界队列中等待；
+// 队列已满或等待超时的请求被拒绝（503 + Retry-After），而不是降级为不完整的算法。
+// 长时间任务同样占用计算名额，但在任务自己的有界队列中等待，不受请求队列的长度和等待时间限制。
+// 排队的入口（`enqueue`）和排队期限（`queue_deadline`）也提供同步版本，模拟器在虚拟时钟上驱动同一个控制器
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::{timeout, Duration};
+
+use super::clock::{Clock, SystemClock};
+
+/// 准入许可，丢弃时释放计算名额（上限降低后多出的名额不再归还）
+#[derive(Debug)]
+pub struct AdmissionPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    excess: Arc<AtomicUsize>,
+}
+
+impl Drop for AdmissionPermit {
+    fn drop(&mut self) {
+        let retire = self.excess.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |excess| excess.checked_sub(1));
+        if let (Ok(_), Some(permit)) = (retire, self.permit.take()) {
+            permit.forget();
+        }
+    }
+}
+
+/// 请求被拒绝的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// 等待队列已满
+    QueueFull,
+    /// 排队超过最长等待时间
+    Timeout,
+}
+
+#[derive(Debug)]
+pub struct AdmissionController {
+    permits: Arc<Semaphore>,
+    /// 当前的计算名额上限
+    max_in_flight: Mutex<usize>,
+    /// 上限降低时尚未收回的名额（正在使用，归还时丢弃）
+    excess: Arc<AtomicUsize>,
+    max_queue_length: usize,
+    max_queue_wait: Duration,
+    /// 正在排队的请求数
+    waiting: Arc<AtomicUsize>,
+    /// 累计拒绝的请求数
+    rejected: AtomicU64,
+    /// 记录排队开始时间（模拟器使用虚拟时钟）
+    clock: Arc<dyn Clock>,
+}
+
+/// 排队中的请求，丢弃时退出队列（请求 future 被丢弃时也能正确减一）
+#[derive(Debug)]
+pub struct QueueTicket {
+    waiting: Arc<AtomicUsize>,
+    since: Instant,
+}
+
+impl Drop for QueueTicket {
+    fn drop(&mut self) {
+        self.waiting.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl AdmissionController {
+    pub fn new(max_in_flight: usize, max_queue_length: usize, max_queue_wait: Duration) -> Self {
+        let max_in_flight = max_in_flight.max(1);
+        Self {
+            permits: Arc::new(Semaphore::new(max_in_flight)),
+            max_in_flight: Mutex::new(max_in_flight),
+            excess: Arc::new(AtomicUsize::new(0)),
+            max_queue_length,
+            max_queue_wait,
+            waiting: Arc::new(AtomicUsize::new(0)),
+            rejected: AtomicU64::new(0),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// 使用指定的时钟记录排队开始时间
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    fn permit(&self, permit: OwnedSemaphorePermit) -> AdmissionPermit {
+        AdmissionPermit {
+            permit: Some(permit),
+            excess: Arc::clone(&self.excess),
+        }
+    }
+
+    /// 调整计算名额上限（至少 1 个）；降低时正在进行的计算不受影响，结束后不再归还多出的名额
+    pub fn set_max_in_flight(&self, max_in_flight: usize) {
+        let max_in_flight = max_in_flight.max(1);
+        let Ok(mut current) = self.max_in_flight.lock() else {
+            return;
+        };
+        if max_in_flight > *current {
+            // 先抵消尚未收回的名额
+            let grow = max_in_flight - *current;
+            let cancelled = self
+                .excess
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |excess| Some(excess - excess.min(grow)))
+                .map_or(0, |excess| excess.min(grow));
+            self.permits.add_permits(grow - cancelled);
+        } else {
+            let shrink = *current - max_in_flight;
+            let forgotten = self.permits.forget_permits(shrink);
+            self.excess.fetch_add(shrink - forgotten, Ordering::SeqCst);
+        }
+        *current = max_in_flight;
+    }
+
+    /// 申请计算名额：有空闲名额时立即返回，否则排队等待
+    pub async fn admit(&self) -> Result<AdmissionPermit, Rejection> {
+        if let Some(permit) = self.try_admit() {
+            return Ok(permit);
+        }
+
+        let ticket = self.enqueue()?;
+        match timeout(self.max_queue_wait, Arc::clone(&self.permits).acquire_owned()).await {
+            Ok(Ok(permit)) => Ok(self.permit(permit)),
+            // 信号量不会被关闭，只可能是等待超时
+            Ok(Err(_)) | Err(_) => Err(self.time_out(ticket)),
+        }
+    }
+
+    /// 有空闲名额时立即取得（排队中的请求优先：有请求在等待时不会有空闲名额）
+    pub fn try_admit(&self) -> Option<AdmissionPermit> {
+        Arc::clone(&self.permits).try_acquire_owned().ok().map(|permit| self.permit(permit))
+    }
+
+    /// 进入等待队列；队列已满时拒绝
+    pub fn enqueue(&self) -> Result<QueueTicket, Rejection> {
+        // 先占位再检查，避免并发请求同时越过队列上限
+        if self.waiting.fetch_add(1, Ordering::SeqCst) >= self.max_queue_length {
+            self.waiting.fetch_sub(1, Ordering::SeqCst);
+            return Err(self.reject(Rejection::QueueFull));
+        }
+        Ok(QueueTicket {
+            waiting: Arc::clone(&self.waiting),
+            since: self.clock.now(),
+        })
+    }
+
+    /// 排队请求的最晚准入时刻，超过后应调用 `time_out`
+    pub fn queue_deadline(&self, ticket: &QueueTicket) -> Instant {
+        ticket.since + self.max_queue_wait
+    }
+
+    /// 排队超时：退出队列并记为拒绝
+    pub fn time_out(&self, ticket: QueueTicket) -> Rejection {
+        drop(ticket);
+        self.reject(Rejection::Timeout)
+    }
+
+    /// 为长时间任务申请计算名额：一直等到有空闲名额（任务的数量由调用方限制）
+    pub async fn admit_job(&self) -> AdmissionPermit {
+        // 信号量不会被关闭
+        let permit = Arc::clone(&self.permits).acquire_owned().await.expect("admission semaphore is never closed");
+        self.permit(permit)
+    }
+
+    fn reject(&self, reason: Rejection) -> Rejection {
+        self.rejected.fetch_add(1, Ordering::SeqCst);
+        log::warn!("Compute request rejected: {:?}", reason);
+        reason
+    }
+
+    /// 建议客户端重试前等待的秒数（一个最长排队时间，至少 1 秒）
+    pub fn retry_after_secs(&self) -> u64 {
+        self.max_queue_wait.as_secs_f64().ceil().max(1.0) as u64
+    }
+
+    /// 当前的计算名额上限
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight.lock().map_or(1, |max_in_flight| *max_in_flight)
+    }
+
+    /// 正在进行的计算数（含任务）
+    pub fn in_flight(&self) -> usize {
+        (self.max_in_flight() + self.excess.load(Ordering::SeqCst)).saturating_sub(self.permits.available_permits())
+    }
+
+    /// 正在排队的请求数
+    pub fn queued(&self) -> usize {
+        self.waiting.load(Ordering::SeqCst)
+    }
+
+    /// 累计拒绝的请求数
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_balancer::clock::VirtualClock;
+
+    #[tokio::test]
+    async fn test_admission_queue_limits() {
+        let admission = Arc::new(AdmissionController::new(1, 1, Duration::from_millis(50)));
+        let first = admission.admit().await.unwrap();
+        assert_eq!(admission.in_flight(), 1);
+
+        // 第二个请求排队并超时
+        let waiter = {
+            let admission = Arc::clone(&admission);
+            tokio::spawn(async move { admission.admit().await.map(|_| ()) })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(admission.queued(), 1);
+
+        // 队列已满，第三个请求立即被拒绝
+        assert_eq!(admission.admit().await.unwrap_err(), Rejection::QueueFull);
+        assert_eq!(waiter.await.unwrap(), Err(Rejection::Timeout));
+        assert_eq!(admission.rejected(), 2);
+        assert_eq!(admission.queued(), 0);
+
+        // 名额释放后可以再次进入
+        drop(first);
+        assert!(admission.admit().await.is_ok());
+        assert_eq!(admission.retry_after_secs(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_admission_limit_follows_pool_size() {
+        let admission = AdmissionController::new(2, 0, Duration::from_millis(10));
+        let first = admission.admit().await.unwrap();
+        let second = admission.admit_job().await;
+
+        // 线程池缩小：正在进行的计算不受影响，结束后名额不再归还
+        admission.set_max_in_flight(1);
+        assert_eq!(admission.in_flight(), 2);
+        drop(first);
+        assert_eq!(admission.in_flight(), 1);
+        assert_eq!(admission.admit().await.unwrap_err(), Rejection::QueueFull);
+        drop(second);
+        let third = admission.admit().await.unwrap();
+        assert_eq!(admission.admit().await.unwrap_err(), Rejection::QueueFull);
+
+        // 线程池扩大后立即增加名额
+        admission.set_max_in_flight(3);
+        let _more = (admission.admit().await.unwrap(), admission.admit().await.unwrap());
+        assert_eq!(admission.in_flight(), 3);
+        drop(third);
+        assert_eq!(admission.in_flight(), 2);
+    }
+
+    #[test]
+    fn test_queue_on_virtual_clock() {
+        let clock = Arc::new(VirtualClock::new());
+        let admission = AdmissionController::new(1, 1, Duration::from_secs(2)).with_clock(Arc::clone(&clock) as _);
+        let running = admission.try_admit().unwrap();
+        assert!(admission.try_admit().is_none());
+
+        // 排队期限按虚拟时钟计算
+        clock.advance_to(Duration::from_secs(5));
+        let ticket = admission.enqueue().unwrap();
+        assert_eq!(admission.queue_deadline(&ticket), clock.now() + Duration::from_secs(2));
+        assert_eq!(admission.enqueue().unwrap_err(), Rejection::QueueFull);
+
+        assert_eq!(admission.time_out(ticket), Rejection::Timeout);
+        assert_eq!((admission.queued(), admission.rejected()), (0, 2));
+        drop(running);
+        assert!(admission.try_admit().is_some());
+    }
+}
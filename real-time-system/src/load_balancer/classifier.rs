@@ -0,0 +1,209 @@
+// 负载级别分类
+// 综合进行中的请求数、最近的 p95 延迟和进程 CPU 使用率判断负载级别。
+// 进入和退出每个级别使用不同的阈值（滞回），级别变化后至少保持最短停留时间，
+// 避免负载在阈值附近波动时级别每个检查周期都来回切换。
+
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+use super::LoadLevel;
+
+/// 一组阈值：任一信号达到对应阈值即视为“超过”
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LevelThresholds {
+    /// 进行中的请求数
+    pub active_requests: usize,
+    /// 最近请求的 p95 延迟（毫秒）
+    pub p95_latency_ms: f64,
+    /// 进程 CPU 使用率（0–1，按 CPU 核数归一化）
+    pub cpu_usage: f64,
+}
+
+/// 分类所用的负载信号
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct LoadSignals {
+    pub active_requests: usize,
+    pub p95_latency_ms: f64,
+    /// 无法读取时为 None（如非 Linux 系统）
+    pub cpu_usage: Option<f64>,
+}
+
+impl LoadSignals {
+    fn exceeds(&self, thresholds: &LevelThresholds) -> bool {
+        self.active_requests >= thresholds.active_requests
+            || self.p95_latency_ms >= thresholds.p95_latency_ms
+            || self.cpu_usage.is_some_and(|cpu| cpu >= thresholds.cpu_usage)
+    }
+}
+
+/// 分类器配置；退出阈值应比对应的进入阈值更宽松（high_exit ≤ high_enter，low_exit ≥ low_enter）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClassifierConfig {
+    /// 任一信号达到即进入高负载
+    pub high_enter: LevelThresholds,
+    /// 所有信号都低于时退出高负载
+    pub high_exit: LevelThresholds,
+    /// 所有信号都低于时进入低负载
+    pub low_enter: LevelThresholds,
+    /// 任一信号达到即退出低负载
+    pub low_exit: LevelThresholds,
+    /// 级别变化后的最短停留时间
+    pub min_dwell: std::time::Duration,
+}
+
+/// 带滞回和最短停留时间的负载级别分类器
+#[derive(Debug)]
+pub struct LoadClassifier {
+    config: ClassifierConfig,
+    level: LoadLevel,
+    since: Instant,
+    signals: LoadSignals,
+}
+
+impl LoadClassifier {
+    /// 创建分类器，now 为创建时刻（由负载均衡器的时钟提供）
+    pub fn new(config: ClassifierConfig, now: Instant) -> Self {
+        Self {
+            config,
+            level: LoadLevel::Low,
+            // 启动后第一次分类不受停留时间限制
+            since: now.checked_sub(config.min_dwell).unwrap_or(now),
+            signals: LoadSignals::default(),
+        }
+    }
+
+    pub fn level(&self) -> LoadLevel {
+        self.level
+    }
+
+    /// 替换阈值和停留时间，保留当前级别
+    pub fn set_config(&mut self, config: ClassifierConfig) {
+        self.config = config;
+    }
+
+    /// 最近一次分类使用的信号
+    pub fn signals(&self) -> LoadSignals {
+        self.signals
+    }
+
+    /// 根据新的信号更新并返回负载级别
+    pub fn update(&mut self, signals: LoadSignals, now: Instant) -> LoadLevel {
+        self.signals = signals;
+        let target = self.target_level(&signals);
+        if target != self.level && now.saturating_duration_since(self.since) >= self.config.min_dwell {
+            log::info!("Load level {:?} -> {:?} ({:?})", self.level, target, signals);
+            self.level = target;
+            self.since = now;
+        }
+        self.level
+    }
+
+    fn target_level(&self, signals: &LoadSignals) -> LoadLevel {
+        let config = &self.config;
+        match self.level {
+            LoadLevel::High if signals.exceeds(&config.high_exit) => LoadLevel::High,
+            LoadLevel::Low if !signals.exceeds(&config.low_exit) => LoadLevel::Low,
+            _ if signals.exceeds(&config.high_enter) => LoadLevel::High,
+            _ if !signals.exceeds(&config.low_enter) => LoadLevel::Low,
+            _ => LoadLevel::Normal,
+        }
+    }
+}
+
+/// 根据 /proc/self/stat 中累计的 CPU 时间计算两次采样之间的 CPU 使用率
+#[derive(Debug, Default)]
+pub struct CpuSampler {
+    last: Option<(Instant, f64)>,
+}
+
+impl CpuSampler {
+    /// 距上次采样的 CPU 使用率（按 CPU 核数归一化）；首次采样或无法读取时返回 None
+    pub fn sample(&mut self) -> Option<f64> {
+        let now = Instant::now();
+        let cpu_secs = read_process_cpu_secs()?;
+        let previous = self.last.replace((now, cpu_secs));
+        let (then, previous_secs) = previous?;
+        let elapsed = now.saturating_duration_since(then).as_secs_f64();
+        if elapsed <= 0.0 {
+            return None;
+        }
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get()) as f64;
+        Some(((cpu_secs - previous_secs) / elapsed / cores).clamp(0.0, 1.0))
+    }
+}
+
+/// 进程累计的用户态 + 内核态 CPU 时间（秒）
+fn read_process_cpu_secs() -> Option<f64> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    let ticks_per_sec = clock_ticks_per_sec()?;
+    parse_cpu_ticks(&stat).map(|ticks| ticks as f64 / ticks_per_sec)
+}
+
+/// /proc/self/stat 中 CPU 时间的单位（USER_HZ）
+fn clock_ticks_per_sec() -> Option<f64> {
+    // SAFETY: sysconf 只读取系统配置，没有内存安全方面的前置条件
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    (ticks > 0).then_some(ticks as f64)
+}
+
+/// 解析 utime + stime（第 14、15 个字段）；进程名可能包含空格，从最后一个 ')' 之后开始计数
+fn parse_cpu_ticks(stat: &str) -> Option<u64> {
+    let fields: Vec<&str> = stat.get(stat.rfind(')')? + 1..)?.split_whitespace().collect();
+    // ')' 之后的第一个字段是第 3 个字段（state）
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn thresholds(active_requests: usize, p95_latency_ms: f64, cpu_usage: f64) -> LevelThresholds {
+        LevelThresholds { active_requests, p95_latency_ms, cpu_usage }
+    }
+
+    fn signals(active_requests: usize, p95_latency_ms: f64) -> LoadSignals {
+        LoadSignals { active_requests, p95_latency_ms, cpu_usage: Some(0.1) }
+    }
+
+    #[test]
+    fn test_hysteresis_and_dwell() {
+        let config = ClassifierConfig {
+            high_enter: thresholds(10, 1000.0, 0.9),
+            high_exit: thresholds(6, 500.0, 0.7),
+            low_enter: thresholds(2, 100.0, 0.3),
+            low_exit: thresholds(4, 200.0, 0.5),
+            min_dwell: Duration::from_secs(5),
+        };
+        let start = Instant::now();
+        let mut classifier = LoadClassifier::new(config, start);
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // 低负载下介于 low_enter 与 low_exit 之间时保持不变
+        assert_eq!(classifier.update(signals(3, 0.0), at(0)), LoadLevel::Low);
+        // 延迟过高直接进入高负载（首次变化不受停留时间限制）
+        assert_eq!(classifier.update(signals(1, 1500.0), at(1)), LoadLevel::High);
+        // 停留时间未到，负载回落也保持高负载
+        assert_eq!(classifier.update(signals(1, 0.0), at(3)), LoadLevel::High);
+        // 低于进入阈值但仍高于退出阈值，保持高负载
+        assert_eq!(classifier.update(signals(8, 0.0), at(10)), LoadLevel::High);
+        assert_eq!(classifier.update(signals(5, 0.0), at(11)), LoadLevel::Normal);
+        assert_eq!(classifier.update(signals(1, 0.0), at(13)), LoadLevel::Normal);
+        assert_eq!(classifier.update(signals(1, 0.0), at(16)), LoadLevel::Low);
+
+        // CPU 使用率同样参与判断
+        let busy = LoadSignals { cpu_usage: Some(0.95), ..signals(0, 0.0) };
+        assert_eq!(classifier.update(busy, at(30)), LoadLevel::High);
+        assert_eq!(classifier.signals(), busy);
+    }
+
+    #[test]
+    fn test_parse_cpu_ticks() {
+        let stat = "1234 (real time (sys)) S 1 1234 1234 0 -1 4194560 100 0 0 0 250 50 0 0 20 0 4 0 100 0 0";
+        assert_eq!(parse_cpu_ticks(stat), Some(300));
+        assert_eq!(parse_cpu_ticks("garbage"), None);
+        assert!(clock_ticks_per_sec().is_some_and(|ticks| ticks > 0.0));
+    }
+}
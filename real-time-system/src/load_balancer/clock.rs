@@ -0,0 +1,60 @@
+// 时钟
+// 负载均衡器通过 `Clock` 读取当前时间（负载历史、延迟窗口、负载级别的停留时间），
+// 服务运行时使用系统时钟；模拟器使用手动推进的虚拟时钟，不需要真正等待。
+
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+pub trait Clock: Send + Sync + Debug {
+    fn now(&self) -> Instant;
+}
+
+/// 系统单调时钟
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// 虚拟时钟：从创建时刻开始，只在调用 `advance_to` 时前进
+#[derive(Debug)]
+pub struct VirtualClock {
+    start: Instant,
+    elapsed_nanos: AtomicU64,
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed_nanos: AtomicU64::new(0),
+        }
+    }
+
+    /// 推进到创建后的 elapsed 时刻（不会后退）
+    pub fn advance_to(&self, elapsed: Duration) {
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        self.elapsed_nanos.fetch_max(nanos, Ordering::SeqCst);
+    }
+
+    /// 创建以来经过的虚拟时间
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.elapsed_nanos.load(Ordering::SeqCst))
+    }
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+}
@@ -0,0 +1,133 @@
+// 负载均衡器配置的校验
+// 运行时更新配置（`PUT /api/admin/load-balancer/config`）前先整体校验，任何一项不合法都不会应用。
+// 准入控制的排队参数和负载历史的容量在创建时确定，只能重启后修改。
+
+use super::LoadBalancerConfig;
+
+/// 检查间隔下限（毫秒）
+const MIN_CHECK_INTERVAL_MS: u64 = 100;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("invalid load balancer config: {0}")]
+    Invalid(String),
+
+    #[error("'{0}' cannot be changed at runtime, restart the server to apply it")]
+    RestartRequired(&'static str),
+}
+
+impl LoadBalancerConfig {
+    /// 检查配置是否合法
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |message: &str| Err(ConfigError::Invalid(message.to_string()));
+
+        let signals = [
+            (
+                "active_requests",
+                [
+                    self.low_enter.active_requests as f64,
+                    self.low_exit.active_requests as f64,
+                    self.high_exit.active_requests as f64,
+                    self.high_enter.active_requests as f64,
+                ],
+            ),
+            (
+                "p95_latency_ms",
+                [self.low_enter.p95_latency_ms, self.low_exit.p95_latency_ms, self.high_exit.p95_latency_ms, self.high_enter.p95_latency_ms],
+            ),
+            (
+                "cpu_usage",
+                [self.low_enter.cpu_usage, self.low_exit.cpu_usage, self.high_exit.cpu_usage, self.high_enter.cpu_usage],
+            ),
+        ];
+        for (name, [low_enter, low_exit, high_exit, high_enter]) in signals {
+            // 比较中出现 NaN 时不成立，同样视为不合法
+            let ordered = low_enter >= 0.0 && low_enter <= low_exit && low_exit <= high_exit && high_exit <= high_enter;
+            if !ordered || low_enter >= high_enter {
+                return Err(ConfigError::Invalid(format!(
+                    "{} thresholds must satisfy 0 <= low_enter <= low_exit <= high_exit <= high_enter and low_enter < high_enter",
+                    name
+                )));
+            }
+        }
+
+        if self.max_query_threads < 2 || self.max_compute_threads < 2 {
+            return invalid("max_query_threads and max_compute_threads must be at least 2");
+        }
+        if self.check_interval_ms < MIN_CHECK_INTERVAL_MS {
+            return Err(ConfigError::Invalid(format!("check_interval_ms must be at least {}", MIN_CHECK_INTERVAL_MS)));
+        }
+        if self.latency_window_ms == 0 {
+            return invalid("latency_window_ms must be positive");
+        }
+        let unit = |value: f64| value > 0.0 && value <= 1.0;
+        if !unit(self.holt_alpha) || !unit(self.holt_beta) || !unit(self.ewma_alpha) {
+            return invalid("holt_alpha, holt_beta and ewma_alpha must be in (0, 1]");
+        }
+        if self.forecast_horizon == 0 || self.history_capacity == 0 {
+            return invalid("forecast_horizon and history_capacity must be positive");
+        }
+        Ok(())
+    }
+
+    /// 检查从 current 更新为本配置时，是否修改了只能重启后生效的项
+    pub fn check_runtime_update(&self, current: &LoadBalancerConfig) -> Result<(), ConfigError> {
+        let restart_only = [
+            ("max_queue_length", self.max_queue_length != current.max_queue_length),
+            ("max_queue_wait_ms", self.max_queue_wait_ms != current.max_queue_wait_ms),
+            ("history_capacity", self.history_capacity != current.history_capacity),
+            ("ewma_alpha", self.ewma_alpha != current.ewma_alpha),
+        ];
+        match restart_only.into_iter().find(|(_, changed)| *changed) {
+            Some((name, _)) => Err(ConfigError::RestartRequired(name)),
+            None => Ok(()),
+        }
+    }
+
+    /// 预测式扩缩容的参数是否与 other 不同（不同时需要重建预测器）
+    pub(super) fn prediction_changed(&self, other: &LoadBalancerConfig) -> bool {
+        self.predictive_scaling != other.predictive_scaling
+            || self.holt_alpha != other.holt_alpha
+            || self.holt_beta != other.holt_beta
+            || self.forecast_horizon != other.forecast_horizon
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_balancer::classifier::LevelThresholds;
+
+    #[test]
+    fn test_validate() {
+        let config = LoadBalancerConfig::default();
+        assert!(config.validate().is_ok());
+
+        // 低负载阈值高于高负载阈值
+        let inverted = LoadBalancerConfig {
+            low_enter: LevelThresholds { active_requests: 30, ..config.low_enter },
+            ..config.clone()
+        };
+        assert!(matches!(inverted.validate(), Err(ConfigError::Invalid(message)) if message.starts_with("active_requests")));
+        let nan = LoadBalancerConfig {
+            high_exit: LevelThresholds { p95_latency_ms: f64::NAN, ..config.high_exit },
+            ..config.clone()
+        };
+        assert!(nan.validate().is_err());
+
+        let single_thread = LoadBalancerConfig { max_query_threads: 1, ..config.clone() };
+        assert!(single_thread.validate().is_err());
+        let zero_interval = LoadBalancerConfig { check_interval_ms: 0, ..config.clone() };
+        assert!(zero_interval.validate().is_err());
+    }
+
+    #[test]
+    fn test_check_runtime_update() {
+        let current = LoadBalancerConfig::default();
+        let live = LoadBalancerConfig { check_interval_ms: 1000, max_query_threads: 16, ..current.clone() };
+        assert!(live.check_runtime_update(&current).is_ok());
+
+        let restart = LoadBalancerConfig { max_queue_length: 1, ..current.clone() };
+        assert!(matches!(restart.check_runtime_update(&current), Err(ConfigError::RestartRequired("max_queue_length"))));
+    }
+}
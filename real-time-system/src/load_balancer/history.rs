@@ -0,0 +1,424 @@
+// 负载历史时间序列
+// 监控任务每个检查周期记录一个带时间戳的样本（活跃请求、延迟、排队深度、线程数），
+// 保存在固定容量的环形缓冲区中；另外单独保存最近请求的延迟，用于计算窗口内的百分位数。
+// 延迟按时间片保存（保留时长由负载均衡器按最长可查询的窗口设置）：每个时间片记录请求总数，
+// 并用蓄水池抽样保留有限个样本，计算百分位数时按各时间片的请求数加权合并。
+// 内存占用只与时间片个数上限有关，与请求速率无关；排序在锁外进行，不阻塞记录延迟的请求。
+// EWMA 随每个样本更新，用于平滑瞬时波动；它不受查询窗口限制，覆盖自启动以来的全部样本（越旧权重越小）。
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 每个时间片最多保留的延迟样本数
+const SLICE_SAMPLES: usize = 64;
+/// 时间片个数上限，时间片长度为保留时长除以此值
+const MAX_SLICES: u32 = 3600;
+/// 最短的时间片
+const MIN_SLICE: Duration = Duration::from_secs(1);
+
+/// 固定容量的环形缓冲区，写满后覆盖最旧的元素
+#[derive(Debug)]
+pub struct RingBuffer<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.items.len() == self.capacity {
+            self.items.pop_front();
+        }
+        self.items.push_back(item);
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// 从旧到新遍历
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+        self.items.iter()
+    }
+}
+
+/// 一个负载样本
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadSample {
+    #[serde(skip)]
+    pub at: Instant,
+    pub timestamp: DateTime<Utc>,
+    pub active_requests: usize,
+    /// 上一个样本以来完成的请求数
+    pub completed_requests: u64,
+    /// 上一个样本以来完成的请求的平均延迟（没有完成的请求时为 None）
+    pub mean_latency_ms: Option<f64>,
+    /// 等待准入和等待计算线程的请求数
+    pub queue_depth: usize,
+    pub in_flight_compute: usize,
+    pub worker_threads: usize,
+    pub compute_threads: usize,
+}
+
+/// 记录样本时由负载均衡器提供的指标
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SampleMetrics {
+    pub active_requests: usize,
+    pub queue_depth: usize,
+    pub in_flight_compute: usize,
+    pub worker_threads: usize,
+    pub compute_threads: usize,
+}
+
+/// 百分位数（最近秩法）；没有数据时均为 0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Percentiles {
+    pub count: usize,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+impl Percentiles {
+    pub fn from_values(values: Vec<f64>) -> Self {
+        let count = values.len();
+        Self::from_weighted(values.into_iter().map(|value| (value, 1.0)).collect(), count)
+    }
+
+    /// 带权重的样本（每个样本代表 weight 个值），count 为代表的值的总数
+    pub fn from_weighted(mut values: Vec<(f64, f64)>, count: usize) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+        values.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let total: f64 = values.iter().map(|&(_, weight)| weight).sum();
+        let rank = |p: f64| {
+            let target = p * total;
+            let mut cumulative = 0.0;
+            for &(value, weight) in &values {
+                cumulative += weight;
+                if cumulative >= target {
+                    return value;
+                }
+            }
+            values[values.len() - 1].0
+        };
+        Self {
+            count,
+            p50: rank(0.50),
+            p95: rank(0.95),
+            p99: rank(0.99),
+        }
+    }
+}
+
+/// 一个时间窗口内的负载汇总
+#[derive(Debug, Clone, Serialize)]
+pub struct HistorySummary {
+    pub window_secs: u64,
+    /// 活跃请求数的 EWMA（全部样本，不限于窗口）
+    pub ewma_active_requests: f64,
+    /// 各检查周期平均延迟的 EWMA（全部样本，不限于窗口；没有完成请求的周期不参与）
+    pub ewma_latency_ms: f64,
+    pub latency_ms: Percentiles,
+    pub active_requests: Percentiles,
+    pub queue_depth: Percentiles,
+    /// 窗口内的样本（从旧到新）
+    pub samples: Vec<LoadSample>,
+}
+
+/// 一个时间片内结束的请求的延迟
+#[derive(Debug)]
+struct LatencySlice {
+    start: Instant,
+    /// 最后一个请求的结束时间
+    last: Instant,
+    count: u64,
+    /// 蓄水池抽样保留的延迟（毫秒）
+    samples: Vec<f64>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    samples: RingBuffer<LoadSample>,
+    /// 保留时长内的延迟时间片（从旧到新）
+    latencies: VecDeque<LatencySlice>,
+    latency_retention: Duration,
+    /// 蓄水池抽样的 xorshift 状态
+    rng: u64,
+    /// 上一个样本以来完成的请求的延迟之和与个数
+    pending_latency_ms: f64,
+    pending_count: u64,
+    ewma_active: Option<f64>,
+    ewma_latency: Option<f64>,
+}
+
+impl Inner {
+    /// 将延迟计入 now 所在的时间片
+    fn push_latency(&mut self, ms: f64, now: Instant) {
+        let slice_len = (self.latency_retention / MAX_SLICES).max(MIN_SLICE);
+        let current = self
+            .latencies
+            .back_mut()
+            .filter(|slice| now.saturating_duration_since(slice.start) < slice_len);
+        let Some(slice) = current else {
+            self.latencies.push_back(LatencySlice { start: now, last: now, count: 1, samples: vec![ms] });
+            if self.latencies.len() > MAX_SLICES as usize {
+                self.latencies.pop_front();
+            }
+            return;
+        };
+        slice.count += 1;
+        slice.last = slice.last.max(now);
+        if slice.samples.len() < SLICE_SAMPLES {
+            slice.samples.push(ms);
+        } else {
+            // 蓄水池抽样：第 count 个值以 SLICE_SAMPLES / count 的概率替换一个已保留的样本
+            self.rng ^= self.rng << 13;
+            self.rng ^= self.rng >> 7;
+            self.rng ^= self.rng << 17;
+            let index = (self.rng % slice.count) as usize;
+            if index < SLICE_SAMPLES {
+                slice.samples[index] = ms;
+            }
+        }
+    }
+
+    /// 删除超过保留时长的时间片
+    fn expire_latencies(&mut self, now: Instant) {
+        while let Some(slice) = self.latencies.front() {
+            if now.saturating_duration_since(slice.last) <= self.latency_retention {
+                break;
+            }
+            self.latencies.pop_front();
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LoadHistory {
+    inner: Mutex<Inner>,
+    /// EWMA 平滑系数（0, 1]，越大越偏重最新样本
+    alpha: f64,
+}
+
+impl LoadHistory {
+    /// capacity 个样本；请求延迟保留 latency_retention（应不短于要查询的最长窗口）
+    pub fn new(capacity: usize, alpha: f64, latency_retention: Duration) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                samples: RingBuffer::new(capacity),
+                latencies: VecDeque::new(),
+                latency_retention,
+                rng: 0x9E37_79B9_7F4A_7C15,
+                pending_latency_ms: 0.0,
+                pending_count: 0,
+                ewma_active: None,
+                ewma_latency: None,
+            }),
+            alpha: alpha.clamp(f64::EPSILON, 1.0),
+        }
+    }
+
+    /// 记录一个在 now 结束的请求的延迟
+    pub fn record_latency_at(&self, latency: Duration, now: Instant) {
+        let ms = latency.as_secs_f64() * 1000.0;
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.push_latency(ms, now);
+            inner.pending_latency_ms += ms;
+            inner.pending_count += 1;
+            inner.expire_latencies(now);
+        }
+    }
+
+    /// 调整请求延迟的保留时长（缩短时在下一次记录延迟时清理，之后的时间片按新的长度划分）
+    pub fn set_latency_retention(&self, retention: Duration) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.latency_retention = retention;
+        }
+    }
+
+    /// 记录 now 时刻的样本并更新 EWMA
+    pub fn record_at(&self, metrics: SampleMetrics, now: Instant) -> Option<LoadSample> {
+        let mut inner = self.inner.lock().ok()?;
+        let mean_latency_ms = (inner.pending_count > 0).then(|| inner.pending_latency_ms / inner.pending_count as f64);
+        let sample = LoadSample {
+            at: now,
+            timestamp: Utc::now(),
+            active_requests: metrics.active_requests,
+            completed_requests: inner.pending_count,
+            mean_latency_ms,
+            queue_depth: metrics.queue_depth,
+            in_flight_compute: metrics.in_flight_compute,
+            worker_threads: metrics.worker_threads,
+            compute_threads: metrics.compute_threads,
+        };
+        inner.pending_latency_ms = 0.0;
+        inner.pending_count = 0;
+
+        inner.ewma_active = Some(self.smooth(inner.ewma_active, metrics.active_requests as f64));
+        if let Some(latency) = mean_latency_ms {
+            inner.ewma_latency = Some(self.smooth(inner.ewma_latency, latency));
+        }
+        inner.samples.push(sample.clone());
+        Some(sample)
+    }
+
+    fn smooth(&self, previous: Option<f64>, value: f64) -> f64 {
+        match previous {
+            Some(previous) => self.alpha * value + (1.0 - self.alpha) * previous,
+            None => value,
+        }
+    }
+
+    /// 保存的样本数
+    pub fn len(&self) -> usize {
+        self.inner.lock().map(|inner| inner.samples.len()).unwrap_or(0)
+    }
+
+    /// 所有保存样本的平均活跃请求数
+    pub fn average_active_requests(&self) -> usize {
+        let Ok(inner) = self.inner.lock() else {
+            return 0;
+        };
+        match inner.samples.len() {
+            0 => 0,
+            len => inner.samples.iter().map(|s| s.active_requests).sum::<usize>() / len,
+        }
+    }
+
+    /// now 之前 window 内的请求延迟百分位数（按时间片的最后一个请求判断是否在窗口内）
+    pub fn latency_percentiles_at(&self, window: Duration, now: Instant) -> Percentiles {
+        let (values, count) = {
+            let Ok(inner) = self.inner.lock() else {
+                return Percentiles::default();
+            };
+            let slices = || inner.latencies.iter().rev().take_while(|slice| now.saturating_duration_since(slice.last) <= window);
+            let values: Vec<(f64, f64)> = slices()
+                .flat_map(|slice| {
+                    let weight = slice.count as f64 / slice.samples.len() as f64;
+                    slice.samples.iter().map(move |&ms| (ms, weight))
+                })
+                .collect();
+            (values, slices().map(|slice| slice.count as usize).sum())
+        };
+        // 在锁外排序
+        Percentiles::from_weighted(values, count)
+    }
+
+    /// now 之前 window 内的样本及汇总
+    pub fn summary_at(&self, window: Duration, now: Instant) -> HistorySummary {
+        let latency_ms = self.latency_percentiles_at(window, now);
+        let Ok(inner) = self.inner.lock() else {
+            return HistorySummary {
+                window_secs: window.as_secs(),
+                ewma_active_requests: 0.0,
+                ewma_latency_ms: 0.0,
+                latency_ms,
+                active_requests: Percentiles::default(),
+                queue_depth: Percentiles::default(),
+                samples: Vec::new(),
+            };
+        };
+        let mut samples: Vec<LoadSample> = inner
+            .samples
+            .iter()
+            .rev()
+            .take_while(|s| now.saturating_duration_since(s.at) <= window)
+            .cloned()
+            .collect();
+        let (ewma_active, ewma_latency) = (inner.ewma_active, inner.ewma_latency);
+        drop(inner);
+        samples.reverse();
+
+        HistorySummary {
+            window_secs: window.as_secs(),
+            ewma_active_requests: ewma_active.unwrap_or(0.0),
+            ewma_latency_ms: ewma_latency.unwrap_or(0.0),
+            latency_ms,
+            active_requests: Percentiles::from_values(samples.iter().map(|s| s.active_requests as f64).collect()),
+            queue_depth: Percentiles::from_values(samples.iter().map(|s| s.queue_depth as f64).collect()),
+            samples,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_buffer_and_percentiles() {
+        let mut ring = RingBuffer::new(3);
+        for i in 0..5 {
+            ring.push(i);
+        }
+        assert_eq!(ring.iter().copied().collect::<Vec<_>>(), vec![2, 3, 4]);
+
+        let percentiles = Percentiles::from_values((1..=100).map(f64::from).collect());
+        assert_eq!((percentiles.p50, percentiles.p95, percentiles.p99), (50.0, 95.0, 99.0));
+        assert_eq!(Percentiles::from_values(Vec::new()).count, 0);
+    }
+
+    #[test]
+    fn test_history_window_and_ewma() {
+        let history = LoadHistory::new(10, 0.5, Duration::from_secs(120));
+        let start = Instant::now();
+
+        history.record_latency_at(Duration::from_millis(100), start);
+        history.record_latency_at(Duration::from_millis(300), start);
+        let first = history.record_at(SampleMetrics { active_requests: 4, ..Default::default() }, start).unwrap();
+        assert_eq!(first.mean_latency_ms, Some(200.0));
+        assert_eq!(first.completed_requests, 2);
+
+        let later = start + Duration::from_secs(60);
+        history.record_latency_at(Duration::from_millis(50), later);
+        history.record_at(SampleMetrics { active_requests: 8, queue_depth: 2, ..Default::default() }, later);
+
+        // 10 秒窗口只包含第二个样本，EWMA 覆盖全部样本
+        let summary = history.summary_at(Duration::from_secs(10), later);
+        assert_eq!(summary.samples.len(), 1);
+        assert_eq!(summary.latency_ms.count, 1);
+        assert_eq!(summary.queue_depth.p99, 2.0);
+        assert_eq!(summary.ewma_active_requests, 6.0);
+        assert_eq!(summary.ewma_latency_ms, 125.0);
+
+        let summary = history.summary_at(Duration::from_secs(120), later);
+        assert_eq!(summary.samples.len(), 2);
+        assert_eq!(summary.latency_ms.p99, 300.0);
+        assert_eq!(history.average_active_requests(), 6);
+    }
+
+    #[test]
+    fn test_latency_retention() {
+        let history = LoadHistory::new(10, 0.5, Duration::from_secs(60));
+        let start = Instant::now();
+
+        // 保留时长内的请求全部计入总数，每个时间片只保留有限个样本
+        for i in 0..10_000u64 {
+            history.record_latency_at(Duration::from_millis(i % 100), start + Duration::from_millis(i));
+        }
+        let now = start + Duration::from_secs(10);
+        let percentiles = history.latency_percentiles_at(Duration::from_secs(60), now);
+        assert_eq!(percentiles.count, 10_000);
+        assert!((40.0..=60.0).contains(&percentiles.p50) && percentiles.p99 >= 90.0);
+        let inner = history.inner.lock().unwrap();
+        assert!(inner.latencies.iter().all(|slice| slice.samples.len() <= SLICE_SAMPLES));
+        drop(inner);
+
+        // 超过保留时长的记录在下一次记录时清理
+        history.record_latency_at(Duration::from_millis(5), start + Duration::from_secs(80));
+        let later = start + Duration::from_secs(80);
+        assert_eq!(history.latency_percentiles_at(Duration::from_secs(3600), later).count, 1);
+    }
+}
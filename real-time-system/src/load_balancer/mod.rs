@@ -1,7 +1,26 @@
+pub mod admission;
+pub mod classifier;
+pub mod clock;
+pub mod config;
+pub mod history;
+pub mod simulator;
+pub mod predictor;
+pub mod proxy;
+pub mod tracking;
+
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
 use tokio::time::{self, Duration};
-use dashmap::DashMap;
+use crate::compute::ComputePool;
+use serde::{Deserialize, Serialize};
+use admission::{AdmissionController, AdmissionPermit, Rejection};
+use clock::{Clock, SystemClock};
+use config::ConfigError;
+use classifier::{ClassifierConfig, CpuSampler, LevelThresholds, LoadClassifier, LoadSignals};
+use predictor::{PredictionStats, PredictiveScaler, ScalingDecision};
+use history::{HistorySummary, LoadHistory, SampleMetrics};
+use tracking::{RequestGuard, RequestTracker, RouteStats};
 
 /// 负载均衡器状态
 #[derive(Debug, Clone)]
@@ -10,60 +29,247 @@ pub struct LoadBalancer {
     active_requests: Arc<AtomicUsize>,
     /// 当前worker线程数（可动态调整）
     current_worker_threads: Arc<AtomicUsize>,
-    /// 历史负载数据（用于趋势分析）
-    load_history: Arc<DashMap<String, Vec<usize>>>,
-    /// 配置参数
-    config: LoadBalancerConfig,
+    /// 负载历史时间序列（用于趋势分析）
+    history: Arc<LoadHistory>,
+    /// 配置参数（可在运行时整体替换，监控任务订阅其变化）
+    config: Arc<watch::Sender<LoadBalancerConfig>>,
+    /// 计算线程池，线程数随 `calculate_compute_threads` 调整
+    compute_pool: Option<Arc<ComputePool>>,
+    /// 计算请求的准入控制
+    admission: Arc<AdmissionController>,
+    /// 各路由的请求计数
+    routes: Arc<RequestTracker>,
+    /// 负载级别分类器（由监控任务定期更新）
+    classifier: Arc<Mutex<LoadClassifier>>,
+    /// 负载级别变化通知（订阅者如空闲预计算在负载上升时立即暂停）
+    level_watch: Arc<watch::Sender<LoadLevel>>,
+    /// 进程 CPU 使用率采样（使用虚拟时钟时为 None）
+    cpu: Option<Arc<Mutex<CpuSampler>>>,
+    /// 时间来源（模拟时为虚拟时钟）
+    clock: Arc<dyn Clock>,
+    /// 预测式扩缩容（未启用时为 None）
+    predictive: Arc<Mutex<Option<PredictiveScaler>>>,
 }
 
-/// 负载均衡器配置
-#[derive(Debug, Clone)]
+/// 负载均衡器配置（JSON 中缺少的字段使用默认值）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct LoadBalancerConfig {
-    /// 低负载阈值（请求数低于此值为低负载）
-    pub low_load_threshold: usize,
-    /// 高负载阈值（请求数高于此值为高负载）
-    pub high_load_threshold: usize,
+    /// 任一信号达到即进入高负载
+    pub high_enter: LevelThresholds,
+    /// 所有信号都低于时退出高负载（应不高于 high_enter）
+    pub high_exit: LevelThresholds,
+    /// 所有信号都低于时进入低负载
+    pub low_enter: LevelThresholds,
+    /// 任一信号达到即退出低负载（应不低于 low_enter）
+    pub low_exit: LevelThresholds,
+    /// 负载级别变化后的最短停留时间（毫秒）
+    pub min_dwell_ms: u64,
+    /// 计算 p95 延迟的时间窗口（毫秒）
+    pub latency_window_ms: u64,
+    /// 启用预测式扩缩容：按负载趋势提前分配线程
+    pub predictive_scaling: bool,
+    /// Holt 平滑的水平系数（0, 1]
+    pub holt_alpha: f64,
+    /// Holt 平滑的趋势系数（0, 1]
+    pub holt_beta: f64,
+    /// 预测提前的检查周期数
+    pub forecast_horizon: usize,
     /// 检查间隔（毫秒）
     pub check_interval_ms: u64,
     /// 最大计算线程数
     pub max_compute_threads: usize,
     /// 最大查询线程数
     pub max_query_threads: usize,
+    /// 等待计算名额的请求数上限
+    pub max_queue_length: usize,
+    /// 请求等待计算名额的最长时间（毫秒）
+    pub max_queue_wait_ms: u64,
+    /// 保存的负载样本数（每个检查周期一个）
+    pub history_capacity: usize,
+    /// 负载 EWMA 的平滑系数（0, 1]
+    pub ewma_alpha: f64,
 }
 
 impl Default for LoadBalancerConfig {
     fn default() -> Self {
         Self {
-            low_load_threshold: 5,
-            high_load_threshold: 20,
+            high_enter: LevelThresholds { active_requests: 20, p95_latency_ms: 2000.0, cpu_usage: 0.9 },
+            high_exit: LevelThresholds { active_requests: 15, p95_latency_ms: 1000.0, cpu_usage: 0.75 },
+            low_enter: LevelThresholds { active_requests: 5, p95_latency_ms: 100.0, cpu_usage: 0.3 },
+            low_exit: LevelThresholds { active_requests: 8, p95_latency_ms: 250.0, cpu_usage: 0.5 },
+            min_dwell_ms: 10_000,
+            latency_window_ms: 30_000,
             check_interval_ms: 5000,  // 5秒
             max_compute_threads: 4,
             max_query_threads: 8,
+            max_queue_length: 32,
+            max_queue_wait_ms: 2000,
+            history_capacity: 720,
+            ewma_alpha: 0.3,
+            predictive_scaling: false,
+            holt_alpha: 0.5,
+            holt_beta: 0.3,
+            forecast_horizon: 2,
         }
     }
 }
 
 impl LoadBalancer {
-    /// 创建新的负载均衡器
-    pub fn new(config: LoadBalancerConfig) -> Self {
+    /// 创建新的负载均衡器；配置不合法（如阈值顺序颠倒）时返回错误
+    pub fn new(config: LoadBalancerConfig) -> Result<Self, ConfigError> {
+        config.validate()?;
         let initial_threads = config.max_query_threads;
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
 
-        Self {
+        Ok(Self {
             active_requests: Arc::new(AtomicUsize::new(0)),
             current_worker_threads: Arc::new(AtomicUsize::new(initial_threads)),
-            load_history: Arc::new(DashMap::new()),
-            config,
+            history: Arc::new(LoadHistory::new(
+                config.history_capacity,
+                config.ewma_alpha,
+                Self::latency_retention(&config),
+            )),
+            admission: Arc::new(Self::admission(&config, Arc::clone(&clock))),
+            classifier: Arc::new(Mutex::new(Self::classifier(&config, clock.as_ref()))),
+            level_watch: Arc::new(watch::Sender::new(LoadLevel::Low)),
+            compute_pool: None,
+            routes: Arc::new(RequestTracker::default()),
+            cpu: Some(Arc::new(Mutex::new(CpuSampler::default()))),
+            clock,
+            predictive: Arc::new(Mutex::new(Self::predictive_scaler(&config))),
+            config: Arc::new(watch::Sender::new(config)),
+        })
+    }
+
+    fn classifier_config(config: &LoadBalancerConfig) -> ClassifierConfig {
+        ClassifierConfig {
+            high_enter: config.high_enter,
+            high_exit: config.high_exit,
+            low_enter: config.low_enter,
+            low_exit: config.low_exit,
+            min_dwell: Duration::from_millis(config.min_dwell_ms),
         }
     }
 
-    /// 增加活跃请求计数
-    pub fn increment_request(&self) {
-        self.active_requests.fetch_add(1, Ordering::SeqCst);
+    /// 请求延迟的保留时长：负载级别使用的延迟窗口和样本覆盖的时间（负载历史可查询的最长窗口）中较长者
+    fn latency_retention(config: &LoadBalancerConfig) -> Duration {
+        let history_span = config.check_interval_ms.saturating_mul(config.history_capacity as u64);
+        Duration::from_millis(config.latency_window_ms.max(history_span))
+    }
+
+    /// 计算名额等于计算线程数，随线程池扩缩容调整（见 `adjust_worker_threads`）
+    fn admission(config: &LoadBalancerConfig, clock: Arc<dyn Clock>) -> AdmissionController {
+        AdmissionController::new(
+            config.max_compute_threads,
+            config.max_queue_length,
+            Duration::from_millis(config.max_queue_wait_ms),
+        )
+        .with_clock(clock)
+    }
+
+    fn classifier(config: &LoadBalancerConfig, clock: &dyn Clock) -> LoadClassifier {
+        LoadClassifier::new(Self::classifier_config(config), clock.now())
+    }
+
+    fn predictive_scaler(config: &LoadBalancerConfig) -> Option<PredictiveScaler> {
+        config
+            .predictive_scaling
+            .then(|| PredictiveScaler::new(config.holt_alpha, config.holt_beta, config.forecast_horizon))
+    }
+
+    /// 当前配置
+    pub fn config(&self) -> LoadBalancerConfig {
+        self.config.borrow().clone()
+    }
+
+    /// 校验并整体替换配置：负载级别阈值、停留时间、检查间隔、线程数上限和预测参数立即生效，
+    /// 准入控制和负载历史的参数只能重启后修改。返回替换前的配置
+    pub fn update_config(&self, config: LoadBalancerConfig) -> Result<LoadBalancerConfig, ConfigError> {
+        config.validate()?;
+        let mut result = Ok(());
+        let mut previous = None;
+        // 在配置的写锁内完成全部替换，其他线程不会看到新旧混合的配置
+        self.config.send_if_modified(|current| {
+            result = config.check_runtime_update(current);
+            if result.is_err() {
+                return false;
+            }
+            if let Ok(mut classifier) = self.classifier.lock() {
+                classifier.set_config(Self::classifier_config(&config));
+            }
+            self.history.set_latency_retention(Self::latency_retention(&config));
+            if config.prediction_changed(current) {
+                if let Ok(mut scaler) = self.predictive.lock() {
+                    *scaler = Self::predictive_scaler(&config);
+                }
+            }
+            previous = Some(std::mem::replace(current, config.clone()));
+            true
+        });
+        result?;
+        log::info!("Load balancer config updated: {:?}", config);
+
+        // 按新的线程数上限重新分配线程
+        self.adjust_worker_threads();
+        Ok(previous.unwrap_or(config))
+    }
+
+    /// 使用指定的时钟（如模拟器的虚拟时钟）；进程 CPU 使用率在虚拟时间下没有意义，不再采样
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.classifier = Arc::new(Mutex::new(Self::classifier(&self.config(), clock.as_ref())));
+        self.admission = Arc::new(Self::admission(&self.config(), Arc::clone(&clock)));
+        self.cpu = None;
+        self.clock = clock;
+        self
+    }
+
+    /// 关联计算线程池，之后每次调整线程数时同步调整其大小
+    pub fn with_compute_pool(mut self, pool: Arc<ComputePool>) -> Self {
+        pool.resize(self.calculate_compute_threads());
+        self.compute_pool = Some(pool);
+        self
+    }
+
+    /// 开始跟踪 route 上的一个活跃请求，返回的守卫被丢弃时计数自动减一
+    /// （请求的 future 被提前丢弃或处理函数 panic 时也能正确计数）
+    pub fn track_request(&self, route: &str) -> RequestGuard {
+        self.routes.begin(route, Arc::clone(&self.active_requests))
+    }
+
+    /// 各路由的请求统计
+    pub fn route_stats(&self) -> Vec<RouteStats> {
+        self.routes.snapshot()
+    }
+
+    /// 记录一个已结束请求的延迟
+    pub fn record_latency(&self, latency: Duration) {
+        self.history.record_latency_at(latency, self.clock.now());
     }
 
-    /// 减少活跃请求计数
-    pub fn decrement_request(&self) {
-        self.active_requests.fetch_sub(1, Ordering::SeqCst);
+    /// 最近 window 内的负载历史及汇总
+    pub fn history_summary(&self, window: Duration) -> HistorySummary {
+        self.history.summary_at(window, self.clock.now())
+    }
+
+    /// 为一次计算申请准入许可（缓存命中的请求无需申请）
+    pub async fn admit_compute(&self) -> Result<AdmissionPermit, Rejection> {
+        self.admission.admit().await
+    }
+
+    /// 有空闲名额时立即取得准入许可，不排队（空闲时的预计算使用）
+    pub fn try_admit_compute(&self) -> Option<AdmissionPermit> {
+        self.admission.try_admit()
+    }
+
+    /// 为长时间任务申请准入许可，等到有空闲名额为止
+    pub async fn admit_job(&self) -> AdmissionPermit {
+        self.admission.admit_job().await
+    }
+
+    /// 被拒绝的请求建议的重试等待秒数
+    pub fn retry_after_secs(&self) -> u64 {
+        self.admission.retry_after_secs()
     }
 
     /// 获取当前活跃请求数
@@ -71,17 +277,43 @@ impl LoadBalancer {
         self.active_requests.load(Ordering::SeqCst)
     }
 
-    /// 获取当前负载级别
+    /// 获取当前负载级别（最近一次 `update_load_level` 的结果）
     pub fn get_load_level(&self) -> LoadLevel {
-        let current = self.get_active_requests();
+        self.classifier.lock().map_or(LoadLevel::Normal, |classifier| classifier.level())
+    }
 
-        if current < self.config.low_load_threshold {
-            LoadLevel::Low
-        } else if current > self.config.high_load_threshold {
-            LoadLevel::High
-        } else {
-            LoadLevel::Normal
-        }
+    /// 进程 CPU 使用率（距上次采样）
+    fn sample_cpu(&self) -> Option<f64> {
+        self.cpu.as_ref()?.lock().ok()?.sample()
+    }
+
+    /// 结合进行中的请求数、p95 延迟和给定的 CPU 使用率重新判断负载级别
+    pub fn update_load_level(&self, cpu_usage: Option<f64>) -> LoadLevel {
+        let now = self.clock.now();
+        let signals = LoadSignals {
+            active_requests: self.get_active_requests(),
+            p95_latency_ms: self
+                .history
+                .latency_percentiles_at(Duration::from_millis(self.config().latency_window_ms), now)
+                .p95,
+            cpu_usage,
+        };
+        let level = match self.classifier.lock() {
+            Ok(mut classifier) => classifier.update(signals, now),
+            Err(_) => LoadLevel::Normal,
+        };
+        self.level_watch.send_if_modified(|current| std::mem::replace(current, level) != level);
+        level
+    }
+
+    /// 订阅负载级别变化
+    pub fn subscribe_load_level(&self) -> watch::Receiver<LoadLevel> {
+        self.level_watch.subscribe()
+    }
+
+    /// 最近一次判断负载级别时使用的信号
+    pub fn load_signals(&self) -> LoadSignals {
+        self.classifier.lock().map(|classifier| classifier.signals()).unwrap_or_default()
     }
 
     /// 获取当前worker线程数
@@ -90,55 +322,108 @@ impl LoadBalancer {
     }
 
     /// 动态调整worker线程数（核心功能）
+    /// 启用预测式扩缩容时，按预测的负载级别与当前级别中较高者分配线程
     pub fn adjust_worker_threads(&self) -> usize {
         let current_load = self.get_active_requests();
         let load_level = self.get_load_level();
         let current_threads = self.get_current_worker_threads();
 
-        // 根据负载级别调整线程数
-        let new_threads = match load_level {
+        let forecast = self
+            .predictive
+            .lock()
+            .ok()
+            .and_then(|scaler| scaler.as_ref().and_then(|scaler| scaler.forecast()));
+        let predicted_level = forecast.map(|forecast| self.level_for_active_requests(forecast));
+        let effective_level = predicted_level.map_or(load_level, |predicted| predicted.max(load_level));
+        let new_threads = self.query_threads_for(effective_level, current_load);
+
+        // 如果线程数有变化，记录日志
+        if new_threads != current_threads {
+            self.current_worker_threads.store(new_threads, Ordering::SeqCst);
+            log::info!(
+                "Adjusted worker threads: {} -> {} (load: {:?}, effective: {:?}, active requests: {})",
+                current_threads,
+                new_threads,
+                load_level,
+                effective_level,
+                current_load
+            );
+        }
+
+        // 查询线程减少时计算线程相应增加，反之亦然
+        let compute_threads = self.calculate_compute_threads();
+        if let Some(pool) = &self.compute_pool {
+            pool.resize(compute_threads);
+        }
+        self.admission.set_max_in_flight(compute_threads);
+
+        if let Some(scaler) = self.predictive.lock().ok().as_mut().and_then(|scaler| scaler.as_mut()) {
+            scaler.record(ScalingDecision {
+                timestamp: chrono::Utc::now(),
+                active_requests: current_load,
+                forecast_active_requests: forecast,
+                load_level,
+                predicted_level,
+                effective_level,
+                query_threads: new_threads,
+                compute_threads,
+                pre_scaled: effective_level > load_level,
+            });
+        }
+
+        new_threads
+    }
+
+    /// 各负载级别对应的查询线程数
+    fn query_threads_for(&self, load_level: LoadLevel, current_load: usize) -> usize {
+        let max_query_threads = self.config().max_query_threads;
+        let threads = match load_level {
             LoadLevel::Low => {
                 // 低负载：减少线程数（但至少保留2个）
-                2.max(self.config.max_query_threads / 2)
+                2.max(max_query_threads / 2)
             }
             LoadLevel::Normal => {
                 // 正常负载：根据当前请求数调整
                 if current_load < 5 {
-                    self.config.max_query_threads / 2
+                    max_query_threads / 2
                 } else {
-                    self.config.max_query_threads * 2 / 3
+                    max_query_threads * 2 / 3
                 }
             }
             LoadLevel::High => {
                 // 高负载：最大化查询线程
-                self.config.max_query_threads
+                max_query_threads
             }
         };
 
         // 限制在合理范围内
-        let new_threads = new_threads.clamp(2, self.config.max_query_threads);
+        threads.clamp(2, max_query_threads)
+    }
 
-        // 如果线程数有变化，记录日志
-        if new_threads != current_threads {
-            self.current_worker_threads.store(new_threads, Ordering::SeqCst);
-            log::info!(
-                "Adjusted worker threads: {} -> {} (load: {}, active requests: {})",
-                current_threads,
-                new_threads,
-                match load_level {
-                    LoadLevel::Low => "Low",
-                    LoadLevel::Normal => "Normal",
-                    LoadLevel::High => "High",
-                },
-                current_load
-            );
+    /// 只按活跃请求数判断的负载级别（用于预测值）
+    fn level_for_active_requests(&self, active_requests: f64) -> LoadLevel {
+        let config = self.config();
+        if active_requests >= config.high_enter.active_requests as f64 {
+            LoadLevel::High
+        } else if active_requests >= config.low_exit.active_requests as f64 {
+            LoadLevel::Normal
+        } else {
+            LoadLevel::Low
         }
+    }
 
-        new_threads
+    /// 预测效果统计（未启用预测式扩缩容时为 None）
+    pub fn prediction_stats(&self) -> Option<PredictionStats> {
+        self.predictive
+            .lock()
+            .ok()
+            .and_then(|scaler| scaler.as_ref().map(|scaler| scaler.stats()))
     }
-    /// 计算应该分配给计算（挖矿）的线程数
+
+    /// 计算应该分配给计算的线程数（低负载时空闲的计算线程用于预计算，见 `precompute`）
     pub fn calculate_compute_threads(&self) -> usize {
-        let total_threads = self.config.max_compute_threads + self.config.max_query_threads;
+        let config = self.config();
+        let total_threads = config.max_compute_threads + config.max_query_threads;
         let query_threads = self.get_current_worker_threads();
 
         // 剩余线程给计算（至少保留1个）
@@ -150,48 +435,76 @@ impl LoadBalancer {
         self.get_current_worker_threads()
     }
 
-    /// 记录负载历史（用于分析和调试）
-    pub fn record_load_history(&self) {
-        let current = self.get_active_requests();
-        let timestamp = chrono::Local::now().format("%H:%M:%S").to_string();
-
-        self.load_history
-            .entry("active_requests".to_string())
-            .or_insert_with(Vec::new)
-            .push(current);
+    /// 当前的负载指标（排队深度包括等待准入和等待计算线程的请求）
+    pub fn current_metrics(&self) -> SampleMetrics {
+        let pool_queued = self.compute_pool.as_ref().map_or(0, |pool| pool.queued());
+        let compute_threads = self
+            .compute_pool
+            .as_ref()
+            .map_or_else(|| self.calculate_compute_threads(), |pool| pool.target_threads());
+        SampleMetrics {
+            active_requests: self.get_active_requests(),
+            queue_depth: self.admission.queued() + pool_queued,
+            in_flight_compute: self.admission.in_flight(),
+            worker_threads: self.get_current_worker_threads(),
+            compute_threads,
+        }
+    }
 
-        // 限制历史记录长度
-        if let Some(mut history) = self.load_history.get_mut("active_requests") {
-            if history.len() > 100 {
-                history.remove(0);
-            }
+    /// 记录负载历史（用于分析和调试）
+    pub fn record_sample(&self, metrics: SampleMetrics) {
+        let Some(sample) = self.history.record_at(metrics, self.clock.now()) else {
+            return;
+        };
+        if let Some(scaler) = self.predictive.lock().ok().as_mut().and_then(|scaler| scaler.as_mut()) {
+            scaler.observe(sample.active_requests);
         }
 
         log::debug!(
-            "Load stats - Active: {}, Level: {:?}, Compute threads: {}, Query threads: {}",
-            current,
+            "[{}] Load stats - Active: {}, Level: {:?}, Latency: {:?} ms, Queue: {}, Compute threads: {}, Query threads: {}",
+            sample.timestamp.format("%H:%M:%S"),
+            sample.active_requests,
             self.get_load_level(),
-            self.calculate_compute_threads(),
-            self.calculate_query_threads()
+            sample.mean_latency_ms,
+            sample.queue_depth,
+            sample.compute_threads,
+            sample.worker_threads
         );
     }
 
+    /// 一个监控周期：记录负载、更新负载级别、调整线程数
+    /// （模拟器用模型给出的指标和 CPU 使用率直接驱动）
+    pub fn monitor_tick(&self, metrics: SampleMetrics, cpu_usage: Option<f64>) {
+        self.record_sample(metrics);
+        self.update_load_level(cpu_usage);
+        self.adjust_worker_threads();
+    }
+
     /// 启动负载监控任务
     pub async fn start_monitoring(self: Arc<Self>) {
         log::info!("Starting load balancer monitoring and auto-adjustment");
 
-        let mut interval = time::interval(Duration::from_millis(self.config.check_interval_ms));
+        let mut config = self.config.subscribe();
+        let mut interval = time::interval(Duration::from_millis(config.borrow_and_update().check_interval_ms));
 
         loop {
-            interval.tick().await;
-
-            // 1. 记录当前负载
-            self.record_load_history();
+            tokio::select! {
+                _ = interval.tick() => {}
+                Ok(()) = config.changed() => {
+                    // 配置更新后按新的检查间隔重新计时
+                    let period = Duration::from_millis(config.borrow_and_update().check_interval_ms);
+                    if period != interval.period() {
+                        log::info!("Load check interval changed to {:?}", period);
+                        interval = time::interval_at(time::Instant::now() + period, period);
+                    }
+                    continue;
+                }
+            }
 
-            // 2. 动态调整线程数（核心）
-            self.adjust_worker_threads();
+            // 1. 记录当前负载、更新负载级别并动态调整线程数（核心）
+            self.monitor_tick(self.current_metrics(), self.sample_cpu());
 
-            // 3. 根据负载级别记录日志
+            // 2. 根据负载级别记录日志
             let load_level = self.get_load_level();
             let current_load = self.get_active_requests();
             let current_threads = self.get_current_worker_threads();
@@ -218,31 +531,26 @@ impl LoadBalancer {
         let current = self.get_active_requests();
         let level = self.get_load_level();
 
-        let avg_load = if let Some(history) = self.load_history.get("active_requests") {
-            if !history.is_empty() {
-                history.iter().sum::<usize>() / history.len()
-            } else {
-                0
-            }
-        } else {
-            0
-        };
-
         LoadBalancerStats {
             active_requests: current,
             load_level: level,
             recommended_compute_threads: self.calculate_compute_threads(),
             recommended_query_threads: self.calculate_query_threads(),
-            average_load: avg_load,
-            history_size: self.load_history.get("active_requests")
-                .map(|h| h.len())
-                .unwrap_or(0),
+            average_load: self.history.average_active_requests(),
+            in_flight_compute: self.admission.in_flight(),
+            max_in_flight_compute: self.admission.max_in_flight(),
+            queued_compute: self.admission.queued(),
+            rejected_requests: self.admission.rejected(),
+            routes: self.route_stats(),
+            signals: self.load_signals(),
+            prediction: self.prediction_stats(),
+            history_size: self.history.len(),
         }
     }
 }
 
-/// 负载级别
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// 负载级别（按从低到高排序）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub enum LoadLevel {
     Low,
     Normal,
@@ -258,4 +566,96 @@ pub struct LoadBalancerStats {
     pub recommended_query_threads: usize,
     pub average_load: usize,
     pub history_size: usize,
-}
\ No newline at end of file
+    pub in_flight_compute: usize,
+    /// 计算名额上限（等于计算线程数）
+    pub max_in_flight_compute: usize,
+    pub queued_compute: usize,
+    pub rejected_requests: u64,
+    pub routes: Vec<RouteStats>,
+    pub signals: LoadSignals,
+    pub prediction: Option<PredictionStats>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adjust_resizes_compute_pool() {
+        let pool = Arc::new(ComputePool::new(1));
+        let load_balancer = LoadBalancer::new(LoadBalancerConfig::default()).unwrap().with_compute_pool(Arc::clone(&pool));
+        // 初始查询线程为 max_query_threads，计算线程为剩余的 4 个
+        assert_eq!(pool.target_threads(), 4);
+
+        // 低负载时查询线程减半，让出的线程交给计算线程池
+        assert_eq!(load_balancer.adjust_worker_threads(), 4);
+        assert_eq!(pool.target_threads(), 8);
+        assert_eq!(pool.live_threads(), 8);
+    }
+
+    #[test]
+    fn test_new_validates_config() {
+        let inverted = LoadBalancerConfig {
+            low_enter: LoadBalancerConfig::default().high_enter,
+            ..LoadBalancerConfig::default()
+        };
+        assert!(matches!(LoadBalancer::new(inverted), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_track_request_feeds_load_level() {
+        // 只按请求数分类，避免并行运行的测试占用 CPU 影响结果（其他信号总是低于低负载阈值、达不到高负载阈值）
+        let active_only = |active_requests, other| LevelThresholds {
+            active_requests,
+            p95_latency_ms: other,
+            cpu_usage: other,
+        };
+        let load_balancer = LoadBalancer::new(LoadBalancerConfig {
+            high_enter: active_only(2, f64::INFINITY),
+            high_exit: active_only(2, f64::INFINITY),
+            low_enter: active_only(1, f64::MAX),
+            low_exit: active_only(1, f64::MAX),
+            min_dwell_ms: 0,
+            ..LoadBalancerConfig::default()
+        })
+        .unwrap();
+        let guards: Vec<_> = (0..2).map(|_| load_balancer.track_request("GET /api/factorize/{number}")).collect();
+        assert_eq!(load_balancer.get_active_requests(), 2);
+        assert_eq!(load_balancer.update_load_level(None), LoadLevel::High);
+        assert_eq!(load_balancer.get_stats().signals.active_requests, 2);
+
+        // 守卫被丢弃（请求被取消）后负载回落
+        drop(guards);
+        assert_eq!(load_balancer.update_load_level(None), LoadLevel::Low);
+        assert_eq!(load_balancer.get_stats().routes[0].cancelled, 2);
+    }
+
+    #[test]
+    fn test_predictive_pre_scaling() {
+        let load_balancer = LoadBalancer::new(LoadBalancerConfig {
+            predictive_scaling: true,
+            holt_alpha: 1.0,
+            holt_beta: 1.0,
+            forecast_horizon: 3,
+            ..LoadBalancerConfig::default()
+        })
+        .unwrap();
+        assert!(LoadBalancer::new(LoadBalancerConfig::default()).unwrap().prediction_stats().is_none());
+
+        // 活跃请求每个周期增加 4 个：当前为 8，3 个周期后预计为 20，达到高负载
+        let mut guards = Vec::new();
+        for _ in 0..3 {
+            guards.extend((0..4).map(|_| load_balancer.track_request("GET /")));
+            load_balancer.record_sample(load_balancer.current_metrics());
+        }
+        assert_eq!(load_balancer.get_load_level(), LoadLevel::Low);
+        assert_eq!(load_balancer.adjust_worker_threads(), load_balancer.config().max_query_threads);
+
+        let stats = load_balancer.prediction_stats().unwrap();
+        assert_eq!(stats.forecast_active_requests, Some(24.0));
+        let decision = stats.recent_decisions.last().unwrap();
+        assert_eq!(decision.effective_level, LoadLevel::High);
+        assert!(decision.pre_scaled);
+        assert_eq!(stats.pre_scaled_decisions, 1);
+    }
+}
@@ -0,0 +1,201 @@
+// 预测式扩缩容
+// 用 Holt 线性指数平滑（水平 + 趋势）拟合每个检查周期的活跃请求数，预测 horizon 个周期之后的负载。
+// 预测会进入更高的负载级别时提前按该级别分配线程，负载真正到来时线程已经就绪；
+// 预测只用于提前扩容，不会提前缩容。每次预测在 horizon 个周期后与实际值比较，用于评估预测效果。
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+
+use super::history::RingBuffer;
+use super::LoadLevel;
+
+/// 保留的扩缩容决策数
+const DECISION_CAPACITY: usize = 50;
+
+/// Holt 线性指数平滑
+#[derive(Debug, Clone)]
+pub struct HoltPredictor {
+    /// 水平平滑系数（0, 1]
+    alpha: f64,
+    /// 趋势平滑系数（0, 1]
+    beta: f64,
+    level: Option<f64>,
+    trend: f64,
+}
+
+impl HoltPredictor {
+    pub fn new(alpha: f64, beta: f64) -> Self {
+        Self {
+            alpha: alpha.clamp(f64::EPSILON, 1.0),
+            beta: beta.clamp(f64::EPSILON, 1.0),
+            level: None,
+            trend: 0.0,
+        }
+    }
+
+    /// 加入一个观测值
+    pub fn observe(&mut self, value: f64) {
+        self.level = Some(match self.level {
+            None => value,
+            Some(level) => {
+                let next = self.alpha * value + (1.0 - self.alpha) * (level + self.trend);
+                self.trend = self.beta * (next - level) + (1.0 - self.beta) * self.trend;
+                next
+            }
+        });
+    }
+
+    /// steps 个周期之后的预测值（不小于 0）；还没有观测值时返回 None
+    pub fn forecast(&self, steps: usize) -> Option<f64> {
+        self.level.map(|level| (level + self.trend * steps as f64).max(0.0))
+    }
+}
+
+/// 一次扩缩容决策
+#[derive(Debug, Clone, Serialize)]
+pub struct ScalingDecision {
+    pub timestamp: DateTime<Utc>,
+    pub active_requests: usize,
+    pub forecast_active_requests: Option<f64>,
+    /// 分类器给出的当前负载级别
+    pub load_level: LoadLevel,
+    /// 按预测值判断的负载级别
+    pub predicted_level: Option<LoadLevel>,
+    /// 实际用于分配线程的级别
+    pub effective_level: LoadLevel,
+    pub query_threads: usize,
+    pub compute_threads: usize,
+    /// 是否因预测而提前扩容
+    pub pre_scaled: bool,
+}
+
+/// 预测效果统计及最近的决策（`/api/load-stats`）
+#[derive(Debug, Clone, Serialize)]
+pub struct PredictionStats {
+    pub horizon_steps: usize,
+    pub level: Option<f64>,
+    pub trend: f64,
+    pub forecast_active_requests: Option<f64>,
+    /// 已与实际值比较的预测数
+    pub evaluated_forecasts: u64,
+    /// 平均绝对误差（活跃请求数）
+    pub mean_absolute_error: Option<f64>,
+    pub pre_scaled_decisions: u64,
+    /// 最近的决策（从旧到新）
+    pub recent_decisions: Vec<ScalingDecision>,
+}
+
+/// 预测器、待评估的预测和决策记录
+#[derive(Debug)]
+pub struct PredictiveScaler {
+    predictor: HoltPredictor,
+    horizon: usize,
+    /// 尚未到期的预测（最早的在前），到期后与实际值比较
+    pending: VecDeque<f64>,
+    evaluated: u64,
+    absolute_error_sum: f64,
+    pre_scaled: u64,
+    decisions: RingBuffer<ScalingDecision>,
+}
+
+impl PredictiveScaler {
+    pub fn new(alpha: f64, beta: f64, horizon: usize) -> Self {
+        Self {
+            predictor: HoltPredictor::new(alpha, beta),
+            horizon: horizon.max(1),
+            pending: VecDeque::new(),
+            evaluated: 0,
+            absolute_error_sum: 0.0,
+            pre_scaled: 0,
+            decisions: RingBuffer::new(DECISION_CAPACITY),
+        }
+    }
+
+    /// 加入本周期的活跃请求数，并返回 horizon 个周期之后的预测
+    pub fn observe(&mut self, active_requests: usize) -> Option<f64> {
+        let actual = active_requests as f64;
+        // horizon 个周期前做出的预测在本周期到期
+        if self.pending.len() >= self.horizon {
+            if let Some(predicted) = self.pending.pop_front() {
+                self.evaluated += 1;
+                self.absolute_error_sum += (predicted - actual).abs();
+            }
+        }
+
+        self.predictor.observe(actual);
+        let forecast = self.predictor.forecast(self.horizon);
+        if let Some(forecast) = forecast {
+            self.pending.push_back(forecast);
+        }
+        forecast
+    }
+
+    pub fn forecast(&self) -> Option<f64> {
+        self.predictor.forecast(self.horizon)
+    }
+
+    pub fn record(&mut self, decision: ScalingDecision) {
+        if decision.pre_scaled {
+            self.pre_scaled += 1;
+            log::info!(
+                "Pre-scaling for forecast load {:.1} ({:?} -> {:?})",
+                decision.forecast_active_requests.unwrap_or(0.0),
+                decision.load_level,
+                decision.effective_level
+            );
+        }
+        self.decisions.push(decision);
+    }
+
+    pub fn stats(&self) -> PredictionStats {
+        PredictionStats {
+            horizon_steps: self.horizon,
+            level: self.predictor.level,
+            trend: self.predictor.trend,
+            forecast_active_requests: self.forecast(),
+            evaluated_forecasts: self.evaluated,
+            mean_absolute_error: (self.evaluated > 0).then(|| self.absolute_error_sum / self.evaluated as f64),
+            pre_scaled_decisions: self.pre_scaled,
+            recent_decisions: self.decisions.iter().cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_holt_follows_linear_trend() {
+        let mut predictor = HoltPredictor::new(0.8, 0.5);
+        assert_eq!(predictor.forecast(1), None);
+        for value in [0.0, 2.0, 4.0, 6.0, 8.0, 10.0, 12.0, 14.0] {
+            predictor.observe(value);
+        }
+        // 每个周期增加 2，3 个周期后约为 20
+        let forecast = predictor.forecast(3).unwrap();
+        assert!((forecast - 20.0).abs() < 1.0, "forecast {}", forecast);
+
+        // 下降趋势的预测不会小于 0
+        for value in [10.0, 5.0, 0.0] {
+            predictor.observe(value);
+        }
+        assert_eq!(predictor.forecast(20), Some(0.0));
+    }
+
+    #[test]
+    fn test_forecast_error_tracking() {
+        let mut scaler = PredictiveScaler::new(1.0, 1.0, 1);
+        // alpha = beta = 1 时预测为线性外推：观测 1 后预测 1，观测 3 后预测 5
+        assert_eq!(scaler.observe(1), Some(1.0));
+        assert_eq!(scaler.observe(3), Some(5.0));
+        scaler.observe(5);
+
+        let stats = scaler.stats();
+        // |1 - 3| 与 |5 - 5|
+        assert_eq!(stats.evaluated_forecasts, 2);
+        assert_eq!(stats.mean_absolute_error, Some(1.0));
+        assert_eq!(stats.forecast_active_requests, Some(7.0));
+    }
+}
@@ -0,0 +1,363 @@
+// 反向代理的后端集合
+// 代理模式下，前端实例把分解请求转发给一组后端实例（见 `web::proxy`）。后端按策略排序：
+// 进行中请求最少、按数做一致性哈希（同一个数总是落到同一个后端，命中其缓存）或轮询。
+// 健康检查定期请求后端的 `/api/health`，连续失败的后端被剔除，恢复后重新加入；
+// 转发时的连接失败同样计入连续失败次数。
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::time::{self, Duration};
+
+/// 后端的选择策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceStrategy {
+    /// 进行中请求最少的后端
+    LeastActive,
+    /// 按数的一致性哈希
+    ConsistentHash,
+    /// 轮询
+    RoundRobin,
+}
+
+impl FromStr for BalanceStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "least_active" => Ok(Self::LeastActive),
+            "consistent_hash" => Ok(Self::ConsistentHash),
+            "round_robin" => Ok(Self::RoundRobin),
+            _ => Err(format!(
+                "unknown balance strategy '{}', expected least_active, consistent_hash or round_robin",
+                s
+            )),
+        }
+    }
+}
+
+/// 代理配置（JSON 中缺少的字段使用默认值）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxyConfig {
+    /// 后端实例的地址，如 http://127.0.0.1:8081
+    pub backends: Vec<String>,
+    pub strategy: BalanceStrategy,
+    /// 健康检查间隔（毫秒）
+    pub health_check_interval_ms: u64,
+    /// 单次健康检查的超时（毫秒）
+    pub health_check_timeout_ms: u64,
+    /// 转发请求的超时（毫秒）
+    pub request_timeout_ms: u64,
+    /// 连续失败多少次后剔除
+    pub unhealthy_threshold: u32,
+    /// 被剔除的后端连续通过多少次健康检查后重新加入
+    pub healthy_threshold: u32,
+    /// 一致性哈希中每个后端的虚拟节点数
+    pub virtual_nodes: usize,
+    /// 一次请求最多尝试的后端数（前一个连接失败时换下一个）
+    pub max_attempts: usize,
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        Self {
+            backends: Vec::new(),
+            strategy: BalanceStrategy::LeastActive,
+            health_check_interval_ms: 2000,
+            health_check_timeout_ms: 1000,
+            request_timeout_ms: 60_000,
+            unhealthy_threshold: 2,
+            healthy_threshold: 1,
+            virtual_nodes: 100,
+            max_attempts: 2,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Backend {
+    url: String,
+    healthy: AtomicBool,
+    active: AtomicUsize,
+    consecutive_failures: AtomicU32,
+    consecutive_successes: AtomicU32,
+    forwarded: AtomicU64,
+    failed: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+/// 后端状态（`/api/admin/backends`）
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendStatus {
+    pub url: String,
+    pub healthy: bool,
+    pub active_requests: usize,
+    pub forwarded: u64,
+    /// 转发或健康检查失败的次数
+    pub failed: u64,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+}
+
+/// 转发期间持有，丢弃时后端的进行中请求数减一
+#[derive(Debug)]
+pub struct BackendGuard<'a> {
+    backend: &'a Backend,
+}
+
+impl Drop for BackendGuard<'_> {
+    fn drop(&mut self) {
+        self.backend.active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug)]
+pub struct BackendPool {
+    config: ProxyConfig,
+    backends: Vec<Backend>,
+    /// 一致性哈希环：(虚拟节点哈希, 后端下标)，按哈希排序
+    ring: Vec<(u64, usize)>,
+    /// 轮询位置
+    next: AtomicUsize,
+    client: reqwest::Client,
+}
+
+/// 稳定的 64 位哈希（与进程和 Rust 版本无关，重启后同一个数仍落到同一个后端）：
+/// FNV-1a 加 MurmurHash3 的最终混合，只差末位数字的短键也能均匀分布在环上
+fn stable_hash(bytes: &[u8]) -> u64 {
+    let mut hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3));
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
+
+impl BackendPool {
+    /// 创建后端集合，所有后端初始视为健康
+    pub fn new(config: ProxyConfig) -> Self {
+        let backends: Vec<Backend> = config
+            .backends
+            .iter()
+            .map(|url| Backend {
+                url: url.trim_end_matches('/').to_string(),
+                healthy: AtomicBool::new(true),
+                active: AtomicUsize::new(0),
+                consecutive_failures: AtomicU32::new(0),
+                consecutive_successes: AtomicU32::new(0),
+                forwarded: AtomicU64::new(0),
+                failed: AtomicU64::new(0),
+                last_error: Mutex::new(None),
+            })
+            .collect();
+
+        let mut ring: Vec<(u64, usize)> = backends
+            .iter()
+            .enumerate()
+            .flat_map(|(index, backend)| {
+                (0..config.virtual_nodes.max(1)).map(move |node| (stable_hash(format!("{}#{}", backend.url, node).as_bytes()), index))
+            })
+            .collect();
+        ring.sort_unstable();
+
+        Self {
+            config,
+            backends,
+            ring,
+            next: AtomicUsize::new(0),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn config(&self) -> &ProxyConfig {
+        &self.config
+    }
+
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    pub fn url(&self, index: usize) -> &str {
+        &self.backends[index].url
+    }
+
+    /// 按策略排好序的健康后端（最多 max_attempts 个），key 用于一致性哈希
+    pub fn candidates(&self, key: &str) -> Vec<usize> {
+        let healthy = |index: &usize| self.backends[*index].healthy.load(Ordering::SeqCst);
+        let count = self.backends.len();
+        if count == 0 {
+            return Vec::new();
+        }
+        let rotated = || {
+            let start = self.next.fetch_add(1, Ordering::SeqCst);
+            (0..count).map(move |i| (start + i) % count)
+        };
+        let mut order: Vec<usize> = match self.config.strategy {
+            BalanceStrategy::RoundRobin => rotated().filter(healthy).collect(),
+            BalanceStrategy::LeastActive => {
+                // 稳定排序：进行中请求数相同时按轮询顺序，避免总是落到第一个后端
+                let mut order: Vec<usize> = rotated().filter(healthy).collect();
+                order.sort_by_key(|&index| self.backends[index].active.load(Ordering::SeqCst));
+                order
+            }
+            BalanceStrategy::ConsistentHash => {
+                // 从 key 在环上的位置顺时针取不重复的健康后端
+                let key_hash = stable_hash(key.as_bytes());
+                let position = self.ring.partition_point(|&(hash, _)| hash < key_hash);
+                let mut order = Vec::new();
+                for &(_, index) in self.ring[position..].iter().chain(&self.ring[..position]) {
+                    if healthy(&index) && !order.contains(&index) {
+                        order.push(index);
+                        if order.len() == count {
+                            break;
+                        }
+                    }
+                }
+                order
+            }
+        };
+        order.truncate(self.config.max_attempts.max(1));
+        order
+    }
+
+    /// 开始向后端 index 转发一个请求
+    pub fn begin(&self, index: usize) -> BackendGuard<'_> {
+        let backend = &self.backends[index];
+        backend.active.fetch_add(1, Ordering::SeqCst);
+        backend.forwarded.fetch_add(1, Ordering::SeqCst);
+        BackendGuard { backend }
+    }
+
+    /// 后端响应正常（健康检查通过或转发时连接成功）
+    pub fn record_success(&self, index: usize) {
+        let backend = &self.backends[index];
+        backend.consecutive_failures.store(0, Ordering::SeqCst);
+        if backend.healthy.load(Ordering::SeqCst) {
+            return;
+        }
+        let successes = backend.consecutive_successes.fetch_add(1, Ordering::SeqCst) + 1;
+        if successes >= self.config.healthy_threshold {
+            backend.healthy.store(true, Ordering::SeqCst);
+            backend.consecutive_successes.store(0, Ordering::SeqCst);
+            log::info!("Backend {} is healthy again", backend.url);
+        }
+    }
+
+    /// 后端无法访问或健康检查失败，连续失败达到阈值时剔除
+    pub fn record_failure(&self, index: usize, error: String) {
+        let backend = &self.backends[index];
+        backend.failed.fetch_add(1, Ordering::SeqCst);
+        backend.consecutive_successes.store(0, Ordering::SeqCst);
+        let failures = backend.consecutive_failures.fetch_add(1, Ordering::SeqCst) + 1;
+        if failures >= self.config.unhealthy_threshold && backend.healthy.swap(false, Ordering::SeqCst) {
+            log::warn!("Backend {} ejected after {} consecutive failures: {}", backend.url, failures, error);
+        }
+        if let Ok(mut last_error) = backend.last_error.lock() {
+            *last_error = Some(error);
+        }
+    }
+
+    pub fn statuses(&self) -> Vec<BackendStatus> {
+        self.backends
+            .iter()
+            .map(|backend| BackendStatus {
+                url: backend.url.clone(),
+                healthy: backend.healthy.load(Ordering::SeqCst),
+                active_requests: backend.active.load(Ordering::SeqCst),
+                forwarded: backend.forwarded.load(Ordering::SeqCst),
+                failed: backend.failed.load(Ordering::SeqCst),
+                consecutive_failures: backend.consecutive_failures.load(Ordering::SeqCst),
+                last_error: backend.last_error.lock().ok().and_then(|error| error.clone()),
+            })
+            .collect()
+    }
+
+    /// 并发检查一遍所有后端的 `/api/health`；限流（429）和过载（503）的后端仍然存活，不剔除
+    pub async fn check_health(&self) {
+        let timeout = Duration::from_millis(self.config.health_check_timeout_ms);
+        let mut checks = tokio::task::JoinSet::new();
+        for (index, backend) in self.backends.iter().enumerate() {
+            let request = self.client.get(format!("{}/api/health", backend.url)).timeout(timeout);
+            checks.spawn(async move { (index, request.send().await) });
+        }
+        while let Some(Ok((index, result))) = checks.join_next().await {
+            match result {
+                Ok(response) if is_alive(response.status()) => self.record_success(index),
+                Ok(response) => self.record_failure(index, format!("health check returned {}", response.status())),
+                Err(e) => self.record_failure(index, e.to_string()),
+            }
+        }
+    }
+
+    /// 启动定期健康检查
+    pub async fn start_health_checks(self: Arc<Self>) {
+        log::info!(
+            "Proxying to {} backends ({:?}), health check every {} ms",
+            self.backends.len(),
+            self.config.strategy,
+            self.config.health_check_interval_ms
+        );
+        let mut interval = time::interval(Duration::from_millis(self.config.health_check_interval_ms.max(1)));
+        loop {
+            interval.tick().await;
+            self.check_health().await;
+        }
+    }
+}
+
+/// 健康检查的响应是否说明后端存活
+fn is_alive(status: reqwest::StatusCode) -> bool {
+    status.is_success()
+        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || status == reqwest::StatusCode::SERVICE_UNAVAILABLE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(strategy: BalanceStrategy, max_attempts: usize) -> BackendPool {
+        BackendPool::new(ProxyConfig {
+            backends: (1..=3).map(|i| format!("http://127.0.0.1:{}/", 9000 + i)).collect(),
+            strategy,
+            max_attempts,
+            ..ProxyConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_round_robin_and_least_active() {
+        let pool = pool(BalanceStrategy::RoundRobin, 1);
+        let picks: Vec<usize> = (0..4).map(|_| pool.candidates("")[0]).collect();
+        assert_eq!(picks, vec![0, 1, 2, 0]);
+        assert_eq!(pool.url(0), "http://127.0.0.1:9001");
+
+        let pool = self::pool(BalanceStrategy::LeastActive, 3);
+        let _busy = [pool.begin(0), pool.begin(0), pool.begin(2)];
+        assert_eq!(pool.candidates(""), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn test_consistent_hash_and_ejection() {
+        let pool = pool(BalanceStrategy::ConsistentHash, 3);
+        let order = pool.candidates("1234567");
+        assert_eq!(order.len(), 3);
+        assert_eq!(pool.candidates("1234567"), order);
+        // 不同的数分布到不同的后端
+        let primaries: std::collections::HashSet<usize> = (0..100).map(|n| pool.candidates(&n.to_string())[0]).collect();
+        assert_eq!(primaries.len(), 3);
+
+        // 连续失败两次后剔除，该数改由环上的下一个后端处理；恢复后回到原后端
+        pool.record_failure(order[0], "connection refused".to_string());
+        assert_eq!(pool.candidates("1234567")[0], order[0]);
+        pool.record_failure(order[0], "connection refused".to_string());
+        assert_eq!(pool.candidates("1234567"), order[1..].to_vec());
+        assert!(!pool.statuses()[order[0]].healthy);
+
+        pool.record_success(order[0]);
+        assert_eq!(pool.candidates("1234567"), order);
+    }
+}
@@ -0,0 +1,397 @@
+// 负载均衡器模拟器
+// 用虚拟时钟回放请求到达轨迹（离散事件模拟），不需要真正等待：
+// 每个请求通过 `track_request` 计入活跃请求，计算请求经负载均衡器自己的准入控制器（在虚拟时钟上）
+// 取得计算名额或进入有界队列，排队超时同样由准入控制器判定；
+// 正在计算的请求平分计算线程（处理器共享模型），每个检查周期执行一次 `monitor_tick`。
+// 相同的轨迹和配置总是得到相同的报告，可以离线比较不同的 `LoadBalancerConfig`。
+//
+// 用法：real-time-system simulate <trace.json> [config.json]
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::admission::{AdmissionPermit, QueueTicket, Rejection};
+use super::clock::{Clock, VirtualClock};
+use super::config::ConfigError;
+use super::history::{Percentiles, SampleMetrics};
+use super::tracking::{Outcome, RequestGuard};
+use super::{LoadBalancer, LoadBalancerConfig, LoadLevel};
+
+/// 模拟请求使用的路由名
+const SIMULATED_ROUTE: &str = "SIMULATED";
+
+/// 剩余计算量小于该值（毫秒）视为完成，避免浮点误差
+const EPSILON_MS: f64 = 1e-6;
+
+/// 轨迹中的一个请求
+#[derive(Debug, Clone, Deserialize)]
+pub struct TraceRequest {
+    /// 到达时间（相对模拟开始，毫秒）
+    pub at_ms: u64,
+    /// 独占一个计算线程时的计算时间（毫秒）；0 表示缓存命中，不占用计算名额
+    pub cost_ms: f64,
+}
+
+/// 线程分配或负载级别发生变化的监控周期
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ThreadDecision {
+    pub at_ms: f64,
+    pub load_level: LoadLevel,
+    pub active_requests: usize,
+    pub query_threads: usize,
+    pub compute_threads: usize,
+}
+
+/// 各负载级别停留的时间（毫秒）
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LevelTimes {
+    pub low_ms: f64,
+    pub normal_ms: f64,
+    pub high_ms: f64,
+}
+
+impl LevelTimes {
+    fn add(&mut self, level: LoadLevel, ms: f64) {
+        match level {
+            LoadLevel::Low => self.low_ms += ms,
+            LoadLevel::Normal => self.normal_ms += ms,
+            LoadLevel::High => self.high_ms += ms,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimulationReport {
+    pub duration_ms: f64,
+    pub requests: usize,
+    pub completed: u64,
+    /// 队列已满被拒绝的请求数
+    pub rejected_queue_full: u64,
+    /// 排队超时被拒绝的请求数
+    pub rejected_timeout: u64,
+    /// 已完成请求的模型延迟（含排队时间）
+    pub latency_ms: Percentiles,
+    pub mean_latency_ms: f64,
+    pub max_queue_depth: usize,
+    pub time_in_level: LevelTimes,
+    pub ticks: u64,
+    pub decisions: Vec<ThreadDecision>,
+}
+
+/// 正在计算的请求（完成时归还计算名额）
+struct Running {
+    arrival_ms: f64,
+    remaining_ms: f64,
+    guard: RequestGuard,
+    _permit: AdmissionPermit,
+}
+
+/// 等待计算名额的请求（按到达顺序，与准入控制器的信号量一致）
+struct Waiting {
+    arrival_ms: f64,
+    cost_ms: f64,
+    guard: RequestGuard,
+    ticket: QueueTicket,
+}
+
+struct Simulation {
+    config: LoadBalancerConfig,
+    clock: Arc<VirtualClock>,
+    load_balancer: LoadBalancer,
+    now_ms: f64,
+    running: Vec<Running>,
+    queue: VecDeque<Waiting>,
+    latencies: Vec<f64>,
+    report: SimulationReport,
+}
+
+/// 按 config 回放 trace（无需按到达时间排序）；配置不合法时返回错误
+pub fn simulate(config: LoadBalancerConfig, trace: &[TraceRequest]) -> Result<SimulationReport, ConfigError> {
+    let mut arrivals = trace.to_vec();
+    arrivals.sort_by_key(|request| request.at_ms);
+
+    let clock = Arc::new(VirtualClock::new());
+    let load_balancer = LoadBalancer::new(config.clone())?.with_clock(Arc::clone(&clock) as _);
+    let mut simulation = Simulation {
+        config,
+        clock,
+        load_balancer,
+        now_ms: 0.0,
+        running: Vec::new(),
+        queue: VecDeque::new(),
+        latencies: Vec::new(),
+        report: SimulationReport {
+            duration_ms: 0.0,
+            requests: arrivals.len(),
+            completed: 0,
+            rejected_queue_full: 0,
+            rejected_timeout: 0,
+            latency_ms: Percentiles::default(),
+            mean_latency_ms: 0.0,
+            max_queue_depth: 0,
+            time_in_level: LevelTimes::default(),
+            ticks: 0,
+            decisions: Vec::new(),
+        },
+    };
+    simulation.run(&arrivals);
+    Ok(simulation.finish())
+}
+
+impl Simulation {
+    fn run(&mut self, arrivals: &[TraceRequest]) {
+        let interval_ms = self.config.check_interval_ms.max(1) as f64;
+        let mut next_tick_ms = interval_ms;
+        let mut next_arrival = 0;
+
+        while next_arrival < arrivals.len() || !self.running.is_empty() || !self.queue.is_empty() {
+            let arrival_ms = arrivals.get(next_arrival).map_or(f64::INFINITY, |request| request.at_ms as f64);
+            let next_event_ms = arrival_ms
+                .min(next_tick_ms)
+                .min(self.next_completion_ms())
+                .min(self.next_timeout_ms());
+            self.advance_to(next_event_ms);
+
+            self.complete_finished();
+            self.expire_waiting();
+            self.admit_waiting();
+            while arrivals.get(next_arrival).is_some_and(|request| request.at_ms as f64 <= self.now_ms) {
+                self.arrive(&arrivals[next_arrival]);
+                next_arrival += 1;
+            }
+            if next_tick_ms <= self.now_ms + EPSILON_MS {
+                self.tick();
+                next_tick_ms += interval_ms;
+            }
+        }
+    }
+
+    /// 每个正在计算的请求获得的计算速度（线程不足时平分）
+    fn service_rate(&self) -> f64 {
+        let threads = self.load_balancer.calculate_compute_threads() as f64;
+        (threads / self.running.len().max(1) as f64).min(1.0)
+    }
+
+    fn next_completion_ms(&self) -> f64 {
+        let rate = self.service_rate();
+        self.running
+            .iter()
+            .map(|job| self.now_ms + job.remaining_ms / rate)
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    fn next_timeout_ms(&self) -> f64 {
+        self.queue.front().map_or(f64::INFINITY, |waiting| {
+            let deadline = self.load_balancer.admission.queue_deadline(&waiting.ticket);
+            self.now_ms + deadline.saturating_duration_since(self.clock.now()).as_secs_f64() * 1000.0
+        })
+    }
+
+    fn advance_to(&mut self, time_ms: f64) {
+        let elapsed_ms = (time_ms - self.now_ms).max(0.0);
+        let rate = self.service_rate();
+        for job in &mut self.running {
+            job.remaining_ms -= elapsed_ms * rate;
+        }
+        self.report.time_in_level.add(self.load_balancer.get_load_level(), elapsed_ms);
+        self.now_ms = time_ms.max(self.now_ms);
+        self.clock.advance_to(Duration::from_secs_f64(self.now_ms / 1000.0));
+    }
+
+    fn complete_finished(&mut self) {
+        let (finished, running): (Vec<_>, Vec<_>) = std::mem::take(&mut self.running)
+            .into_iter()
+            .partition(|job| job.remaining_ms <= EPSILON_MS);
+        self.running = running;
+        for job in finished {
+            self.complete(job.arrival_ms, job.guard);
+        }
+    }
+
+    fn complete(&mut self, arrival_ms: f64, guard: RequestGuard) {
+        let latency_ms = self.now_ms - arrival_ms;
+        self.load_balancer.record_latency(Duration::from_secs_f64(latency_ms / 1000.0));
+        self.latencies.push(latency_ms);
+        self.report.completed += 1;
+        guard.finish(Outcome::Completed);
+    }
+
+    /// 排队超过最长等待时间的请求被拒绝（503）
+    fn expire_waiting(&mut self) {
+        while self.next_timeout_ms() <= self.now_ms + EPSILON_MS {
+            if let Some(waiting) = self.queue.pop_front() {
+                self.reject(self.load_balancer.admission.time_out(waiting.ticket), waiting.guard);
+            }
+        }
+    }
+
+    /// 按到达顺序把空出的计算名额交给排队的请求
+    fn admit_waiting(&mut self) {
+        while !self.queue.is_empty() {
+            let Some(permit) = self.load_balancer.admission.try_admit() else {
+                break;
+            };
+            if let Some(waiting) = self.queue.pop_front() {
+                self.running.push(Running {
+                    arrival_ms: waiting.arrival_ms,
+                    remaining_ms: waiting.cost_ms,
+                    guard: waiting.guard,
+                    _permit: permit,
+                });
+            }
+        }
+    }
+
+    fn arrive(&mut self, request: &TraceRequest) {
+        let guard = self.load_balancer.track_request(SIMULATED_ROUTE);
+        let arrival_ms = request.at_ms as f64;
+        if request.cost_ms <= 0.0 {
+            self.complete(arrival_ms, guard);
+            return;
+        }
+        let admission = &self.load_balancer.admission;
+        if let Some(permit) = admission.try_admit() {
+            self.running.push(Running {
+                arrival_ms,
+                remaining_ms: request.cost_ms,
+                guard,
+                _permit: permit,
+            });
+            return;
+        }
+        match admission.enqueue() {
+            Ok(ticket) => {
+                self.queue.push_back(Waiting {
+                    arrival_ms,
+                    cost_ms: request.cost_ms,
+                    guard,
+                    ticket,
+                });
+                self.report.max_queue_depth = self.report.max_queue_depth.max(self.load_balancer.admission.queued());
+            }
+            Err(rejection) => self.reject(rejection, guard),
+        }
+    }
+
+    fn reject(&mut self, rejection: Rejection, guard: RequestGuard) {
+        match rejection {
+            Rejection::QueueFull => self.report.rejected_queue_full += 1,
+            Rejection::Timeout => self.report.rejected_timeout += 1,
+        }
+        guard.finish(Outcome::Failed);
+    }
+
+    fn tick(&mut self) {
+        let load_balancer = &self.load_balancer;
+        let compute_threads = load_balancer.calculate_compute_threads();
+        let total_threads = (self.config.max_compute_threads + self.config.max_query_threads).max(1);
+        // 模型 CPU 使用率：忙碌的计算线程占全部线程的比例
+        let cpu_usage = self.running.len().min(compute_threads) as f64 / total_threads as f64;
+        let metrics = SampleMetrics {
+            active_requests: load_balancer.get_active_requests(),
+            queue_depth: load_balancer.admission.queued(),
+            in_flight_compute: load_balancer.admission.in_flight(),
+            worker_threads: load_balancer.get_current_worker_threads(),
+            compute_threads,
+        };
+        load_balancer.monitor_tick(metrics, Some(cpu_usage));
+        self.report.ticks += 1;
+
+        let decision = ThreadDecision {
+            at_ms: self.now_ms,
+            load_level: load_balancer.get_load_level(),
+            active_requests: metrics.active_requests,
+            query_threads: load_balancer.calculate_query_threads(),
+            compute_threads: load_balancer.calculate_compute_threads(),
+        };
+        let changed = self.report.decisions.last().is_none_or(|last| {
+            (last.load_level, last.query_threads, last.compute_threads)
+                != (decision.load_level, decision.query_threads, decision.compute_threads)
+        });
+        if changed {
+            self.report.decisions.push(decision);
+        }
+    }
+
+    fn finish(mut self) -> SimulationReport {
+        self.report.duration_ms = self.now_ms;
+        if !self.latencies.is_empty() {
+            self.report.mean_latency_ms = self.latencies.iter().sum::<f64>() / self.latencies.len() as f64;
+        }
+        self.report.latency_ms = Percentiles::from_values(self.latencies);
+        self.report
+    }
+}
+
+/// 命令行入口：`simulate <trace.json> [config.json]`，报告以 JSON 输出到标准输出
+pub fn run_cli(args: &[String]) -> io::Result<()> {
+    let Some(trace_path) = args.first() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: real-time-system simulate <trace.json> [config.json]",
+        ));
+    };
+    let trace: Vec<TraceRequest> = serde_json::from_reader(io::BufReader::new(std::fs::File::open(trace_path)?))?;
+    let config: LoadBalancerConfig = match args.get(1) {
+        Some(path) => serde_json::from_reader(io::BufReader::new(std::fs::File::open(path)?))?,
+        None => LoadBalancerConfig::default(),
+    };
+    let report = simulate(config, &trace).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 每秒 rate 个请求，持续 secs 秒，每个请求计算 cost_ms
+    fn steady(start_ms: u64, secs: u64, rate: u64, cost_ms: f64) -> Vec<TraceRequest> {
+        (0..secs * rate)
+            .map(|i| TraceRequest { at_ms: start_ms + i * 1000 / rate, cost_ms })
+            .collect()
+    }
+
+    #[test]
+    fn test_simulation_is_deterministic() {
+        // 空闲 → 突发 → 空闲
+        let mut trace = steady(0, 20, 2, 50.0);
+        trace.extend(steady(20_000, 10, 40, 400.0));
+        trace.extend(steady(30_000, 60, 2, 0.0));
+
+        let config = LoadBalancerConfig {
+            check_interval_ms: 1000,
+            min_dwell_ms: 2000,
+            ..LoadBalancerConfig::default()
+        };
+        let report = simulate(config.clone(), &trace).unwrap();
+        assert_eq!(report, simulate(config, &trace).unwrap());
+
+        // 每个请求要么完成要么被拒绝，时间全部计入某个负载级别
+        assert_eq!(report.completed + report.rejected_queue_full + report.rejected_timeout, trace.len() as u64);
+        assert!(report.rejected_queue_full + report.rejected_timeout > 0);
+        let levels = &report.time_in_level;
+        assert!((levels.low_ms + levels.normal_ms + levels.high_ms - report.duration_ms).abs() < 1e-6);
+        assert!(levels.high_ms > 0.0);
+        // 突发期间查询线程增加，结束后回落
+        assert_eq!(report.decisions.first().map(|d| d.load_level), Some(LoadLevel::Low));
+        assert_eq!(report.decisions.last().map(|d| d.load_level), Some(LoadLevel::Low));
+        assert!(report.decisions.iter().any(|d| d.query_threads == 8));
+    }
+
+    #[test]
+    fn test_compare_queue_limits() {
+        let trace = steady(0, 5, 20, 500.0);
+        let strict = simulate(LoadBalancerConfig { max_queue_length: 0, ..LoadBalancerConfig::default() }, &trace).unwrap();
+        let lenient = simulate(LoadBalancerConfig { max_queue_length: 1000, max_queue_wait_ms: 60_000, ..LoadBalancerConfig::default() }, &trace).unwrap();
+
+        assert!(strict.rejected_queue_full > 0);
+        assert_eq!(strict.max_queue_depth, 0);
+        assert_eq!(lenient.completed, trace.len() as u64);
+        // 不拒绝请求的代价是更高的排队延迟
+        assert!(lenient.latency_ms.p99 > strict.latency_ms.p99);
+    }
+}
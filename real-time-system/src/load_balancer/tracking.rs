@@ -0,0 +1,133 @@
+// 按路由统计进行中的请求
+// 每个请求由 `RequestGuard` 跟踪：创建时计入进行中，处理结束时记录完成或失败；
+// 没有记录结果就被丢弃的守卫（future 因客户端断开被丢弃、处理函数 panic）计为取消，
+// 进行中的计数在任何情况下都会减回去，不会让负载级别一直停留在高负载
+
+use dashmap::DashMap;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// 请求的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Default)]
+struct RouteCounters {
+    in_flight: AtomicUsize,
+    completed: AtomicU64,
+    failed: AtomicU64,
+    cancelled: AtomicU64,
+}
+
+/// 单个路由的请求统计
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RouteStats {
+    pub route: String,
+    pub in_flight: usize,
+    pub completed: u64,
+    pub failed: u64,
+    pub cancelled: u64,
+}
+
+/// 各路由的请求计数
+#[derive(Debug, Default)]
+pub struct RequestTracker {
+    routes: DashMap<String, Arc<RouteCounters>>,
+}
+
+impl RequestTracker {
+    /// 开始跟踪 route 上的一个请求；active 为全局活跃请求计数，与路由计数同步增减
+    pub fn begin(&self, route: &str, active: Arc<AtomicUsize>) -> RequestGuard {
+        let counters = match self.routes.get(route) {
+            Some(counters) => Arc::clone(&counters),
+            None => Arc::clone(&self.routes.entry(route.to_string()).or_default()),
+        };
+        counters.in_flight.fetch_add(1, Ordering::SeqCst);
+        active.fetch_add(1, Ordering::SeqCst);
+        RequestGuard {
+            active,
+            counters,
+            outcome: None,
+        }
+    }
+
+    /// 各路由的统计（按路由名排序）
+    pub fn snapshot(&self) -> Vec<RouteStats> {
+        let mut stats: Vec<_> = self
+            .routes
+            .iter()
+            .map(|entry| RouteStats {
+                route: entry.key().clone(),
+                in_flight: entry.in_flight.load(Ordering::SeqCst),
+                completed: entry.completed.load(Ordering::SeqCst),
+                failed: entry.failed.load(Ordering::SeqCst),
+                cancelled: entry.cancelled.load(Ordering::SeqCst),
+            })
+            .collect();
+        stats.sort_by(|a, b| a.route.cmp(&b.route));
+        stats
+    }
+}
+
+/// 进行中请求的守卫（见 `LoadBalancer::track_request`）
+#[derive(Debug)]
+pub struct RequestGuard {
+    active: Arc<AtomicUsize>,
+    counters: Arc<RouteCounters>,
+    outcome: Option<Outcome>,
+}
+
+impl RequestGuard {
+    /// 记录处理结果并结束跟踪
+    pub fn finish(mut self, outcome: Outcome) {
+        self.outcome = Some(outcome);
+    }
+}
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        let counter = match self.outcome {
+            Some(Outcome::Completed) => &self.counters.completed,
+            Some(Outcome::Failed) => &self.counters.failed,
+            None => &self.counters.cancelled,
+        };
+        counter.fetch_add(1, Ordering::SeqCst);
+        self.counters.in_flight.fetch_sub(1, Ordering::SeqCst);
+        self.active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_guard_outcomes() {
+        let tracker = RequestTracker::default();
+        let active = Arc::new(AtomicUsize::new(0));
+
+        let completed = tracker.begin("GET /a", Arc::clone(&active));
+        let failed = tracker.begin("GET /a", Arc::clone(&active));
+        let cancelled = tracker.begin("GET /b", Arc::clone(&active));
+        assert_eq!(active.load(Ordering::SeqCst), 3);
+        assert_eq!(tracker.snapshot()[0].in_flight, 2);
+
+        completed.finish(Outcome::Completed);
+        failed.finish(Outcome::Failed);
+        // 未记录结果就被丢弃
+        drop(cancelled);
+
+        assert_eq!(active.load(Ordering::SeqCst), 0);
+        assert_eq!(
+            tracker.snapshot(),
+            vec![
+                RouteStats { route: "GET /a".to_string(), in_flight: 0, completed: 1, failed: 1, cancelled: 0 },
+                RouteStats { route: "GET /b".to_string(), in_flight: 0, completed: 0, failed: 0, cancelled: 1 },
+            ]
+        );
+    }
+}
@@ -1,46 +1,203 @@
 mod cache;
+mod compute;
 mod factorization;
+mod jobs;
 mod models;
 mod web;
 mod load_balancer;
+mod precompute;
 
+use actix_web::middleware::from_fn;
 use actix_web::{App, HttpServer};
 use actix_web::web::Data;
-use cache::memory::FactorizationCache;
+use cache::{start_cache_snapshots, AdmissionConfig, CacheConfig, EvictionPolicy, FactorizationCache, Wal, CACHE_FILE};
+use compute::ComputePool;
+use factorization::FactorizerRegistry;
+use jobs::JobManager;
 use std::sync::Arc;
+use web::admin::AdminAuth;
+use web::rate_limit::{RateLimitConfig, RateLimiter};
+use load_balancer::classifier::LevelThresholds;
+use load_balancer::proxy::{BackendPool, ProxyConfig};
 use load_balancer::{LoadBalancer, LoadBalancerConfig};
+use precompute::{PrecomputeConfig, PrecomputeSource, Precomputer};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    // 离线模拟：real-time-system simulate <trace.json> [config.json]
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("simulate") {
+        env_logger::init_from_env(env_logger::Env::new().default_filter_or("warn"));
+        return load_balancer::simulator::run_cli(&args[1..]);
+    }
+    // 缓存文件格式转换：real-time-system convert-cache <input> <output(.bin 为二进制)>
+    if args.first().map(String::as_str) == Some("convert-cache") {
+        return cache::format::run_cli(&args[1..]);
+    }
+
     // 初始化日志
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("debug"));
 
-    // 创建缓存实例
-    let cache = Arc::new(FactorizationCache::new());
+    // 创建缓存实例：最多 10 万条、约 64 MB，优先保留计算耗时长的结果；
+    // 实时计算的结果在第二次被请求时才写入缓存，计算耗时 100 ms 以上的结果直接写入
+    let cache = Arc::new(FactorizationCache::with_config(CacheConfig {
+        max_entries: Some(100_000),
+        max_bytes: Some(64 * 1024 * 1024),
+        policy: EvictionPolicy::CostAware,
+        admission: AdmissionConfig::Frequency { min_requests: 2, sample_size: 100_000, min_cost_ms: Some(100) },
+        ..CacheConfig::default()
+    }));
+
+    // 创建分解算法注册表
+    let registry = Arc::new(FactorizerRegistry::with_defaults());
 
     // 创建负载均衡器
     let load_balancer_config = LoadBalancerConfig {
-        low_load_threshold: 3,
-        high_load_threshold: 15,
+        // 负载级别：进入和退出使用不同阈值，变化后至少保持 9 秒（三个检查周期）
+        high_enter: LevelThresholds { active_requests: 15, p95_latency_ms: 2000.0, cpu_usage: 0.9 },
+        high_exit: LevelThresholds { active_requests: 10, p95_latency_ms: 1000.0, cpu_usage: 0.75 },
+        low_enter: LevelThresholds { active_requests: 3, p95_latency_ms: 100.0, cpu_usage: 0.3 },
+        low_exit: LevelThresholds { active_requests: 6, p95_latency_ms: 250.0, cpu_usage: 0.5 },
+        min_dwell_ms: 9000,
+        latency_window_ms: 30_000,
+        // 预测性扩缩容默认关闭；开启后按负载趋势预测两个检查周期之后的负载，提前分配线程
+        predictive_scaling: false,
+        holt_alpha: 0.5,
+        holt_beta: 0.3,
+        forecast_horizon: 2,
         check_interval_ms: 3000,
         max_compute_threads: 4,
         max_query_threads: 8,
+        max_queue_length: 32,
+        max_queue_wait_ms: 2000,
+        history_capacity: 1200,
+        ewma_alpha: 0.3,
+    };
+    let load_balancer = LoadBalancer::new(load_balancer_config)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+
+    // 创建计算线程池（线程数由负载均衡器决定并随负载动态调整），分解任务不在 actix worker 上执行
+    let compute_pool = Arc::new(ComputePool::new(load_balancer.calculate_compute_threads()));
+    let load_balancer = Arc::new(load_balancer.with_compute_pool(Arc::clone(&compute_pool)));
+
+    // 创建长时间分解任务管理器
+    let job_manager = Arc::new(JobManager::new(
+        Arc::clone(&cache),
+        Arc::clone(&registry),
+        Arc::clone(&compute_pool),
+        Arc::clone(&load_balancer),
+    ));
+
+    // 低负载时用空闲的计算线程预先分解热门查询附近的数
+    let precomputer = Arc::new(Precomputer::new(
+        PrecomputeConfig {
+            enabled: true,
+            source: PrecomputeSource::PopularNeighbours { top: 20, radius: 10 },
+            max_time_ms: 10_000,
+            idle_poll_ms: 5000,
+        },
+        Arc::clone(&cache),
+        Arc::clone(&registry),
+        Arc::clone(&compute_pool),
+        Arc::clone(&load_balancer),
+    ));
+
+    // 按客户端限流：突发 100 个令牌，每秒补充 10 个（约相当于一个计算线程）；
+    // API_KEYS 为逗号分隔的 API 密钥，只有列表中的密钥单独计费，其余请求按对端 IP 计费
+    let api_keys = std::env::var("API_KEYS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(String::from)
+        .collect();
+    // TRUSTED_PROXIES 为逗号分隔的代理前端 IP，只有来自这些地址的请求才按 X-Forwarded-For 识别客户端
+    let trusted_proxies = std::env::var("TRUSTED_PROXIES")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|ip| !ip.is_empty())
+        .map(|ip| ip.parse().map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("{}: {}", ip, e))))
+        .collect::<Result<_, _>>()?;
+    let rate_limiter = Arc::new(RateLimiter::new(RateLimitConfig {
+        capacity: 100.0,
+        refill_per_sec: 10.0,
+        max_tracked_clients: 10_000,
+        api_keys,
+        trusted_proxies,
+    }));
+
+    // 管理端点：设置了 ADMIN_TOKEN 时凭令牌访问，否则只允许本机访问
+    let admin_auth = AdminAuth {
+        token: std::env::var("ADMIN_TOKEN").ok().filter(|token| !token.is_empty()),
     };
-    let load_balancer = Arc::new(LoadBalancer::new(load_balancer_config));
 
     // 从文件加载缓存（如果存在）
-    if let Err(e) = cache.load_from_file("data/cache.json") {
+    // CACHE_FILE 可指定缓存文件，扩展名为 .bin 时以二进制格式写快照（加载时按文件头识别格式）
+    let cache_file = std::env::var("CACHE_FILE").unwrap_or_else(|_| CACHE_FILE.to_string());
+    if let Err(e) = cache.load_from_file(&cache_file) {
         log::warn!("Failed to load cache file: {}, starting with empty cache", e);
     }
 
+    // 重放写前日志（`<缓存文件>.wal`）中快照之后的插入，并立即折叠进新快照
+    let wal_file = Wal::path_for(&cache_file);
+    match Wal::open(&wal_file) {
+        Ok((wal, entries)) => {
+            let replayed = entries.len();
+            cache.replay(entries);
+            cache.attach_wal(wal);
+            if replayed > 0 {
+                log::info!("Replayed {} cache entries from {}", replayed, wal_file.display());
+                if let Err(e) = cache.compact(&cache_file) {
+                    log::warn!("Failed to compact cache WAL: {}", e);
+                }
+            }
+        }
+        Err(e) => log::warn!("Failed to open cache WAL: {}, inserts are only saved by snapshots", e),
+    }
+
+    // 启动缓存定期快照任务
+    tokio::spawn(start_cache_snapshots(Arc::clone(&cache), cache_file.clone()));
+    let shutdown_cache = Arc::clone(&cache);
+
     // 启动负载监控任务
     let lb_clone = Arc::clone(&load_balancer);
     tokio::spawn(async move {
         lb_clone.start_monitoring().await;
     });
 
-    // 启动 HTTP 服务器
-    let bind_address = "127.0.0.1:8080";
+    // 启动空闲时预计算任务
+    tokio::spawn(Arc::clone(&precomputer).run());
+
+    // 代理模式：PROXY_BACKENDS 为逗号分隔的后端地址，分解请求转发给后端，
+    // PROXY_STRATEGY 为 least_active（默认）、consistent_hash 或 round_robin
+    let backends: Vec<String> = std::env::var("PROXY_BACKENDS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|backend| !backend.is_empty())
+        .map(String::from)
+        .collect();
+    let backend_pool = if backends.is_empty() {
+        None
+    } else {
+        let strategy = match std::env::var("PROXY_STRATEGY") {
+            Ok(strategy) => strategy
+                .parse()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?,
+            Err(_) => ProxyConfig::default().strategy,
+        };
+        let pool = Arc::new(BackendPool::new(ProxyConfig {
+            backends,
+            strategy,
+            ..ProxyConfig::default()
+        }));
+        tokio::spawn(Arc::clone(&pool).start_health_checks());
+        Some(pool)
+    };
+
+    // 启动 HTTP 服务器（BIND_ADDRESS 可指定监听地址，便于在本机启动多个实例）
+    let bind_address = std::env::var("BIND_ADDRESS").unwrap_or_else(|_| "127.0.0.1:8080".to_string());
     log::info!("Starting server at http://{}", bind_address);
 
     // 关键：动态计算worker线程数
@@ -48,15 +205,38 @@ async fn main() -> std::io::Result<()> {
     log::info!("Initial worker threads: {}", initial_worker_threads);
 
     HttpServer::new(move || {
-        App::new()
+        let mut app = App::new();
+        if let Some(pool) = &backend_pool {
+            app = app.app_data(Data::new(Arc::clone(pool)));
+        }
+        app
             .app_data(Data::new(Arc::clone(&cache)))
             .app_data(Data::new(Arc::clone(&load_balancer)))
-            .configure(web::routes::configure)
+            .app_data(Data::new(Arc::clone(&registry)))
+            .app_data(Data::new(Arc::clone(&job_manager)))
+            .app_data(Data::new(Arc::clone(&compute_pool)))
+            .app_data(Data::new(Arc::clone(&rate_limiter)))
+            .app_data(Data::new(Arc::clone(&precomputer)))
+            .app_data(Data::new(admin_auth.clone()))
+            // 所有路由的进行中请求计数（守卫在请求结束或被取消时自动减一）
+            .wrap(from_fn(web::in_flight::track_in_flight))
+            .configure(web::configure)
     })
+    // 记录连接句柄，用于在计算期间检测客户端断开
+    .on_connect(web::disconnect::on_connect)
     // 动态设置worker线程数（作业核心要求）
     .workers(initial_worker_threads)
-    .bind(bind_address)?
+    .bind(&bind_address)?
     .run()
-    .await
+    .await?;
 
+    // 收到 SIGTERM/SIGINT 后服务器优雅关闭，退出前把缓存写回磁盘
+    match shutdown_cache.compact(&cache_file) {
+        Ok(info) => log::info!(
+            "Saved {} cache entries ({} bytes) to {} on shutdown in {} ms",
+            info.entries, info.bytes, cache_file, info.duration_ms
+        ),
+        Err(e) => log::error!("Failed to save cache on shutdown: {}", e),
+    }
+    Ok(())
 }
\ No newline at end of file
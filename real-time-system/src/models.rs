@@ -1,22 +1,76 @@
 use serde::{Deserialize, Serialize};
+use crate::factorization::Factorization;
+use json_number::JsonInteger;
 
 // 缓存条目格式（与预处理系统保持一致）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheEntry {
-    pub number: u64,
-    pub factors: Vec<u64>,
+    #[serde(with = "json_number")]
+    pub number: u128,
+    #[serde(serialize_with = "json_number::serialize_vec", deserialize_with = "json_number::deserialize_vec")]
+    pub factors: Vec<u128>,
     pub computation_time_ms: u64,
     pub algorithm: String,
 }
 
-// API 响应格式
+// 分解请求的查询参数
+#[derive(Debug, Deserialize)]
+pub struct FactorizeQuery {
+    /// 指定分解算法（不指定时自动选择）
+    pub algorithm: Option<String>,
+    /// 计算时间上限（毫秒），超时后返回已找到的因子和未分解的余因子
+    pub timeout_ms: Option<u64>,
+}
+
+// 负载历史的查询参数
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    /// 时间窗口，形如 `90`、`30s`、`5m`、`1h`（默认 5 分钟）
+    pub window: Option<String>,
+}
+
+// 提交分解任务的请求体
+#[derive(Debug, Deserialize)]
+pub struct JobRequest {
+    /// 十进制字符串
+    pub number: String,
+    /// 分解算法（默认 siqs）
+    pub algorithm: Option<String>,
+}
+
+// 超过 128 位的缓存条目，数值以十进制字符串保存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BigCacheEntry {
+    pub number: String,
+    pub factors: Vec<String>,
+    pub computation_time_ms: u64,
+    pub algorithm: String,
+}
+
+// API 响应格式（N 为 u128 或 BigUint）
 #[derive(Debug, Serialize)]
-pub struct FactorizationResponse {
-    pub number: u64,
-    pub factors: Vec<u64>,
+#[serde(bound(serialize = "N: JsonInteger"))]
+pub struct FactorizationResponse<N = u128> {
+    #[serde(serialize_with = "json_number::serialize")]
+    pub number: N,
+    /// 已找到的全部因子（与旧版响应兼容；完整分解时即素因子）
+    #[serde(serialize_with = "json_number::serialize_vec")]
+    pub factors: Vec<N>,
+    #[serde(flatten)]
+    pub factorization: Factorization<N>,
     pub is_prime: bool,
     pub cached: bool,
     pub computation_time_ms: Option<u64>,
+    pub algorithm: String,
+}
+
+// 素性检测响应格式
+#[derive(Debug, Serialize)]
+pub struct PrimalityResponse {
+    #[serde(serialize_with = "json_number::serialize")]
+    pub number: num_bigint::BigUint,
+    pub is_prime: bool,
+    pub computation_time_us: u64,
 }
 
 // 错误类型
@@ -25,6 +79,24 @@ pub enum AppError {
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Service overloaded, retry after {retry_after_secs}s")]
+    Overloaded { retry_after_secs: u64 },
+
+    #[error("Rate limit exceeded, retry after {retry_after_secs}s")]
+    RateLimited { retry_after_secs: u64 },
+
+    #[error("Service unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Bad gateway: {0}")]
+    BadGateway(String),
+
     #[error("Internal server error")]
     InternalError,
 }
@@ -35,9 +107,174 @@ impl actix_web::error::ResponseError for AppError {
             AppError::InvalidInput(msg) => actix_web::HttpResponse::BadRequest().json(
                 serde_json::json!({"error": msg})
             ),
+            AppError::NotFound(msg) => actix_web::HttpResponse::NotFound().json(
+                serde_json::json!({"error": msg})
+            ),
+            AppError::Forbidden(msg) => actix_web::HttpResponse::Forbidden().json(
+                serde_json::json!({"error": msg})
+            ),
+            AppError::Overloaded { retry_after_secs } => actix_web::HttpResponse::ServiceUnavailable()
+                .insert_header((actix_web::http::header::RETRY_AFTER, retry_after_secs.to_string()))
+                .json(serde_json::json!({"error": self.to_string()})),
+            AppError::RateLimited { retry_after_secs } => actix_web::HttpResponse::TooManyRequests()
+                .insert_header((actix_web::http::header::RETRY_AFTER, retry_after_secs.to_string()))
+                .json(serde_json::json!({"error": self.to_string()})),
+            AppError::Unavailable(_) => actix_web::HttpResponse::ServiceUnavailable().json(
+                serde_json::json!({"error": self.to_string()})
+            ),
+            AppError::BadGateway(_) => actix_web::HttpResponse::BadGateway().json(
+                serde_json::json!({"error": self.to_string()})
+            ),
             AppError::InternalError => actix_web::HttpResponse::InternalServerError().json(
                 serde_json::json!({"error": "Internal server error"})
             ),
         }
     }
-}
\ No newline at end of file
+}
+
+/// 大整数的 JSON 编码
+///
+/// 不超过 2^53 - 1 的值输出为数字（与已有的 u64 缓存文件保持一致），
+/// 更大的值输出为十进制字符串，避免 JavaScript 等客户端丢失精度。
+/// 反序列化（仅 u128）时数字和字符串两种形式都接受。
+pub mod json_number {
+    use num_bigint::BigUint;
+    use num_traits::ToPrimitive;
+    use serde::de::{self, Deserializer, Visitor};
+    use serde::ser::Serializer;
+    use serde::{Deserialize, Serialize};
+    use std::fmt;
+
+    /// JSON 能精确表示的最大整数（IEEE 754 双精度）
+    const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+    /// 可按上述规则序列化的整数类型
+    pub trait JsonInteger: fmt::Display {
+        /// 值不超过 2^53 - 1 时返回 Some
+        fn as_safe_u64(&self) -> Option<u64>;
+    }
+
+    impl JsonInteger for u128 {
+        fn as_safe_u64(&self) -> Option<u64> {
+            u64::try_from(*self).ok().filter(|&v| v <= MAX_SAFE_INTEGER)
+        }
+    }
+
+    impl JsonInteger for BigUint {
+        fn as_safe_u64(&self) -> Option<u64> {
+            self.to_u64().filter(|&v| v <= MAX_SAFE_INTEGER)
+        }
+    }
+
+    pub fn serialize<T: JsonInteger, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        match value.as_safe_u64() {
+            Some(v) => serializer.serialize_u64(v),
+            None => serializer.collect_str(value),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+        deserializer.deserialize_any(U128Visitor)
+    }
+
+    pub fn serialize_vec<T: JsonInteger, S: Serializer>(values: &[T], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(JsonRef))
+    }
+
+    pub fn deserialize_vec<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u128>, D::Error> {
+        let values = Vec::<JsonU128>::deserialize(deserializer)?;
+        Ok(values.into_iter().map(|v| v.0).collect())
+    }
+
+    /// 单个值的包装，用于序列化数组元素
+    struct JsonRef<'a, T>(&'a T);
+
+    impl<T: JsonInteger> Serialize for JsonRef<'_, T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize(self.0, serializer)
+        }
+    }
+
+    /// 单个 u128 的包装，用于反序列化数组元素
+    struct JsonU128(u128);
+
+    impl<'de> Deserialize<'de> for JsonU128 {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize(deserializer).map(JsonU128)
+        }
+    }
+
+    struct U128Visitor;
+
+    impl Visitor<'_> for U128Visitor {
+        type Value = u128;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a non-negative integer or a decimal string")
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<u128, E> {
+            Ok(v as u128)
+        }
+
+        fn visit_u128<E: de::Error>(self, v: u128) -> Result<u128, E> {
+            Ok(v)
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<u128, E> {
+            u128::try_from(v).map_err(|_| E::invalid_value(de::Unexpected::Signed(v), &self))
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<u128, E> {
+            v.parse().map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_entry_json_number() {
+        // 旧的 u64 缓存文件仍可读取
+        let legacy: CacheEntry = serde_json::from_str(
+            r#"{"number":18446744073709551615,"factors":[3,5,17,257,641,65537,6700417],"computation_time_ms":1,"algorithm":"simple_trial"}"#
+        ).unwrap();
+        assert_eq!(legacy.number, u64::MAX as u128);
+        assert_eq!(legacy.factors.len(), 7);
+
+        // 超过 2^53 的值序列化为字符串，并能原样读回
+        let entry = CacheEntry {
+            number: u128::MAX,
+            factors: vec![3, 67280421310721],
+            computation_time_ms: 0,
+            algorithm: "pollard_rho_brent".to_string(),
+        };
+        let json = serde_json::to_string(&entry).unwrap();
+        assert!(json.contains(r#""number":"340282366920938463463374607431768211455""#));
+        assert!(json.contains(r#""factors":[3,67280421310721]"#));
+        let decoded: CacheEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.number, u128::MAX);
+        assert_eq!(decoded.factors, entry.factors);
+    }
+
+    #[test]
+    fn test_response_keeps_factors_field() {
+        let factorization = Factorization::partial(vec![3], vec![10403]);
+        let response = FactorizationResponse {
+            number: 31_209,
+            factors: factorization.factors(),
+            factorization,
+            is_prime: false,
+            cached: false,
+            computation_time_ms: Some(5),
+            algorithm: "pollard_rho_brent".to_string(),
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["factors"], serde_json::json!([3, 10403]));
+        assert_eq!(json["proven_primes"], serde_json::json!([3]));
+        assert_eq!(json["unfactored_cofactors"], serde_json::json!([10403]));
+        assert_eq!(json["complete"], false);
+    }
+}
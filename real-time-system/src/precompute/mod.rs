@@ -0,0 +1,6 @@
+pub mod source;
+pub mod worker;
+
+// 重新导出
+pub use source::PrecomputeSource;
+pub use worker::{PrecomputeConfig, Precomputer};
@@ -0,0 +1,157 @@
+// 预计算的候选数
+// 按配置的来源依次给出尚未缓存的数：数值区间、最近未命中的数，或热门查询两侧的数。
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+
+use crate::cache::FactorizationCache;
+
+/// 放弃的数的记录上限，超出后清空（之后可能再尝试一次）
+const ABANDONED_CAPACITY: usize = 10_000;
+
+/// 热门查询两侧半径的上限（每次生成的候选数约为 top × 2 × radius 个）
+pub const MAX_NEIGHBOUR_RADIUS: u128 = 1_000;
+
+/// 候选数的来源
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PrecomputeSource {
+    /// 依次分解 [start, end) 中的数
+    Range { start: u128, end: u128 },
+    /// 最近未命中缓存的数（最新的优先）
+    RecentMisses,
+    /// 查询次数最多的 top 个数两侧 radius 以内的数（radius 不超过 `MAX_NEIGHBOUR_RADIUS`）
+    PopularNeighbours { top: usize, radius: u128 },
+}
+
+#[derive(Debug)]
+pub struct Candidates {
+    source: PrecomputeSource,
+    /// 区间来源的下一个数
+    cursor: u128,
+    /// 待尝试的数（被取消后重试的数在最前）
+    queue: VecDeque<u128>,
+    /// 超时放弃的数，不再给出
+    abandoned: HashSet<u128>,
+}
+
+impl Candidates {
+    pub fn new(source: PrecomputeSource) -> Self {
+        let cursor = match source {
+            PrecomputeSource::Range { start, .. } => start,
+            _ => 0,
+        };
+        Self {
+            source,
+            cursor,
+            queue: VecDeque::new(),
+            abandoned: HashSet::new(),
+        }
+    }
+
+    /// 下一个尚未缓存的候选数；暂时没有时返回 None
+    pub fn next(&mut self, cache: &FactorizationCache) -> Option<u128> {
+        loop {
+            let n = match self.queue.pop_front() {
+                Some(n) => n,
+                None => self.draw(cache)?,
+            };
+            if n >= 2 && !cache.contains(n) && !self.abandoned.contains(&n) {
+                return Some(n);
+            }
+        }
+    }
+
+    /// 计算被取消的数，下次最先给出
+    pub fn retry(&mut self, n: u128) {
+        self.queue.push_front(n);
+    }
+
+    /// 放弃在时间限制内无法完成的数
+    pub fn abandon(&mut self, n: u128) {
+        if self.abandoned.len() >= ABANDONED_CAPACITY {
+            self.abandoned.clear();
+        }
+        self.abandoned.insert(n);
+    }
+
+    /// 区间来源剩余的数（不含待重试的数）；其他来源为 None
+    pub fn remaining(&self) -> Option<u128> {
+        match self.source {
+            PrecomputeSource::Range { end, .. } => Some(end.saturating_sub(self.cursor)),
+            _ => None,
+        }
+    }
+
+    /// 区间来源已全部给出
+    pub fn is_exhausted(&self) -> bool {
+        self.queue.is_empty() && self.remaining() == Some(0)
+    }
+
+    /// 从来源取出下一个数（可能已缓存）
+    fn draw(&mut self, cache: &FactorizationCache) -> Option<u128> {
+        match self.source {
+            PrecomputeSource::Range { end, .. } => {
+                let n = self.cursor;
+                (n < end).then(|| {
+                    self.cursor += 1;
+                    n
+                })
+            }
+            PrecomputeSource::RecentMisses => cache.queries().pop_recent_miss(),
+            PrecomputeSource::PopularNeighbours { top, radius } => {
+                // 队列为空时按当前的热门查询重新生成；都已缓存或放弃时 next 返回 None
+                for (n, _) in cache.queries().popular(top) {
+                    for d in 1..=radius {
+                        let neighbours = [n.checked_sub(d), n.checked_add(d)];
+                        self.queue.extend(neighbours.into_iter().flatten().filter(|&m| m >= 2 && !cache.contains(m)));
+                    }
+                }
+                self.queue.retain(|n| !self.abandoned.contains(n));
+                self.queue.pop_front()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_skips_cached() {
+        let cache = FactorizationCache::new();
+        cache.insert_with_factors(3, vec![3], 0, "simple_trial".to_string());
+        let mut candidates = Candidates::new(PrecomputeSource::Range { start: 0, end: 5 });
+
+        assert_eq!(candidates.next(&cache), Some(2));
+        // 被取消的数先重试，已缓存的 3 被跳过
+        candidates.retry(2);
+        assert_eq!(candidates.next(&cache), Some(2));
+        assert_eq!(candidates.next(&cache), Some(4));
+        assert_eq!(candidates.remaining(), Some(0));
+        assert!(candidates.is_exhausted());
+        assert_eq!(candidates.next(&cache), None);
+    }
+
+    #[test]
+    fn test_popular_neighbours() {
+        let cache = FactorizationCache::new();
+        for _ in 0..3 {
+            cache.get(100);
+        }
+        cache.get(50);
+        cache.insert_with_factors(101, vec![101], 0, "simple_trial".to_string());
+        let mut candidates = Candidates::new(PrecomputeSource::PopularNeighbours { top: 1, radius: 2 });
+
+        assert_eq!(candidates.next(&cache), Some(99));
+        cache.insert_with_factors(99, vec![3, 3, 11], 0, "simple_trial".to_string());
+        candidates.abandon(98);
+        // 101 已缓存，98 已放弃
+        assert_eq!(candidates.next(&cache), Some(102));
+        cache.insert_with_factors(102, vec![2, 3, 17], 0, "simple_trial".to_string());
+        // 重新生成后没有新的候选数
+        assert_eq!(candidates.next(&cache), None);
+        assert_eq!(candidates.remaining(), None);
+    }
+}
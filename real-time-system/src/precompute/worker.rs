@@ -0,0 +1,410 @@
+// 空闲时预计算
+// 负载为 Low 时，计算线程池中的计算线程大多空闲。预计算任务每次取一个候选数，
+// 在计算线程中分解并写入缓存，之后的查询直接命中。负载一旦离开 Low，正在进行的分解立即被取消
+// （不完整的结果不会缓存），负载回到 Low 后从被取消的数继续。
+// 每次只占用一个计算线程，避免与随时到来的请求争抢；开始分解前不排队地取得一个准入名额，
+// 名额被用户请求占满时不启动；分解期间占用的名额计入准入控制，请求不会在计算线程池中排在预计算后面。
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+use super::source::{Candidates, PrecomputeSource, MAX_NEIGHBOUR_RADIUS};
+use crate::cache::FactorizationCache;
+use crate::compute::ComputePool;
+use crate::factorization::{Budget, CancellationToken, FactorizerRegistry};
+use crate::load_balancer::{LoadBalancer, LoadLevel};
+
+/// 预计算配置（JSON 中缺少的字段使用默认值）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrecomputeConfig {
+    pub enabled: bool,
+    pub source: PrecomputeSource,
+    /// 单个数的最长计算时间（毫秒），超时的数被放弃
+    pub max_time_ms: u64,
+    /// 暂时没有候选数时的重新检查间隔（毫秒）
+    pub idle_poll_ms: u64,
+}
+
+impl Default for PrecomputeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            source: PrecomputeSource::RecentMisses,
+            max_time_ms: 10_000,
+            idle_poll_ms: 5000,
+        }
+    }
+}
+
+impl PrecomputeConfig {
+    /// 校验配置：热门查询两侧的半径超过上限时按上限计
+    pub fn validated(mut self) -> Self {
+        if let PrecomputeSource::PopularNeighbours { radius, .. } = &mut self.source {
+            if *radius > MAX_NEIGHBOUR_RADIUS {
+                log::warn!("Precompute radius {} exceeds {}, clamping", radius, MAX_NEIGHBOUR_RADIUS);
+                *radius = MAX_NEIGHBOUR_RADIUS;
+            }
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrecomputeState {
+    /// 未启用
+    Disabled,
+    /// 负载不是 Low 或没有空闲的计算名额，等待
+    Paused,
+    /// 正在分解
+    Running,
+    /// 暂时没有候选数
+    Idle,
+    /// 区间中的数已全部处理
+    Exhausted,
+}
+
+/// 预计算进度（`/api/stats`）
+#[derive(Debug, Clone, Serialize)]
+pub struct PrecomputeStats {
+    pub state: PrecomputeState,
+    pub source: PrecomputeSource,
+    /// 分解完成并写入缓存的数
+    pub computed: u64,
+    /// 因负载上升被取消（之后重试）的次数
+    pub cancelled: u64,
+    /// 超时或失败而放弃的数
+    pub abandoned: u64,
+    /// 写入缓存的数的累计计算时间（毫秒）
+    pub compute_time_ms: u64,
+    pub current_number: Option<u128>,
+    pub last_computed: Option<u128>,
+    /// 区间来源剩余的数
+    pub remaining: Option<u128>,
+}
+
+/// 一个数的预计算结果
+enum Outcome {
+    Cached(Duration),
+    Cancelled,
+    Abandoned,
+}
+
+pub struct Precomputer {
+    config: PrecomputeConfig,
+    cache: Arc<FactorizationCache>,
+    registry: Arc<FactorizerRegistry>,
+    pool: Arc<ComputePool>,
+    load_balancer: Arc<LoadBalancer>,
+    candidates: Mutex<Candidates>,
+    stats: Mutex<PrecomputeStats>,
+}
+
+impl Precomputer {
+    pub fn new(
+        config: PrecomputeConfig,
+        cache: Arc<FactorizationCache>,
+        registry: Arc<FactorizerRegistry>,
+        pool: Arc<ComputePool>,
+        load_balancer: Arc<LoadBalancer>,
+    ) -> Self {
+        let config = config.validated();
+        let stats = PrecomputeStats {
+            state: if config.enabled { PrecomputeState::Paused } else { PrecomputeState::Disabled },
+            source: config.source.clone(),
+            computed: 0,
+            cancelled: 0,
+            abandoned: 0,
+            compute_time_ms: 0,
+            current_number: None,
+            last_computed: None,
+            remaining: None,
+        };
+        Self {
+            candidates: Mutex::new(Candidates::new(config.source.clone())),
+            stats: Mutex::new(stats),
+            config,
+            cache,
+            registry,
+            pool,
+            load_balancer,
+        }
+    }
+
+    pub fn stats(&self) -> PrecomputeStats {
+        let mut stats = self.stats.lock().map(|stats| stats.clone()).unwrap_or_else(|e| e.into_inner().clone());
+        stats.remaining = self.candidates.lock().ok().and_then(|candidates| candidates.remaining());
+        stats
+    }
+
+    /// 预计算循环；未启用时立即返回，区间来源处理完后结束
+    pub async fn run(self: Arc<Self>) {
+        if !self.config.enabled {
+            return;
+        }
+        log::info!("Idle precomputation started ({:?})", self.config.source);
+        let mut level = self.load_balancer.subscribe_load_level();
+
+        loop {
+            // 1. 等待低负载
+            let current = *level.borrow_and_update();
+            if current != LoadLevel::Low {
+                self.update(|stats| stats.state = PrecomputeState::Paused);
+                let low = level.wait_for(|level| *level == LoadLevel::Low).await.is_ok();
+                if !low {
+                    return;
+                }
+                continue;
+            }
+
+            // 2. 不排队地申请计算名额，名额都被请求占用时稍后再试
+            let Some(permit) = self.load_balancer.try_admit_compute() else {
+                self.update(|stats| stats.state = PrecomputeState::Paused);
+                tokio::time::sleep(Duration::from_millis(self.config.idle_poll_ms)).await;
+                continue;
+            };
+
+            // 3. 取下一个候选数
+            let next = match self.candidates.lock() {
+                Ok(mut candidates) => candidates.next(&self.cache).ok_or(candidates.is_exhausted()),
+                Err(_) => return,
+            };
+            let number = match next {
+                Ok(number) => number,
+                Err(true) => {
+                    log::info!("Idle precomputation finished its range");
+                    self.update(|stats| stats.state = PrecomputeState::Exhausted);
+                    return;
+                }
+                Err(false) => {
+                    self.update(|stats| stats.state = PrecomputeState::Idle);
+                    tokio::time::sleep(Duration::from_millis(self.config.idle_poll_ms)).await;
+                    continue;
+                }
+            };
+
+            // 4. 分解，负载上升时取消
+            self.update(|stats| {
+                stats.state = PrecomputeState::Running;
+                stats.current_number = Some(number);
+            });
+            let outcome = self.compute(number, &mut level).await;
+            drop(permit);
+            let mut candidates = match self.candidates.lock() {
+                Ok(candidates) => candidates,
+                Err(_) => return,
+            };
+            match outcome {
+                Outcome::Cached(elapsed) => self.update(|stats| {
+                    stats.computed += 1;
+                    stats.compute_time_ms += elapsed.as_millis() as u64;
+                    stats.last_computed = Some(number);
+                }),
+                Outcome::Cancelled => {
+                    log::debug!("Precomputation of {} cancelled, load is rising", number);
+                    candidates.retry(number);
+                    self.update(|stats| stats.cancelled += 1);
+                }
+                Outcome::Abandoned => {
+                    candidates.abandon(number);
+                    self.update(|stats| stats.abandoned += 1);
+                }
+            }
+            drop(candidates);
+            self.update(|stats| stats.current_number = None);
+        }
+    }
+
+    /// 在计算线程中分解 number；负载离开 Low 时取消
+    async fn compute(&self, number: u128, level: &mut watch::Receiver<LoadLevel>) -> Outcome {
+        let Some(factorizer) = self.registry.select_best(number) else {
+            return Outcome::Abandoned;
+        };
+        let token = CancellationToken::new();
+        let deadline = Instant::now() + Duration::from_millis(self.config.max_time_ms);
+        let budget = Budget::new(token.clone(), Some(deadline));
+
+        let worker = Arc::clone(&factorizer);
+        let task = self.pool.run(move || {
+            let start = Instant::now();
+            let factorization = worker.factorize(number, &budget);
+            (factorization, start.elapsed())
+        });
+        tokio::pin!(task);
+        let load_rising = async {
+            let _ = level.wait_for(|level| *level != LoadLevel::Low).await;
+        };
+
+        let result = tokio::select! {
+            result = &mut task => result,
+            _ = load_rising => {
+                token.cancel();
+                task.await
+            }
+        };
+
+        match result {
+            Ok((factorization, elapsed)) => {
+                let inserted = self.cache.insert_factorization(
+                    number,
+                    &factorization,
+                    elapsed.as_millis() as u64,
+                    factorizer.name().to_string(),
+                );
+                if inserted {
+                    Outcome::Cached(elapsed)
+                } else if token.is_cancelled() {
+                    Outcome::Cancelled
+                } else {
+                    Outcome::Abandoned
+                }
+            }
+            Err(e) => {
+                log::error!("Precomputation of {} failed: {}", number, e);
+                Outcome::Abandoned
+            }
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut PrecomputeStats)) {
+        if let Ok(mut stats) = self.stats.lock() {
+            f(&mut stats);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::factorization::registry::Factorizer;
+    use crate::factorization::Factorization;
+    use crate::load_balancer::classifier::LevelThresholds;
+    use crate::load_balancer::LoadBalancerConfig;
+
+    /// 一直计算到预算耗尽的算法
+    struct Endless;
+
+    impl Factorizer for Endless {
+        fn name(&self) -> &'static str {
+            "endless"
+        }
+
+        fn estimate_cost(&self, _n: u128) -> f64 {
+            0.0
+        }
+
+        fn factorize(&self, n: u128, budget: &Budget) -> Factorization {
+            while !budget.is_exhausted() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            Factorization::partial(Vec::new(), vec![n])
+        }
+    }
+
+    fn precomputer(source: PrecomputeSource, registry: FactorizerRegistry) -> (Arc<Precomputer>, Arc<LoadBalancer>) {
+        // 只按请求数分类：有 2 个进行中的请求即为高负载，没有请求时为低负载
+        let active_only = |active_requests, other| LevelThresholds {
+            active_requests,
+            p95_latency_ms: other,
+            cpu_usage: other,
+        };
+        let load_balancer = Arc::new(
+            LoadBalancer::new(LoadBalancerConfig {
+                high_enter: active_only(2, f64::INFINITY),
+                high_exit: active_only(1, f64::INFINITY),
+                low_enter: active_only(1, f64::MAX),
+                low_exit: active_only(1, f64::MAX),
+                min_dwell_ms: 0,
+                ..LoadBalancerConfig::default()
+            })
+            .unwrap(),
+        );
+        let config = PrecomputeConfig {
+            enabled: true,
+            source,
+            max_time_ms: 60_000,
+            idle_poll_ms: 10,
+        };
+        let precomputer = Precomputer::new(
+            config,
+            Arc::new(FactorizationCache::new()),
+            Arc::new(registry),
+            Arc::new(ComputePool::new(1)),
+            Arc::clone(&load_balancer),
+        );
+        (Arc::new(precomputer), load_balancer)
+    }
+
+    async fn wait_until(precomputer: &Precomputer, condition: impl Fn(&PrecomputeStats) -> bool) -> PrecomputeStats {
+        for _ in 0..500 {
+            let stats = precomputer.stats();
+            if condition(&stats) {
+                return stats;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("condition not reached: {:?}", precomputer.stats());
+    }
+
+    #[test]
+    fn test_radius_is_clamped() {
+        let config = PrecomputeConfig {
+            source: PrecomputeSource::PopularNeighbours { top: 20, radius: u128::MAX },
+            ..PrecomputeConfig::default()
+        };
+        assert_eq!(
+            config.validated().source,
+            PrecomputeSource::PopularNeighbours { top: 20, radius: MAX_NEIGHBOUR_RADIUS }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fills_range_when_idle() {
+        let (precomputer, _) = precomputer(PrecomputeSource::Range { start: 90, end: 100 }, FactorizerRegistry::with_defaults());
+        tokio::spawn(Arc::clone(&precomputer).run());
+
+        let stats = wait_until(&precomputer, |stats| stats.state == PrecomputeState::Exhausted).await;
+        assert_eq!(stats.computed, 10);
+        assert_eq!(stats.remaining, Some(0));
+        assert_eq!(precomputer.cache.get(91).unwrap().factors, vec![7, 13]);
+    }
+
+    #[tokio::test]
+    async fn test_pauses_when_load_rises() {
+        let mut registry = FactorizerRegistry::new();
+        registry.register(Arc::new(Endless));
+        let (precomputer, load_balancer) = precomputer(PrecomputeSource::Range { start: 1000, end: 1001 }, registry);
+        tokio::spawn(Arc::clone(&precomputer).run());
+        wait_until(&precomputer, |stats| stats.current_number == Some(1000)).await;
+
+        // 负载上升：正在进行的分解被取消，等负载回落后重试
+        let guard = (load_balancer.track_request("GET /"), load_balancer.track_request("GET /"));
+        assert_eq!(load_balancer.update_load_level(None), LoadLevel::High);
+        let stats = wait_until(&precomputer, |stats| stats.state == PrecomputeState::Paused && stats.cancelled == 1).await;
+        assert_eq!(stats.current_number, None);
+        assert!(!precomputer.cache.contains(1000));
+
+        drop(guard);
+        assert_eq!(load_balancer.update_load_level(None), LoadLevel::Low);
+        wait_until(&precomputer, |stats| stats.current_number == Some(1000)).await;
+    }
+
+    #[tokio::test]
+    async fn test_waits_for_free_permit() {
+        let (precomputer, load_balancer) = precomputer(PrecomputeSource::Range { start: 90, end: 92 }, FactorizerRegistry::with_defaults());
+        // 计算名额都被请求占用时不启动预计算
+        let permits: Vec<_> = std::iter::from_fn(|| load_balancer.try_admit_compute()).collect();
+        assert!(!permits.is_empty());
+        tokio::spawn(Arc::clone(&precomputer).run());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let stats = precomputer.stats();
+        assert_eq!((stats.state, stats.computed, stats.current_number), (PrecomputeState::Paused, 0, None));
+
+        drop(permits);
+        let stats = wait_until(&precomputer, |stats| stats.state == PrecomputeState::Exhausted).await;
+        assert_eq!(stats.computed, 2);
+    }
+}
@@ -0,0 +1,90 @@
+// 管理端点的访问控制
+// 配置了管理令牌（`AdminAuth::token`）时，请求须带 `Authorization: Bearer <令牌>`；
+// 未配置时只接受来自本机回环地址的请求。
+
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, ResponseError};
+
+use crate::models::AppError;
+
+#[derive(Debug, Clone, Default)]
+pub struct AdminAuth {
+    /// 管理令牌；为 None 时只允许本机访问
+    pub token: Option<String>,
+}
+
+impl AdminAuth {
+    /// 请求是否有权访问管理端点
+    fn allows(&self, req: &ServiceRequest) -> bool {
+        match &self.token {
+            Some(token) => req
+                .headers()
+                .get(AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .is_some_and(|given| constant_time_eq(given.trim().as_bytes(), token.as_bytes())),
+            None => req.peer_addr().is_some_and(|addr| addr.ip().is_loopback()),
+        }
+    }
+}
+
+/// 比较令牌时不因第一个不同的字节提前返回
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// 访问控制中间件（`middleware::from_fn`）；应用中没有注册 `AdminAuth` 时按未配置令牌处理
+pub async fn require_admin(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let allowed = match req.app_data::<web::Data<AdminAuth>>() {
+        Some(auth) => auth.allows(&req),
+        None => AdminAuth::default().allows(&req),
+    };
+    if !allowed {
+        log::warn!("Rejected admin request to {} from {:?}", req.path(), req.peer_addr());
+        let response = AppError::Forbidden("Admin token required".to_string()).error_response();
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::middleware::from_fn;
+    use actix_web::{test as actix_test, App, HttpResponse};
+
+    #[actix_web::test]
+    async fn test_require_admin() {
+        let service = |auth: Option<AdminAuth>| {
+            let mut app = App::new();
+            if let Some(auth) = auth {
+                app = app.app_data(web::Data::new(auth));
+            }
+            actix_test::init_service(app.service(
+                web::scope("/admin")
+                    .wrap(from_fn(require_admin))
+                    .route("/stats", web::get().to(HttpResponse::Ok)),
+            ))
+        };
+        let request = |peer: &str| actix_test::TestRequest::get().uri("/admin/stats").peer_addr(peer.parse().unwrap());
+
+        // 未配置令牌：只允许本机
+        let app = service(None).await;
+        assert_eq!(actix_test::call_service(&app, request("127.0.0.1:5000").to_request()).await.status(), 200);
+        assert_eq!(actix_test::call_service(&app, request("10.0.0.7:5000").to_request()).await.status(), 403);
+
+        // 配置了令牌：本机也须带令牌
+        let app = service(Some(AdminAuth { token: Some("secret".to_string()) })).await;
+        assert_eq!(actix_test::call_service(&app, request("127.0.0.1:5000").to_request()).await.status(), 403);
+        let wrong = request("10.0.0.7:5000").insert_header((AUTHORIZATION, "Bearer other")).to_request();
+        assert_eq!(actix_test::call_service(&app, wrong).await.status(), 403);
+        let right = request("10.0.0.7:5000").insert_header((AUTHORIZATION, "Bearer secret")).to_request();
+        assert_eq!(actix_test::call_service(&app, right).await.status(), 200);
+    }
+}
@@ -0,0 +1,103 @@
+// 客户端断开检测
+// actix 在处理函数等待期间不会读取连接，客户端断开后处理函数的 future 也不会被丢弃，
+// 因此建立连接时复制一份套接字句柄，计算期间定期 peek 检查连接是否已被重置。
+// 读到 EOF 只说明客户端关闭了写方向（可能只是半关闭，仍在等待响应），不视为断开；
+// 只关闭写方向和完全关闭在服务端无法区分，完全关闭的客户端要到写响应时才会被发现
+
+use actix_web::dev::Extensions;
+use actix_web::HttpRequest;
+use std::any::Any;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::factorization::Budget;
+
+/// 检查客户端是否断开的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 连接级数据：与 actix 共享同一个套接字的句柄
+pub struct ClientSocket(std::net::TcpStream);
+
+impl ClientSocket {
+    /// 连接是否已断开（被对端重置等错误）；半关闭的连接读到 EOF，不算断开
+    fn is_closed(&self) -> bool {
+        // 套接字为非阻塞模式，没有数据时返回 WouldBlock
+        let mut buf = [0u8; 1];
+        match self.0.peek(&mut buf) {
+            Ok(_) => false,
+            Err(e) => !matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::Interrupted),
+        }
+    }
+}
+
+/// `HttpServer::on_connect` 回调：为每个 TCP 连接保存 `ClientSocket`
+pub fn on_connect(conn: &dyn Any, ext: &mut Extensions) {
+    #[cfg(unix)]
+    if let Some(stream) = conn.downcast_ref::<actix_web::rt::net::TcpStream>() {
+        use std::os::fd::AsFd;
+        match stream.as_fd().try_clone_to_owned() {
+            Ok(fd) => {
+                ext.insert(ClientSocket(std::net::TcpStream::from(fd)));
+            }
+            Err(e) => log::warn!("Failed to duplicate client socket: {}", e),
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (conn, ext);
+}
+
+/// 等待 future 完成；期间客户端断开则取消 budget，让计算尽快返回部分结果
+pub async fn cancel_on_disconnect<F: Future>(req: &HttpRequest, budget: &Budget, future: F) -> F::Output {
+    let socket = req.conn_data::<ClientSocket>();
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    tokio::pin!(future);
+
+    loop {
+        tokio::select! {
+            output = &mut future => return output,
+            _ = interval.tick() => {
+                if socket.is_some_and(ClientSocket::is_closed) && !budget.is_exhausted() {
+                    log::info!("Client disconnected from {}, cancelling computation", req.path());
+                    budget.cancel();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::{Shutdown, TcpListener, TcpStream};
+
+    fn connect() -> (TcpStream, ClientSocket) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        server.set_nonblocking(true).unwrap();
+        (client, ClientSocket(server))
+    }
+
+    #[test]
+    fn test_half_close_is_not_disconnect() {
+        let (client, socket) = connect();
+        assert!(!socket.is_closed());
+
+        // 客户端只关闭写方向，仍可以接收响应
+        client.shutdown(Shutdown::Write).unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!socket.is_closed());
+    }
+
+    #[test]
+    fn test_reset_is_disconnect() {
+        let (client, mut socket) = connect();
+        // 客户端还有未读数据时关闭连接，内核发送 RST
+        socket.0.write_all(b"pending").unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        drop(client);
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(socket.is_closed());
+    }
+}
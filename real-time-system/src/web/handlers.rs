@@ -1,86 +1,351 @@
-use actix_web::{web, HttpResponse};
-use crate::{cache::memory::FactorizationCache, models::{FactorizationResponse, CacheEntry}};
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use crate::{cache::FactorizationCache, compute::{ComputeError, ComputePool}, factorization::{self, Budget, CancellationToken, Factorization, FactorizerRegistry}, jobs::{JobError, JobManager}, models::{AppError, FactorizeQuery, FactorizationResponse, HistoryQuery, JobRequest, PrimalityResponse}};
+use num_bigint::BigUint;
 use std::sync::Arc;
-use crate::load_balancer::LoadBalancer;
+use std::time::{Duration, Instant};
+use crate::load_balancer::{admission::AdmissionPermit, LoadBalancer, LoadBalancerConfig};
+use crate::precompute::Precomputer;
+use crate::web::disconnect;
+use crate::web::rate_limit::RateLimiter;
+
+/// 接受的最大十进制位数（更大的数 ECM 基本无法在合理时间内完成）
+pub(crate) const MAX_DIGITS: usize = 100;
+
+/// 健康检查的路径（不限流、不计入延迟）
+pub(crate) const HEALTH_PATH: &str = "/api/health";
+
+/// 超过 128 位的数默认使用的分解流程名称
+pub(crate) const BIG_ALGORITHM: &str = "ecm";
+
+/// 分解任务默认使用的算法
+pub(crate) const JOB_ALGORITHM: &str = "siqs";
+
+/// 负载历史默认的时间窗口
+const DEFAULT_HISTORY_WINDOW: Duration = Duration::from_secs(300);
+
+/// 路径中解析出的数：u128 范围内走快速路径，更大的数走任意精度路径
+enum ParsedNumber {
+    Small(u128),
+    Big(BigUint),
+}
+
+/// 解析路径中的十进制数
+fn parse_number(raw: &str) -> Result<ParsedNumber, AppError> {
+    if raw.is_empty() || !raw.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(AppError::InvalidInput(format!("'{}' is not a valid unsigned decimal integer", raw)));
+    }
+    if raw.len() > MAX_DIGITS {
+        return Err(AppError::InvalidInput(format!("Number must have at most {} digits", MAX_DIGITS)));
+    }
+    match raw.parse::<u128>() {
+        Ok(number) => Ok(ParsedNumber::Small(number)),
+        Err(_) => raw
+            .parse::<BigUint>()
+            .map(ParsedNumber::Big)
+            .map_err(|_| AppError::InvalidInput(format!("'{}' is not a valid unsigned decimal integer", raw))),
+    }
+}
 
 pub async fn factorize_handler(
-    n: web::Path<u64>,
+    req: HttpRequest,
+    n: web::Path<String>,
+    query: web::Query<FactorizeQuery>,
     cache: web::Data<Arc<FactorizationCache>>,
+    registry: web::Data<Arc<FactorizerRegistry>>,
     load_balancer: web::Data<Arc<LoadBalancer>>,  // 新增参数
+    pool: web::Data<Arc<ComputePool>>,
 ) -> HttpResponse {
-    // 记录请求开始
-    load_balancer.increment_request();
+    // 本 future 被提前丢弃时（如服务器关闭）守卫取消仍在进行的计算，客户端断开另见 `disconnect`
+    let token = CancellationToken::new();
+    let _cancel_on_drop = token.drop_guard();
+    let deadline = query.timeout_ms.map(|ms| Instant::now() + Duration::from_millis(ms));
+    let budget = Budget::new(token, deadline);
+
+    match parse_number(&n) {
+        Ok(ParsedNumber::Small(number)) => factorize_small(&req, &pool, number, &query, &cache, &registry, &load_balancer, &budget).await,
+        Ok(ParsedNumber::Big(number)) => factorize_big_number(&req, &pool, number, &query, &cache, &load_balancer, &budget).await,
+        Err(e) => e.error_response(),
+    }
+}
 
-    let number = n.into_inner();
+/// 申请计算名额；排队已满或等待超时时返回 `AppError::Overloaded`
+async fn admit(load_balancer: &LoadBalancer) -> Result<AdmissionPermit, AppError> {
+    load_balancer.admit_compute().await.map_err(|_| AppError::Overloaded {
+        retry_after_secs: load_balancer.retry_after_secs(),
+    })
+}
+
+/// 在计算线程池中执行计算，不占用 actix worker；等待期间客户端断开则取消 budget
+/// 返回结果及计算耗时（不含排队时间）
+async fn run_blocking<T: Send + 'static>(
+    req: &HttpRequest,
+    pool: &ComputePool,
+    budget: &Budget,
+    f: impl FnOnce(&Budget) -> T + Send + 'static,
+) -> Result<(T, Duration), AppError> {
+    let worker = budget.clone();
+    let task = move || {
+        let start = Instant::now();
+        let output = f(&worker);
+        (output, start.elapsed())
+    };
+    disconnect::cancel_on_disconnect(req, budget, pool.run(task))
+        .await
+        .map_err(|e| match e {
+            ComputeError::QueueFull(_) => AppError::Unavailable(e.to_string()),
+            _ => {
+                log::error!("Computation failed: {}", e);
+                AppError::InternalError
+            }
+        })
+}
 
+/// u128 范围内的分解：按注册表选择算法
+#[allow(clippy::too_many_arguments)]
+async fn factorize_small(
+    req: &HttpRequest,
+    pool: &ComputePool,
+    number: u128,
+    query: &FactorizeQuery,
+    cache: &FactorizationCache,
+    registry: &FactorizerRegistry,
+    load_balancer: &LoadBalancer,
+    budget: &Budget,
+) -> HttpResponse {
     // 检查输入有效性
     if number < 2 {
-        load_balancer.decrement_request();  // 记得减少计数
-        return HttpResponse::BadRequest().json(serde_json::json!({
-            "error": "Number must be greater than 1"
-        }));
+        return AppError::InvalidInput("Number must be greater than 1".to_string()).error_response();
     }
 
+    // 客户端指定的算法必须已注册，且可以在请求内同步调用
+    let requested = match query.algorithm.as_deref() {
+        Some(name) => match registry.get(name) {
+            Some(factorizer) if factorizer.is_job_only() => {
+                return AppError::InvalidInput(format!(
+                    "Algorithm '{}' is only available through POST /api/jobs",
+                    name
+                )).error_response();
+            }
+            Some(factorizer) => Some(factorizer),
+            None => {
+                return AppError::InvalidInput(format!(
+                    "Unknown algorithm '{}', available: {}",
+                    name,
+                    registry.names().join(", ")
+                )).error_response();
+            }
+        },
+        None => None,
+    };
+
     // 1. 尝试从缓存获取
     if let Some(entry) = cache.get(number) {
-        let is_prime = entry.factors.len() == 1 && entry.factors[0] == number;
-
-        load_balancer.decrement_request();  // 请求完成
+        let is_prime = factorization::is_prime(number);
 
+        let factorization = Factorization::complete(entry.factors);
         return HttpResponse::Ok().json(FactorizationResponse {
             number,
-            factors: entry.factors,
+            factors: factorization.factors(),
+            factorization,
             is_prime,
             cached: true,
             computation_time_ms: Some(entry.computation_time_ms),
+            algorithm: entry.algorithm,
         });
     }
 
-    // 2. 根据当前负载决定计算策略
-    let load_level = load_balancer.get_load_level();
+    // 2. 申请计算名额（过载时返回 503，而不是降级为不完整的算法）
+    let _permit = match admit(load_balancer).await {
+        Ok(permit) => permit,
+        Err(e) => return e.error_response(),
+    };
+
+    // 3. 使用指定的算法或估算代价最低的完整分解算法实时计算
+    let factorizer = requested
+        .or_else(|| registry.select_best(number))
+        .expect("registry has no factorizers");
 
-    // 如果是高负载，可以使用更快的算法（牺牲准确性）
-    let factors = if matches!(load_level, crate::load_balancer::LoadLevel::High) {
-        // 高负载时使用快速但可能不完整的方法
-        log::warn!("High load detected, using fast factorization for number {}", number);
-        crate::factorization::simple::factorize_fast(number)
-    } else {
-        // 正常负载使用标准方法
-        crate::factorization::simple::factorize(number)
+    let worker = Arc::clone(&factorizer);
+    let (factorization, duration) = match run_blocking(req, pool, budget, move |budget| worker.factorize(number, budget)).await {
+        Ok(computed) => computed,
+        Err(e) => return e.error_response(),
     };
 
-    // 2. 实时计算
-    let start = std::time::Instant::now();
-//     let factors = crate::factorization::simple::factorize(number);
-    let duration = start.elapsed();
+    // 4. 判断是否为质数
+    let is_prime = factorization::is_prime(number);
 
-    // 3. 判断是否为质数
-    let is_prime = factors.len() == 1 && factors[0] == number;
+    // 按准入策略缓存结果（不完整的结果不会被缓存）
+    cache.offer_factorization(
+        number,
+        &factorization,
+        duration.as_millis() as u64,
+        factorizer.name().to_string()
+    );
 
-    // 如果计算耗时较长，则缓存结果
-    if duration.as_millis() > 100 {
-        cache.insert_with_factors(
+    HttpResponse::Ok().json(FactorizationResponse {
+        number,
+        factors: factorization.factors(),
+        factorization,
+        is_prime,
+        cached: false,
+        computation_time_ms: Some(duration.as_millis() as u64),
+        algorithm: factorizer.name().to_string(),
+    })
+}
+
+/// 超过 128 位的分解：试除 → rho → ECM（SIQS 只能通过任务接口使用）
+async fn factorize_big_number(
+    req: &HttpRequest,
+    pool: &ComputePool,
+    number: BigUint,
+    query: &FactorizeQuery,
+    cache: &FactorizationCache,
+    load_balancer: &LoadBalancer,
+    budget: &Budget,
+) -> HttpResponse {
+    let algorithm = query.algorithm.clone().unwrap_or_else(|| BIG_ALGORITHM.to_string());
+    let algorithm = algorithm.as_str();
+    if !factorization::INTERACTIVE_BIG_ALGORITHMS.contains(&algorithm) {
+        if factorization::BIG_ALGORITHMS.contains(&algorithm) {
+            return AppError::InvalidInput(format!(
+                "Algorithm '{}' is only available through POST /api/jobs",
+                algorithm
+            )).error_response();
+        }
+        return AppError::InvalidInput(format!(
+            "Unknown algorithm '{}' for numbers of 2^128 and above, available: {}",
+            algorithm,
+            factorization::INTERACTIVE_BIG_ALGORITHMS.join(", ")
+        )).error_response();
+    }
+
+    // 1. 尝试从缓存获取（缓存中的值均由本服务写入，解析不会失败）
+    if let Some(entry) = cache.get_big(&number) {
+        let factors = entry.factors.iter().filter_map(|f| f.parse().ok()).collect();
+        let is_prime = factorization::is_prime_big(&number);
+
+        let factorization = Factorization::complete(factors);
+        return HttpResponse::Ok().json(FactorizationResponse {
             number,
-            factors.clone(),
-            duration.as_millis() as u64,
-            "simple_trial".to_string()
-        );
+            factors: factorization.factors(),
+            factorization,
+            is_prime,
+            cached: true,
+            computation_time_ms: Some(entry.computation_time_ms),
+            algorithm: entry.algorithm,
+        });
     }
 
-    load_balancer.decrement_request();  // 请求完成
+    // 2. 申请计算名额后实时计算
+    let _permit = match admit(load_balancer).await {
+        Ok(permit) => permit,
+        Err(e) => return e.error_response(),
+    };
+    let (name, target) = (algorithm.to_string(), number.clone());
+    let computed = run_blocking(req, pool, budget, move |budget| factorization::factorize_big_by(&name, &target, budget)).await;
+    let (factorization, duration) = match computed {
+        Ok((Some(factorization), duration)) => (factorization, duration),
+        // 算法名称已在上面检查过
+        Ok((None, _)) => return AppError::InternalError.error_response(),
+        Err(e) => return e.error_response(),
+    };
+
+    let is_prime = factorization::is_prime_big(&number);
+
+    cache.offer_big_factorization(
+        &number,
+        &factorization,
+        duration.as_millis() as u64,
+        algorithm.to_string()
+    );
 
     HttpResponse::Ok().json(FactorizationResponse {
         number,
-        factors,
+        factors: factorization.factors(),
+        factorization,
         is_prime,
         cached: false,
         computation_time_ms: Some(duration.as_millis() as u64),
+        algorithm: algorithm.to_string(),
+    })
+}
+
+// 提交长时间运行的分解任务，立即返回任务编号（202）
+pub async fn submit_job_handler(
+    request: web::Json<JobRequest>,
+    jobs: web::Data<Arc<JobManager>>,
+) -> HttpResponse {
+    let number = match parse_number(&request.number) {
+        Ok(ParsedNumber::Small(number)) if number < 2 => {
+            return AppError::InvalidInput("Number must be greater than 1".to_string()).error_response();
+        }
+        Ok(ParsedNumber::Small(number)) => BigUint::from(number),
+        Ok(ParsedNumber::Big(number)) => number,
+        Err(e) => return e.error_response(),
+    };
+
+    let algorithm = request.algorithm.clone().unwrap_or_else(|| JOB_ALGORITHM.to_string());
+    let job = match jobs.submit(number, algorithm) {
+        Ok(job) => job,
+        Err(e @ JobError::UnsupportedAlgorithm { .. }) => return AppError::InvalidInput(e.to_string()).error_response(),
+        Err(e @ JobError::TooManyJobs(_)) => return AppError::Unavailable(e.to_string()).error_response(),
+    };
+    HttpResponse::Accepted()
+        .insert_header(("Location", format!("/api/jobs/{}", job.id)))
+        .json(job)
+}
+
+// 查询分解任务状态
+pub async fn job_status_handler(
+    id: web::Path<u64>,
+    jobs: web::Data<Arc<JobManager>>,
+) -> HttpResponse {
+    match jobs.get(*id) {
+        Some(job) => HttpResponse::Ok().json(job),
+        None => AppError::NotFound(format!("Job {} does not exist", id)).error_response(),
+    }
+}
+
+// 取消分解任务；已找到的因子作为部分结果保留
+pub async fn cancel_job_handler(
+    id: web::Path<u64>,
+    jobs: web::Data<Arc<JobManager>>,
+) -> HttpResponse {
+    if !jobs.cancel(*id) {
+        return AppError::NotFound(format!("Job {} does not exist", id)).error_response();
+    }
+    match jobs.get(*id) {
+        Some(job) => HttpResponse::Accepted().json(job),
+        None => AppError::NotFound(format!("Job {} does not exist", id)).error_response(),
+    }
+}
+
+// 素性检测端点（确定性 Miller–Rabin，无需分解）
+pub async fn is_prime_handler(
+    n: web::Path<String>,
+) -> HttpResponse {
+    let start = std::time::Instant::now();
+    let (number, is_prime) = match parse_number(&n) {
+        Ok(ParsedNumber::Small(number)) => (BigUint::from(number), factorization::is_prime(number)),
+        Ok(ParsedNumber::Big(number)) => {
+            let is_prime = factorization::is_prime_big(&number);
+            (number, is_prime)
+        }
+        Err(e) => return e.error_response(),
+    };
+    let duration = start.elapsed();
+
+    HttpResponse::Ok().json(PrimalityResponse {
+        number,
+        is_prime,
+        computation_time_us: duration.as_micros() as u64,
     })
 }
 
 // 新增：负载统计端点
 pub async fn load_stats_handler(
     load_balancer: web::Data<Arc<LoadBalancer>>,
+    pool: web::Data<Arc<ComputePool>>,
 ) -> HttpResponse {
     let stats = load_balancer.get_stats();
 
@@ -90,12 +355,54 @@ pub async fn load_stats_handler(
         "current_worker_threads": load_balancer.get_current_worker_threads(), // 新增
         "recommended_compute_threads": stats.recommended_compute_threads,
         "recommended_query_threads": stats.recommended_query_threads,
+        "compute_threads_target": pool.target_threads(),
+        "compute_threads_live": pool.live_threads(),
+        "compute_pool_busy": pool.busy(),
+        "compute_pool_queued": pool.queued(),
         "average_load": stats.average_load,
         "history_size": stats.history_size,
+        "in_flight_compute": stats.in_flight_compute,
+        "max_in_flight_compute": stats.max_in_flight_compute,
+        "queued_compute": stats.queued_compute,
+        "rejected_requests": stats.rejected_requests,
+        "routes": stats.routes,
+        "signals": stats.signals,
+        "prediction": stats.prediction,
         "timestamp": chrono::Utc::now().to_rfc3339(),
     }))
 }
 
+/// 解析时间窗口：纯数字为秒，也可带 s / m / h 后缀
+fn parse_window(raw: &str) -> Result<Duration, AppError> {
+    let raw = raw.trim();
+    let (value, unit_secs) = match raw.char_indices().last() {
+        Some((i, 's')) => (&raw[..i], 1),
+        Some((i, 'm')) => (&raw[..i], 60),
+        Some((i, 'h')) => (&raw[..i], 3600),
+        _ => (raw, 1),
+    };
+    value
+        .parse::<u64>()
+        .ok()
+        .filter(|&value| value > 0)
+        .and_then(|value| value.checked_mul(unit_secs))
+        .map(Duration::from_secs)
+        .ok_or_else(|| AppError::InvalidInput(format!("Invalid window '{}', expected e.g. 90, 30s, 5m or 1h", raw)))
+}
+
+// 负载历史：窗口内的样本序列、EWMA 与百分位数（用于绘图）
+pub async fn load_history_handler(
+    query: web::Query<HistoryQuery>,
+    load_balancer: web::Data<Arc<LoadBalancer>>,
+) -> HttpResponse {
+    let window = match query.window.as_deref().map(parse_window).transpose() {
+        Ok(window) => window.unwrap_or(DEFAULT_HISTORY_WINDOW),
+        Err(e) => return e.error_response(),
+    };
+
+    HttpResponse::Ok().json(load_balancer.history_summary(window))
+}
+
 // 新增：系统健康端点（包含负载信息）
 pub async fn system_health_handler(
     load_balancer: web::Data<Arc<LoadBalancer>>,
@@ -117,9 +424,62 @@ pub async fn system_health_handler(
     }))
 }
 
+// 管理端点：各客户端的限流配置与当前消耗
+pub async fn rate_limits_handler(
+    limiter: web::Data<Arc<RateLimiter>>,
+) -> HttpResponse {
+    let config = limiter.config();
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "capacity": config.capacity,
+        "refill_per_sec": config.refill_per_sec,
+        "clients": limiter.usage(),
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+// 管理端点：当前的负载均衡器配置
+pub async fn load_balancer_config_handler(
+    load_balancer: web::Data<Arc<LoadBalancer>>,
+) -> HttpResponse {
+    HttpResponse::Ok().json(load_balancer.config())
+}
+
+/// 把 patch 中的字段覆盖到 current 上（未给出的字段保持当前值）
+fn merge_config(current: LoadBalancerConfig, patch: serde_json::Value) -> Result<LoadBalancerConfig, AppError> {
+    let serde_json::Value::Object(patch) = patch else {
+        return Err(AppError::InvalidInput("Config must be a JSON object".to_string()));
+    };
+    let mut merged = serde_json::to_value(current).map_err(|_| AppError::InternalError)?;
+    if let Some(fields) = merged.as_object_mut() {
+        fields.extend(patch);
+    }
+    serde_json::from_value(merged).map_err(|e| AppError::InvalidInput(format!("Invalid config: {}", e)))
+}
+
+// 管理端点：更新负载均衡器配置，校验通过后整体生效（无需重启）
+pub async fn update_load_balancer_config_handler(
+    patch: web::Json<serde_json::Value>,
+    load_balancer: web::Data<Arc<LoadBalancer>>,
+) -> HttpResponse {
+    let config = match merge_config(load_balancer.config(), patch.into_inner()) {
+        Ok(config) => config,
+        Err(e) => return e.error_response(),
+    };
+    match load_balancer.update_config(config) {
+        Ok(previous) => HttpResponse::Ok().json(serde_json::json!({
+            "previous": previous,
+            "config": load_balancer.config(),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        })),
+        Err(e) => AppError::InvalidInput(e.to_string()).error_response(),
+    }
+}
+
 // 在 src/web/handlers.rs 中添加：
 pub async fn cache_stats_handler(
     cache: web::Data<Arc<FactorizationCache>>,
+    precomputer: web::Data<Arc<Precomputer>>,
 ) -> HttpResponse {
     let count = cache.len();
     let is_empty = cache.is_empty();
@@ -129,6 +489,86 @@ pub async fn cache_stats_handler(
         "cache_entries": count,
         "is_empty": is_empty,
         "hit_rate": hit_rate,
+        "eviction": cache.eviction_stats(),
+        "admission": cache.admission_stats(),
+        "snapshot": cache.snapshot_stats(),
+        "wal": cache.wal_stats(),
+        "precompute": precomputer.stats(),
         "timestamp": chrono::Utc::now().to_rfc3339(),
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test as actix_test, App};
+
+    #[test]
+    fn test_parse_window() {
+        assert_eq!(parse_window("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_window("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_window("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_window("1h").unwrap(), Duration::from_secs(3600));
+        assert!(parse_window("0").is_err());
+        assert!(parse_window("m").is_err());
+        assert!(parse_window("5d").is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_update_load_balancer_config() {
+        let load_balancer = Arc::new(LoadBalancer::new(LoadBalancerConfig::default()).unwrap());
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::clone(&load_balancer)))
+                .route("/config", web::get().to(load_balancer_config_handler))
+                .route("/config", web::put().to(update_load_balancer_config_handler)),
+        )
+        .await;
+        let put = |body: serde_json::Value| actix_test::TestRequest::put().uri("/config").set_json(body).to_request();
+
+        // 只修改给出的字段
+        let response = actix_test::call_service(&app, put(serde_json::json!({"check_interval_ms": 1000, "max_query_threads": 12}))).await;
+        assert_eq!(response.status(), 200);
+        let config: LoadBalancerConfig =
+            actix_test::call_and_read_body_json(&app, actix_test::TestRequest::get().uri("/config").to_request()).await;
+        assert_eq!((config.check_interval_ms, config.max_query_threads), (1000, 12));
+        assert_eq!(config.max_compute_threads, LoadBalancerConfig::default().max_compute_threads);
+
+        // 不合法、未知字段或需要重启的修改被拒绝，配置保持不变
+        for body in [
+            serde_json::json!({"max_query_threads": 1}),
+            serde_json::json!({"low_enter": {"active_requests": 50, "p95_latency_ms": 100.0, "cpu_usage": 0.3}}),
+            serde_json::json!({"check_interval": 1000}),
+            serde_json::json!({"max_queue_length": 1}),
+        ] {
+            let response = actix_test::call_service(&app, put(body)).await;
+            assert_eq!(response.status(), 400);
+        }
+        assert_eq!(load_balancer.config().max_query_threads, 12);
+        assert_eq!(load_balancer.get_current_worker_threads(), 6);
+    }
+
+    #[actix_web::test]
+    async fn test_config_update_requires_admin() {
+        let load_balancer = Arc::new(LoadBalancer::new(LoadBalancerConfig::default()).unwrap());
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::clone(&load_balancer)))
+                .configure(crate::web::configure),
+        )
+        .await;
+        let put = |peer: &str| {
+            actix_test::TestRequest::put()
+                .uri("/api/admin/load-balancer/config")
+                .peer_addr(peer.parse().unwrap())
+                .set_json(serde_json::json!({"check_interval_ms": 1000}))
+                .to_request()
+        };
+
+        // 未配置管理令牌时只接受本机请求
+        assert_eq!(actix_test::call_service(&app, put("192.0.2.1:5000")).await.status(), 403);
+        assert_eq!(load_balancer.config().check_interval_ms, LoadBalancerConfig::default().check_interval_ms);
+        assert_eq!(actix_test::call_service(&app, put("127.0.0.1:5000")).await.status(), 200);
+        assert_eq!(load_balancer.config().check_interval_ms, 1000);
+    }
+}
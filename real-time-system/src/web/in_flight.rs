@@ -0,0 +1,91 @@
+// 进行中请求的跟踪中间件
+// 覆盖所有路由：请求进入时向负载均衡器登记，按路由模板（而不是具体路径）分别计数，
+// 返回 5xx 或出错的请求计为失败，未返回就被丢弃的请求计为取消；
+// 已结束请求的延迟记入负载历史（健康检查和被限流、被拒绝的请求除外，它们不反映计算负载）
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::StatusCode;
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::load_balancer::tracking::Outcome;
+use crate::load_balancer::LoadBalancer;
+use crate::web::handlers::HEALTH_PATH;
+
+/// 没有匹配任何路由的请求使用的路由名
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// 请求的路由名，如 `GET /api/factorize/{number}`
+fn route_name(req: &ServiceRequest) -> String {
+    let pattern = req.match_pattern();
+    format!("{} {}", req.method(), pattern.as_deref().unwrap_or(UNMATCHED_ROUTE))
+}
+
+/// 跟踪中间件（`middleware::from_fn`）；应用中没有注册 `LoadBalancer` 时不跟踪
+pub async fn track_in_flight(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let Some(load_balancer) = req.app_data::<web::Data<Arc<LoadBalancer>>>().cloned() else {
+        return next.call(req).await;
+    };
+
+    let guard = load_balancer.track_request(&route_name(&req));
+    let health_check = req.match_pattern().as_deref() == Some(HEALTH_PATH);
+    let start = Instant::now();
+    let result = next.call(req).await;
+    let rejected = result.as_ref().is_ok_and(|response| {
+        matches!(response.status(), StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE)
+    });
+    if !health_check && !rejected {
+        load_balancer.record_latency(start.elapsed());
+    }
+    let outcome = match &result {
+        Ok(response) if !response.status().is_server_error() => Outcome::Completed,
+        _ => Outcome::Failed,
+    };
+    guard.finish(outcome);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_balancer::LoadBalancerConfig;
+    use actix_web::middleware::from_fn;
+    use actix_web::{test as actix_test, App, HttpResponse};
+
+    #[actix_web::test]
+    async fn test_tracks_routes() {
+        let load_balancer = Arc::new(LoadBalancer::new(LoadBalancerConfig::default()).unwrap());
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::clone(&load_balancer)))
+                .wrap(from_fn(track_in_flight))
+                .route("/ok/{id}", web::get().to(HttpResponse::Ok))
+                .route("/fail", web::get().to(HttpResponse::InternalServerError))
+                .route("/busy", web::get().to(HttpResponse::ServiceUnavailable))
+                .route(HEALTH_PATH, web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        for uri in ["/ok/1", "/ok/2", "/fail", "/missing", "/busy", HEALTH_PATH] {
+            actix_test::call_service(&app, actix_test::TestRequest::get().uri(uri).to_request()).await;
+        }
+
+        let routes = load_balancer.route_stats();
+        let names: Vec<_> = routes.iter().map(|r| r.route.as_str()).collect();
+        assert_eq!(names, vec!["GET /api/health", "GET /busy", "GET /fail", "GET /ok/{id}", "GET unmatched"]);
+        assert_eq!((routes[2].completed, routes[2].failed), (0, 1));
+        assert_eq!((routes[3].completed, routes[3].failed), (2, 0));
+        assert_eq!(routes[4].completed, 1);
+        assert_eq!(load_balancer.get_active_requests(), 0);
+
+        // 健康检查和被拒绝（503）的请求不计入延迟
+        let summary = load_balancer.history_summary(std::time::Duration::from_secs(60));
+        assert_eq!(summary.latency_ms.count, 4);
+    }
+}
@@ -1,6 +1,12 @@
+pub mod admin;
+pub mod disconnect;
 pub mod handlers;
+pub mod in_flight;
+pub mod proxy;
+pub mod rate_limit;
 pub mod routes;
 
 // 重新导出
+#[allow(unused_imports)]
 pub use handlers::*;
 pub use routes::*;
\ No newline at end of file
@@ -0,0 +1,159 @@
+// 代理模式下的请求转发
+// 配置了后端实例时，`/api/factorize/{number}` 由这里转发给按策略选出的后端（见 `load_balancer::proxy`），
+// 后端的状态码、响应体和 Retry-After 原样返回，并用 x-backend 头标明实际处理的后端。
+// 连接失败时换下一个后端重试（分解请求是幂等的）；客户端断开时转发被丢弃，后端随之取消计算。
+
+use actix_web::http::header::{self, HeaderName};
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::load_balancer::proxy::BackendPool;
+use crate::models::AppError;
+use crate::web::rate_limit::{API_KEY_HEADER, FORWARDED_FOR_HEADER};
+
+/// 标明实际处理请求的后端
+const BACKEND_HEADER: &str = "x-backend";
+
+/// 从后端响应中转发给客户端的头
+const RELAYED_HEADERS: [HeaderName; 2] = [header::CONTENT_TYPE, header::RETRY_AFTER];
+
+/// 是否处于代理模式（用作路由守卫）
+pub fn is_proxy(ctx: &actix_web::guard::GuardContext) -> bool {
+    ctx.app_data::<web::Data<Arc<BackendPool>>>().is_some()
+}
+
+pub async fn forward_factorize(
+    req: HttpRequest,
+    number: web::Path<String>,
+    pool: web::Data<Arc<BackendPool>>,
+) -> HttpResponse {
+    // 前导零不影响数值，去掉后再哈希，同一个数总是落到同一个后端
+    let key = match number.trim_start_matches('0') {
+        "" => "0",
+        trimmed => trimmed,
+    };
+    let candidates = pool.candidates(key);
+    if candidates.is_empty() {
+        return AppError::Unavailable("no healthy backend".to_string()).error_response();
+    }
+
+    let path = req.uri().path_and_query().map_or(req.path(), |path| path.as_str());
+    let timeout = Duration::from_millis(pool.config().request_timeout_ms);
+    let mut last_error = String::new();
+    for index in candidates {
+        let _active = pool.begin(index);
+        let mut request = pool.client().get(format!("{}{}", pool.url(index), path)).timeout(timeout);
+        if let Some(api_key) = req.headers().get(API_KEY_HEADER).and_then(|value| value.to_str().ok()) {
+            request = request.header(API_KEY_HEADER, api_key);
+        }
+        if let Some(peer) = req.peer_addr() {
+            request = request.header(FORWARDED_FOR_HEADER, peer.ip().to_string());
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            // 超时说明后端仍在计算，换一个后端重算没有意义
+            Err(e) if e.is_timeout() => {
+                log::warn!("Backend {} timed out: {}", pool.url(index), e);
+                return AppError::BadGateway(format!("backend {} timed out", pool.url(index))).error_response();
+            }
+            Err(e) => {
+                log::warn!("Failed to forward to backend {}: {}", pool.url(index), e);
+                pool.record_failure(index, e.to_string());
+                last_error = e.to_string();
+                continue;
+            }
+        };
+        pool.record_success(index);
+
+        let status = StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+        let mut builder = HttpResponse::build(status);
+        for name in RELAYED_HEADERS {
+            if let Some(value) = response.headers().get(name.as_str()).and_then(|value| value.to_str().ok()) {
+                builder.insert_header((name, value.to_string()));
+            }
+        }
+        builder.insert_header((BACKEND_HEADER, pool.url(index).to_string()));
+        return match response.bytes().await {
+            Ok(body) => builder.body(body),
+            Err(e) => AppError::BadGateway(format!("failed to read response from {}: {}", pool.url(index), e)).error_response(),
+        };
+    }
+
+    AppError::BadGateway(format!("all backends failed, last error: {}", last_error)).error_response()
+}
+
+// 管理端点：代理模式下各后端的健康状态和转发计数
+pub async fn backends_handler(pool: Option<web::Data<Arc<BackendPool>>>) -> HttpResponse {
+    let Some(pool) = pool else {
+        return AppError::NotFound("proxy mode is not enabled".to_string()).error_response();
+    };
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "strategy": pool.config().strategy,
+        "backends": pool.statuses(),
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_balancer::proxy::{BalanceStrategy, ProxyConfig};
+    use actix_web::{test as actix_test, App, HttpServer};
+
+    /// 在随机端口启动一个模拟后端，健康检查返回 health，分解请求返回后端名称；返回其地址
+    fn spawn_backend(name: &'static str, health: StatusCode) -> String {
+        let server = HttpServer::new(move || {
+            App::new()
+                .route("/api/health", web::get().to(move || async move { HttpResponse::new(health) }))
+                .route("/api/factorize/{number}", web::get().to(move || async move { HttpResponse::Ok().body(name) }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let address = format!("http://{}", server.addrs()[0]);
+        actix_web::rt::spawn(server.run());
+        address
+    }
+
+    #[actix_web::test]
+    async fn test_forwards_and_ejects() {
+        let backends = vec![
+            spawn_backend("a", StatusCode::OK),
+            spawn_backend("b", StatusCode::TOO_MANY_REQUESTS),
+            "http://127.0.0.1:1".to_string(),
+        ];
+        let pool = Arc::new(BackendPool::new(ProxyConfig {
+            backends: backends.clone(),
+            strategy: BalanceStrategy::ConsistentHash,
+            max_attempts: 3,
+            ..ProxyConfig::default()
+        }));
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::clone(&pool)))
+                .route("/api/factorize/{number}", web::get().to(forward_factorize)),
+        )
+        .await;
+
+        // 同一个数（忽略前导零）总是由同一个后端处理；不可用的后端连接失败后被跳过
+        let mut served = Vec::new();
+        for uri in ["/api/factorize/1234567", "/api/factorize/001234567", "/api/factorize/1234567"] {
+            let response = actix_test::call_service(&app, actix_test::TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(response.status(), 200);
+            served.push(response.headers().get(BACKEND_HEADER).unwrap().to_str().unwrap().to_string());
+        }
+        assert!(served.iter().all(|backend| *backend == served[0] && *backend != backends[2]));
+
+        // 健康检查剔除不可用的后端，被限流的后端仍然存活
+        pool.check_health().await;
+        pool.check_health().await;
+        let healthy: Vec<bool> = pool.statuses().iter().map(|status| status.healthy).collect();
+        assert_eq!(healthy, vec![true, true, false]);
+        let spread: std::collections::HashSet<usize> = (0..50).map(|n| pool.candidates(&n.to_string())[0]).collect();
+        assert_eq!(spread.len(), 2);
+    }
+}
@@ -0,0 +1,578 @@
+// 按客户端限流（令牌桶）
+// 每个客户端（X-API-Key 在配置的密钥列表中时按密钥，否则按客户端 IP）一个令牌桶，请求按估算的分解代价扣除令牌：
+// 缓存命中和其他端点只扣 1 个，分解请求和提交的任务按估算运算量线性计费（代理模式下由后端计费），
+// 代价超过桶容量的请求在桶满时放行并记为欠额，之后按欠额等待相应的时间，
+// 避免单个客户端用大量难分解的数把整个服务推入高负载。
+// 健康检查不限流；只有来自配置的可信代理的请求才按 X-Forwarded-For 识别客户端 IP。
+
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, ResponseError};
+use dashmap::DashMap;
+use num_bigint::BigUint;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::cache::FactorizationCache;
+use crate::factorization::{self, FactorizerRegistry};
+use crate::load_balancer::proxy::BackendPool;
+use crate::models::{AppError, FactorizeQuery, JobRequest};
+use crate::web::handlers::{BIG_ALGORITHM, HEALTH_PATH, JOB_ALGORITHM, MAX_DIGITS};
+
+/// 客户端密钥请求头
+pub(crate) const API_KEY_HEADER: &str = "x-api-key";
+
+/// 代理转发时标明客户端 IP 的请求头
+pub(crate) const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// 每个请求的基础代价（缓存命中、查询类端点）
+const BASE_COST: f64 = 1.0;
+
+/// 每个令牌对应的估算运算量（各算法的估算约为每秒 2 亿～10 亿次，即约 10～50 毫秒的计算）
+const OPERATIONS_PER_TOKEN: f64 = 20_000_000.0;
+
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    /// 桶容量（允许的突发令牌数）
+    pub capacity: f64,
+    /// 每秒补充的令牌数
+    pub refill_per_sec: f64,
+    /// 记录的客户端超过该数量时，清理已回满的令牌桶（每补充一个令牌的时间内最多清理一次）
+    pub max_tracked_clients: usize,
+    /// 允许的 API 密钥；不在列表中的密钥被忽略，按客户端 IP 限流
+    pub api_keys: HashSet<String>,
+    /// 可信代理的地址；只有来自这些地址的请求才按 X-Forwarded-For 识别客户端
+    pub trusted_proxies: HashSet<IpAddr>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 100.0,
+            refill_per_sec: 10.0,
+            max_tracked_clients: 10_000,
+            api_keys: HashSet::new(),
+            trusted_proxies: HashSet::new(),
+        }
+    }
+}
+
+/// 单个客户端的令牌桶及累计用量
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    requests: u64,
+    rejected: u64,
+    consumed: f64,
+}
+
+/// 一次扣费的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decision {
+    pub allowed: bool,
+    /// 本次请求的代价
+    pub cost: f64,
+    /// 扣费后剩余的令牌（有欠额时为 0）
+    pub remaining: f64,
+    /// 令牌桶回满所需秒数（含欠额）
+    pub reset_secs: u64,
+    /// 被拒绝时，攒够本次代价（最多一个桶容量）所需秒数
+    pub retry_after_secs: u64,
+}
+
+/// 客户端当前的消耗情况（管理端点）
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientUsage {
+    pub client: String,
+    pub tokens_remaining: f64,
+    pub tokens_consumed: f64,
+    pub requests: u64,
+    pub rejected: u64,
+    pub idle_secs: u64,
+}
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: DashMap<String, Bucket>,
+    created: Instant,
+    /// 上次清理的时间（创建后的毫秒数）
+    last_prune_ms: AtomicU64,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: DashMap::new(),
+            created: Instant::now(),
+            last_prune_ms: AtomicU64::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// 为客户端扣除 cost 个令牌；令牌不足时不扣费并拒绝，代价超过容量时在桶满时放行并记为欠额
+    pub fn try_acquire(&self, client: &str, cost: f64) -> Decision {
+        self.try_acquire_at(client, cost, Instant::now())
+    }
+
+    fn try_acquire_at(&self, client: &str, cost: f64, now: Instant) -> Decision {
+        if self.buckets.len() > self.config.max_tracked_clients && self.prune_due(now) {
+            self.prune(now);
+        }
+
+        let cost = cost.max(0.0);
+        // 代价超过容量的请求永远攒不够令牌，桶满即放行
+        let required = cost.min(self.config.capacity);
+        let mut bucket = self.buckets.entry(client.to_string()).or_insert_with(|| Bucket {
+            tokens: self.config.capacity,
+            updated: now,
+            requests: 0,
+            rejected: 0,
+            consumed: 0.0,
+        });
+        bucket.tokens = self.refilled(&bucket, now);
+        bucket.updated = now;
+        bucket.requests += 1;
+
+        let allowed = bucket.tokens >= required;
+        if allowed {
+            bucket.tokens -= cost;
+            bucket.consumed += cost;
+        } else {
+            bucket.rejected += 1;
+        }
+
+        Decision {
+            allowed,
+            cost,
+            remaining: bucket.tokens.max(0.0),
+            reset_secs: self.secs_to_refill(self.config.capacity - bucket.tokens),
+            retry_after_secs: if allowed { 0 } else { self.secs_to_refill(required - bucket.tokens).max(1) },
+        }
+    }
+
+    /// 各客户端当前的消耗情况（按累计消耗从高到低）
+    pub fn usage(&self) -> Vec<ClientUsage> {
+        let now = Instant::now();
+        let mut usage: Vec<_> = self
+            .buckets
+            .iter()
+            .map(|entry| ClientUsage {
+                client: display_key(entry.key()),
+                tokens_remaining: self.refilled(entry.value(), now),
+                tokens_consumed: entry.consumed,
+                requests: entry.requests,
+                rejected: entry.rejected,
+                idle_secs: now.saturating_duration_since(entry.updated).as_secs(),
+            })
+            .collect();
+        usage.sort_by(|a, b| b.tokens_consumed.total_cmp(&a.tokens_consumed));
+        usage
+    }
+
+    /// 按经过的时间补充令牌后的余额
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        (bucket.tokens + elapsed * self.config.refill_per_sec).min(self.config.capacity)
+    }
+
+    fn secs_to_refill(&self, tokens: f64) -> u64 {
+        if tokens <= 0.0 || self.config.refill_per_sec <= 0.0 {
+            return 0;
+        }
+        (tokens / self.config.refill_per_sec).ceil() as u64
+    }
+
+    /// 距上次清理是否已经过补充一个令牌的时间；大量客户端涌入时没有可清理的桶，
+    /// 不限制的话每个请求都要遍历所有令牌桶
+    fn prune_due(&self, now: Instant) -> bool {
+        if self.config.refill_per_sec <= 0.0 {
+            return false;
+        }
+        let interval_ms = (1000.0 / self.config.refill_per_sec).ceil() as u64;
+        let now_ms = now.saturating_duration_since(self.created).as_millis() as u64;
+        let last = self.last_prune_ms.load(Ordering::Relaxed);
+        now_ms.saturating_sub(last) >= interval_ms
+            && self.last_prune_ms.compare_exchange(last, now_ms, Ordering::Relaxed, Ordering::Relaxed).is_ok()
+    }
+
+    /// 删除已回满的令牌桶（与新建的桶没有区别，只丢失累计用量）
+    fn prune(&self, now: Instant) {
+        let before = self.buckets.len();
+        self.buckets.retain(|_, bucket| self.refilled(bucket, now) < self.config.capacity);
+        log::debug!("Pruned {} idle rate limit buckets", before - self.buckets.len());
+    }
+}
+
+/// 管理端点中不显示完整的 API 密钥
+fn display_key(client: &str) -> String {
+    match client.strip_prefix("key:") {
+        Some(key) if key.chars().count() > 4 => format!("key:{}…", key.chars().take(4).collect::<String>()),
+        _ => client.to_string(),
+    }
+}
+
+/// 客户端标识：优先使用配置中允许的 API 密钥，否则使用客户端 IP
+fn client_key(config: &RateLimitConfig, req: &ServiceRequest) -> String {
+    let api_key = req
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|key| config.api_keys.contains(*key));
+    match (api_key, client_ip(config, req)) {
+        (Some(key), _) => format!("key:{}", key),
+        (None, Some(ip)) => format!("ip:{}", ip),
+        (None, None) => "unknown".to_string(),
+    }
+}
+
+/// 客户端 IP：对端是可信代理时取 X-Forwarded-For 中最后一个地址（由该代理添加），
+/// 否则使用对端 IP（其他来源的转发头可以伪造）
+fn client_ip(config: &RateLimitConfig, req: &ServiceRequest) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    if !config.trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+    let forwarded = req
+        .headers()
+        .get(FORWARDED_FOR_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').next())
+        .and_then(|ip| ip.trim().parse().ok());
+    Some(forwarded.unwrap_or(peer))
+}
+
+/// 请求的代价：分解请求和提交的任务按估算运算量计费，已缓存的数和其他端点只收基础代价；
+/// 代理模式下分解请求由后端计算和计费（前端的缓存中没有结果），前端只收基础代价
+async fn request_cost(req: &mut ServiceRequest) -> f64 {
+    if req.method() == actix_web::http::Method::POST && req.path() == "/api/jobs" {
+        return job_cost(req).await;
+    }
+    let Some(raw) = req.path().strip_prefix("/api/factorize/") else {
+        return BASE_COST;
+    };
+    if req.app_data::<web::Data<Arc<BackendPool>>>().is_some() {
+        return BASE_COST;
+    }
+    // 客户端指定算法时按该算法计费（例如对大数指定试除）
+    let requested = web::Query::<FactorizeQuery>::from_query(req.query_string())
+        .ok()
+        .and_then(|query| query.algorithm.clone());
+    estimate_operations(req, raw, requested.as_deref(), BIG_ALGORITHM).map_or(BASE_COST, cost_from_operations)
+}
+
+/// 提交任务的代价：按请求体中的数和算法估算（请求体读出后放回，处理函数照常解析）
+async fn job_cost(req: &mut ServiceRequest) -> f64 {
+    let body = match req.extract::<web::Bytes>().await {
+        Ok(body) => body,
+        // 读取失败时处理函数同样会失败并返回 4xx
+        Err(_) => return BASE_COST,
+    };
+    req.set_payload(Payload::from(body.clone()));
+    let Ok(job) = serde_json::from_slice::<JobRequest>(&body) else {
+        return BASE_COST;
+    };
+    let algorithm = job.algorithm.as_deref().unwrap_or(JOB_ALGORITHM);
+    estimate_operations(req, job.number.trim(), Some(algorithm), algorithm).map_or(BASE_COST, cost_from_operations)
+}
+
+/// 分解 raw 的估算运算量：u128 范围内按 algorithm（未指定时自动选择），更大的数按 big_algorithm；
+/// 已缓存的数和无效输入（由处理函数返回 400）返回 None
+fn estimate_operations(req: &ServiceRequest, raw: &str, algorithm: Option<&str>, big_algorithm: &str) -> Option<f64> {
+    let cache = req.app_data::<web::Data<Arc<FactorizationCache>>>()?;
+    let registry = req.app_data::<web::Data<Arc<FactorizerRegistry>>>()?;
+    if raw.is_empty() || raw.len() > MAX_DIGITS || !raw.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    match raw.parse::<u128>() {
+        Ok(n) if cache.contains(n) => None,
+        Ok(n) => {
+            let factorizer = match algorithm {
+                Some(name) => registry.get(name),
+                None => registry.select_best(n),
+            };
+            factorizer.map(|f| f.estimate_cost(n))
+        }
+        Err(_) => match raw.parse::<BigUint>() {
+            Ok(n) if cache.contains_big(&n) => None,
+            Ok(n) => factorization::estimate_big_cost_by(big_algorithm, &n),
+            Err(_) => None,
+        },
+    }
+}
+
+/// 代价与估算运算量成正比
+fn cost_from_operations(operations: f64) -> f64 {
+    BASE_COST + operations.max(0.0) / OPERATIONS_PER_TOKEN
+}
+
+fn set_headers(headers: &mut HeaderMap, limiter: &RateLimiter, decision: &Decision) {
+    let values = [
+        ("x-ratelimit-limit", format!("{}", limiter.config().capacity.floor())),
+        ("x-ratelimit-remaining", format!("{}", decision.remaining.floor())),
+        ("x-ratelimit-reset", decision.reset_secs.to_string()),
+        ("x-ratelimit-cost", format!("{:.2}", decision.cost)),
+    ];
+    for (name, value) in values {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(HeaderName::from_static(name), value);
+        }
+    }
+}
+
+/// 限流中间件（`middleware::from_fn`）；应用中没有注册 `RateLimiter` 时不限流，健康检查始终不限流
+pub async fn rate_limit(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let limiter = req.app_data::<web::Data<Arc<RateLimiter>>>().cloned();
+    let Some(limiter) = limiter.filter(|_| req.path() != HEALTH_PATH) else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+
+    let client = client_key(limiter.config(), &req);
+    let cost = request_cost(&mut req).await;
+    let decision = limiter.try_acquire(&client, cost);
+    if !decision.allowed {
+        log::warn!("Rate limit exceeded for {} on {} (cost {:.2})", display_key(&client), req.path(), decision.cost);
+        let mut response = AppError::RateLimited {
+            retry_after_secs: decision.retry_after_secs,
+        }
+        .error_response();
+        set_headers(response.headers_mut(), &limiter, &decision);
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+
+    let mut response = next.call(req).await?;
+    set_headers(response.headers_mut(), &limiter, &decision);
+    Ok(response.map_into_left_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::middleware::from_fn;
+    use actix_web::{test as actix_test, App, HttpResponse};
+    use std::time::Duration;
+
+    fn limiter(capacity: f64, refill_per_sec: f64) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            capacity,
+            refill_per_sec,
+            max_tracked_clients: 2,
+            api_keys: HashSet::from(["client-a".to_string()]),
+            trusted_proxies: HashSet::from(["10.0.0.9".parse().unwrap()]),
+        })
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let limiter = limiter(10.0, 2.0);
+        let start = Instant::now();
+
+        let first = limiter.try_acquire_at("ip:1", 8.0, start);
+        assert!(first.allowed);
+        assert_eq!(first.remaining, 2.0);
+        assert_eq!(first.reset_secs, 4);
+
+        // 令牌不足时拒绝且不扣费
+        let rejected = limiter.try_acquire_at("ip:1", 5.0, start);
+        assert!(!rejected.allowed);
+        assert_eq!(rejected.remaining, 2.0);
+        assert_eq!(rejected.retry_after_secs, 2);
+
+        // 其他客户端不受影响；超过容量的代价在桶满时放行，欠额按代价全额计
+        let expensive = limiter.try_acquire_at("ip:2", 50.0, start);
+        assert!(expensive.allowed);
+        assert_eq!((expensive.remaining, expensive.reset_secs), (0.0, 25));
+        let in_debt = limiter.try_acquire_at("ip:2", 1.0, start);
+        assert_eq!((in_debt.allowed, in_debt.retry_after_secs), (false, 21));
+
+        // 1.5 秒后补充 3 个令牌
+        assert!(limiter.try_acquire_at("ip:1", 5.0, start + Duration::from_millis(1500)).allowed);
+
+        let usage = limiter.usage();
+        assert_eq!(usage[0].client, "ip:2");
+        assert_eq!(usage[0].tokens_consumed, 50.0);
+        assert_eq!(usage[1].tokens_consumed, 13.0);
+        assert_eq!(usage[1].requests, 3);
+        assert_eq!(usage[1].rejected, 1);
+    }
+
+    #[test]
+    fn test_prune_full_buckets() {
+        let limiter = limiter(10.0, 1.0);
+        let start = Instant::now();
+        for client in ["ip:1", "ip:2", "ip:3"] {
+            limiter.try_acquire_at(client, 5.0, start);
+        }
+        // 10 秒后所有桶都已回满，超过上限时被清理
+        limiter.try_acquire_at("key:abcdefgh", 1.0, start + Duration::from_secs(10));
+        let usage = limiter.usage();
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].client, "key:abcd…");
+
+        // 每补充一个令牌的时间（1 秒）内最多清理一次
+        let later = start + Duration::from_secs(10);
+        for client in ["ip:5", "ip:6"] {
+            limiter.try_acquire_at(client, 0.0, later);
+        }
+        limiter.try_acquire_at("ip:7", 0.0, later + Duration::from_millis(500));
+        assert_eq!(limiter.usage().len(), 4);
+        limiter.try_acquire_at("ip:8", 0.0, later + Duration::from_millis(1500));
+        assert_eq!(limiter.usage().len(), 1);
+    }
+
+    #[test]
+    fn test_cost_from_operations() {
+        assert_eq!(cost_from_operations(0.0), BASE_COST);
+        // 代价与运算量成正比
+        assert_eq!(cost_from_operations(OPERATIONS_PER_TOKEN * 10.0), BASE_COST + 10.0);
+        assert_eq!(cost_from_operations(OPERATIONS_PER_TOKEN * 1000.0), BASE_COST + 1000.0);
+    }
+
+    #[actix_web::test]
+    async fn test_middleware_headers_and_rejection() {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(limiter(2.0, 0.001))))
+                .service(
+                    web::scope("/api")
+                        .wrap(from_fn(rate_limit))
+                        .route("/ping", web::get().to(HttpResponse::Ok)),
+                ),
+        )
+        .await;
+
+        let request = || actix_test::TestRequest::get().uri("/api/ping").insert_header((API_KEY_HEADER, "client-a"));
+        let response = actix_test::call_service(&app, request().to_request()).await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers().get("x-ratelimit-limit").unwrap(), "2");
+        assert_eq!(response.headers().get("x-ratelimit-remaining").unwrap(), "1");
+
+        actix_test::call_service(&app, request().to_request()).await;
+        let response = actix_test::call_service(&app, request().to_request()).await;
+        assert_eq!(response.status(), 429);
+        assert!(response.headers().contains_key("retry-after"));
+        assert_eq!(response.headers().get("x-ratelimit-remaining").unwrap(), "0");
+
+        // 不在列表中的密钥被忽略，同一 IP 的请求共用一个桶
+        let unlisted = |key: &str| {
+            actix_test::TestRequest::get()
+                .uri("/api/ping")
+                .insert_header((API_KEY_HEADER, key))
+                .peer_addr("10.0.0.1:4000".parse().unwrap())
+                .to_request()
+        };
+        let response = actix_test::call_service(&app, unlisted("forged-1")).await;
+        assert_eq!(response.headers().get("x-ratelimit-remaining").unwrap(), "1");
+        let response = actix_test::call_service(&app, unlisted("forged-2")).await;
+        assert_eq!(response.headers().get("x-ratelimit-remaining").unwrap(), "0");
+    }
+
+    #[actix_web::test]
+    async fn test_health_exempt_and_forwarded_for() {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(limiter(1.0, 0.001))))
+                .service(
+                    web::scope("/api")
+                        .wrap(from_fn(rate_limit))
+                        .route("/ping", web::get().to(HttpResponse::Ok))
+                        .route("/health", web::get().to(HttpResponse::Ok)),
+                ),
+        )
+        .await;
+        let request = |uri: &str, peer: &str, forwarded_for: &str| {
+            actix_test::TestRequest::get()
+                .uri(uri)
+                .peer_addr(peer.parse().unwrap())
+                .insert_header((FORWARDED_FOR_HEADER, forwarded_for))
+                .to_request()
+        };
+
+        // 健康检查不扣令牌
+        for _ in 0..3 {
+            let response = actix_test::call_service(&app, request(HEALTH_PATH, "10.0.0.8:4000", "")).await;
+            assert_eq!(response.status(), 200);
+            assert!(!response.headers().contains_key("x-ratelimit-remaining"));
+        }
+
+        // 可信代理转发的请求按 X-Forwarded-For 中的客户端分别计费
+        for client in ["192.0.2.1", "192.0.2.2"] {
+            let response = actix_test::call_service(&app, request("/api/ping", "10.0.0.9:4000", client)).await;
+            assert_eq!(response.status(), 200);
+        }
+
+        // 其他来源的转发头被忽略，按对端 IP 计费
+        let first = actix_test::call_service(&app, request("/api/ping", "10.0.0.8:4000", "192.0.2.3")).await;
+        assert_eq!(first.status(), 200);
+        let second = actix_test::call_service(&app, request("/api/ping", "10.0.0.8:4000", "192.0.2.4")).await;
+        assert_eq!(second.status(), 429);
+    }
+
+    #[actix_web::test]
+    async fn test_proxy_front_charges_base_cost() {
+        let pool = BackendPool::new(crate::load_balancer::proxy::ProxyConfig {
+            backends: vec!["http://127.0.0.1:1".to_string()],
+            ..Default::default()
+        });
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(limiter(1000.0, 1.0))))
+                .app_data(web::Data::new(Arc::new(FactorizationCache::new())))
+                .app_data(web::Data::new(Arc::new(FactorizerRegistry::with_defaults())))
+                .app_data(web::Data::new(Arc::new(pool)))
+                .service(
+                    web::scope("/api")
+                        .wrap(from_fn(rate_limit))
+                        .route("/factorize/{number}", web::get().to(HttpResponse::Ok)),
+                ),
+        )
+        .await;
+
+        // 后端按实际计算计费，前端只收基础代价
+        let uri = format!("/api/factorize/{}", "7".repeat(60));
+        let response = actix_test::call_service(&app, actix_test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(response.headers().get("x-ratelimit-cost").unwrap(), "1.00");
+    }
+
+    #[actix_web::test]
+    async fn test_job_cost_keeps_body() {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(limiter(1000.0, 1.0))))
+                .app_data(web::Data::new(Arc::new(FactorizationCache::new())))
+                .app_data(web::Data::new(Arc::new(FactorizerRegistry::with_defaults())))
+                .service(
+                    web::scope("/api")
+                        .wrap(from_fn(rate_limit))
+                        .route("/jobs", web::post().to(|job: web::Json<JobRequest>| async move { job.number.clone() })),
+                ),
+        )
+        .await;
+
+        // 60 位的数按 SIQS 的估算运算量计费，请求体仍能被处理函数读取
+        let number = "1".repeat(60);
+        let request = actix_test::TestRequest::post()
+            .uri("/api/jobs")
+            .set_json(serde_json::json!({"number": number}))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        let cost: f64 = response.headers().get("x-ratelimit-cost").unwrap().to_str().unwrap().parse().unwrap();
+        assert!(cost > 10.0, "cost {}", cost);
+        assert_eq!(actix_test::read_body(response).await, number.as_bytes());
+    }
+}
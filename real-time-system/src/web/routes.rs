@@ -1,13 +1,32 @@
 // src/web/routes.rs
-use actix_web::web;
-use crate::web::handlers;
+use actix_web::middleware::from_fn;
+use actix_web::{guard, web};
+use crate::web::{admin, handlers, proxy, rate_limit};
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api")
+            // 按客户端限流，分解请求按估算代价计费
+            .wrap(from_fn(rate_limit::rate_limit))
+            // 代理模式下分解请求转发给后端实例，否则由本实例计算
+            .route("/factorize/{number}", web::get().guard(guard::fn_guard(proxy::is_proxy)).to(proxy::forward_factorize))
             .route("/factorize/{number}", web::get().to(handlers::factorize_handler))
+            .route("/jobs", web::post().to(handlers::submit_job_handler))
+            .route("/jobs/{id}", web::get().to(handlers::job_status_handler))
+            .route("/jobs/{id}", web::delete().to(handlers::cancel_job_handler))
+            .route("/is-prime/{number}", web::get().to(handlers::is_prime_handler))
             .route("/stats", web::get().to(handlers::cache_stats_handler))  // 使用正确的函数名
             .route("/load-stats", web::get().to(handlers::load_stats_handler))
+            .route("/load-history", web::get().to(handlers::load_history_handler))
             .route("/health", web::get().to(handlers::system_health_handler))
+            // 管理端点（客户端用量、后端状态、运行时修改配置）需要管理令牌或本机访问
+            .service(
+                web::scope("/admin")
+                    .wrap(from_fn(admin::require_admin))
+                    .route("/rate-limits", web::get().to(handlers::rate_limits_handler))
+                    .route("/backends", web::get().to(proxy::backends_handler))
+                    .route("/load-balancer/config", web::get().to(handlers::load_balancer_config_handler))
+                    .route("/load-balancer/config", web::put().to(handlers::update_load_balancer_config_handler)),
+            )
     );
 }
\ No newline at end of file